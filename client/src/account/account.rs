@@ -0,0 +1,112 @@
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use crate::{profile, window};
+use raylib::math::Rectangle;
+use std::sync::RwLockWriteGuard;
+
+pub const HEADING_FONT_SIZE: f32 = 32.;
+pub const TEXT_FONT_SIZE: f32 = 18.;
+/// Top of the status line, below which the fields are laid out
+pub const CONTENT_TOP: f32 = 160.;
+pub const PANEL_WIDTH: f32 = 360.;
+pub const FIELD_HEIGHT: f32 = 40.;
+/// Between a label and its field
+pub const LABEL_GAP: f32 = 8.;
+/// Mirrors the server's limits
+pub const MAX_USERNAME_LENGTH: usize = 24;
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AccountField {
+    Username,
+    Password,
+}
+
+impl AccountField {
+    pub fn next(&self) -> AccountField {
+        match self {
+            AccountField::Username => AccountField::Password,
+            AccountField::Password => AccountField::Username,
+        }
+    }
+}
+
+/// Show the Account stage, prefilling the username of the last login.
+pub fn enter() {
+    let mut username: RwLockWriteGuard<String> = STATE.stage.account.username.write().unwrap();
+    if username.is_empty()
+        && let Some(last_username) = profile::username()
+    {
+        *username = last_username;
+    }
+    STATE.stage.account.password.write().unwrap().clear();
+    stage::register_next(StageType::Account);
+}
+
+/// Log in with the credentials entered, unless a request is already pending.
+pub fn log_in_from_fields() {
+    if let Some((username, password)) = credentials() {
+        super::log_in(username, password);
+    }
+}
+
+/// Create an account with the credentials entered, unless a request is already pending.
+pub fn create_account_from_fields() {
+    if let Some((username, password)) = credentials() {
+        super::create_account(username, password);
+    }
+}
+
+fn credentials() -> Option<(String, String)> {
+    if is_pending() {
+        return None;
+    }
+    let username: String = STATE.stage.account.username.read().unwrap().clone();
+    let password: String = STATE.stage.account.password.read().unwrap().clone();
+    if username.is_empty() || password.is_empty() {
+        window::show_error("Missing credentials", "Enter a username and password.");
+        return None;
+    }
+    Some((username, password))
+}
+
+pub fn clear_password() {
+    STATE.stage.account.password.write().unwrap().clear();
+}
+
+pub fn set_pending(pending: bool) {
+    *STATE.stage.account.pending.write().unwrap() = pending;
+}
+
+pub fn is_pending() -> bool {
+    *STATE.stage.account.pending.read().unwrap()
+}
+
+/// Left edge of the fields and buttons, which are centered horizontally
+pub fn panel_x(screen_width: i32) -> f32 {
+    screen_width as f32 / 2. - PANEL_WIDTH / 2.
+}
+
+pub fn field_rectangle(screen_width: i32, field: AccountField) -> Rectangle {
+    let index: f32 = match field {
+        AccountField::Username => 0.,
+        AccountField::Password => 1.,
+    };
+    Rectangle {
+        x: panel_x(screen_width),
+        y: field_top(index) + TEXT_FONT_SIZE + LABEL_GAP,
+        width: PANEL_WIDTH,
+        height: FIELD_HEIGHT,
+    }
+}
+
+/// Top of the label of the field at the index
+pub fn field_top(index: f32) -> f32 {
+    CONTENT_TOP + TEXT_FONT_SIZE * 2. + index * (TEXT_FONT_SIZE + LABEL_GAP + FIELD_HEIGHT + TEXT_FONT_SIZE)
+}
+
+/// Top of the row holding the log in and create account buttons
+pub fn buttons_row_y() -> f32 {
+    field_top(2.)
+}
@@ -0,0 +1,92 @@
+use crate::account::{self, AccountField, CONTENT_TOP, HEADING_FONT_SIZE, LABEL_GAP, TEXT_FONT_SIZE};
+use crate::button::RectangularButton;
+use crate::color::{MAP_BACKGROUND_COLOR, TEXT_COLOR, WHITE, WINDOW_BACKGROUND_COLOR, WINDOW_BORDER_COLOR};
+use crate::font::DEFAULT_FONT_SPACING;
+use crate::profile;
+use crate::state::STATE;
+use crate::title;
+use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+use raylib::math::{Rectangle, Vector2};
+use std::sync::RwLockReadGuard;
+
+const FIELD_INTERNAL_MARGIN: f32 = 14.;
+
+pub fn draw(rl_draw: &mut RaylibDrawHandle) {
+    rl_draw.clear_background(MAP_BACKGROUND_COLOR);
+
+    draw_status(rl_draw);
+    draw_field(rl_draw, AccountField::Username, "Username");
+    draw_field(rl_draw, AccountField::Password, "Password");
+    for button_l in STATE.stage.account.buttons() {
+        let button: RwLockReadGuard<RectangularButton> = button_l.read().unwrap();
+        title::draw_button(rl_draw, &button);
+    }
+}
+
+fn draw_status(rl_draw: &mut RaylibDrawHandle) {
+    let x: f32 = account::panel_x(rl_draw.get_screen_width());
+    draw_text(
+        rl_draw,
+        "Account",
+        Vector2 {
+            x,
+            y: CONTENT_TOP - HEADING_FONT_SIZE - 16.,
+        },
+        HEADING_FONT_SIZE,
+    );
+
+    let status: String = if account::is_pending() {
+        String::from("Logging in...")
+    } else {
+        match (profile::username(), profile::account_token()) {
+            (Some(username), Some(_)) => format!("Logged in as {}", username),
+            _ => String::from("Not logged in"),
+        }
+    };
+    draw_text(rl_draw, &status, Vector2 { x, y: CONTENT_TOP }, TEXT_FONT_SIZE);
+}
+
+fn draw_field(rl_draw: &mut RaylibDrawHandle, field: AccountField, label: &str) {
+    let rectangle: Rectangle = account::field_rectangle(rl_draw.get_screen_width(), field);
+    draw_text(
+        rl_draw,
+        label,
+        Vector2 {
+            x: rectangle.x,
+            y: rectangle.y - TEXT_FONT_SIZE - LABEL_GAP,
+        },
+        TEXT_FONT_SIZE,
+    );
+
+    let focused: bool = *STATE.stage.account.focus.read().unwrap() == field;
+    rl_draw.draw_rectangle_rec(rectangle, WINDOW_BACKGROUND_COLOR);
+    rl_draw.draw_rectangle_lines_ex(rectangle, 1., if focused { WHITE } else { WINDOW_BORDER_COLOR });
+
+    let mut text: String = match field {
+        AccountField::Username => STATE.stage.account.username.read().unwrap().clone(),
+        AccountField::Password => "*".repeat(STATE.stage.account.password.read().unwrap().chars().count()),
+    };
+    if focused {
+        text.push('_');
+    }
+    draw_text(
+        rl_draw,
+        &text,
+        Vector2 {
+            x: rectangle.x + FIELD_INTERNAL_MARGIN,
+            y: rectangle.y + (rectangle.height - TEXT_FONT_SIZE) / 2.,
+        },
+        TEXT_FONT_SIZE,
+    );
+}
+
+fn draw_text(rl_draw: &mut RaylibDrawHandle, text: &str, position: Vector2, font_size: f32) {
+    rl_draw.draw_text_ex(
+        rl_draw.get_font_default(),
+        text,
+        position,
+        font_size,
+        DEFAULT_FONT_SPACING,
+        TEXT_COLOR,
+    );
+}
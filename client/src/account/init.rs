@@ -0,0 +1,79 @@
+use crate::account::{self, FIELD_HEIGHT, PANEL_WIDTH};
+use crate::button::RectangularButton;
+use crate::input::ClickResult;
+use crate::map::RenderCoord;
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use crate::title::SCREEN_MARGIN;
+use raylib::RaylibHandle;
+use raylib::math::{Rectangle, Vector2};
+
+const BUTTON_DIMENSIONS: Vector2 = Vector2 { x: 120., y: 40. };
+const BUTTON_GAP: f32 = 16.;
+
+pub fn init_account(rl: &mut RaylibHandle) {
+    *STATE.stage.account.back_button.write().unwrap() = create_back_button();
+    *STATE.stage.account.log_in_button.write().unwrap() = create_log_in_button(rl);
+    *STATE.stage.account.create_button.write().unwrap() = create_create_button(rl);
+}
+
+fn create_back_button() -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Back",
+        Rectangle {
+            x: SCREEN_MARGIN,
+            y: SCREEN_MARGIN,
+            width: BUTTON_DIMENSIONS.x,
+            height: BUTTON_DIMENSIONS.y,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        stage::register_next(StageType::Title);
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_log_in_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Log in",
+        Rectangle {
+            x: account::panel_x(rl.get_screen_width()),
+            y: account::buttons_row_y(),
+            width: (PANEL_WIDTH - BUTTON_GAP) / 2.,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        account::log_in_from_fields();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_create_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Create account",
+        Rectangle {
+            x: account::panel_x(rl.get_screen_width()) + (PANEL_WIDTH + BUTTON_GAP) / 2.,
+            y: account::buttons_row_y(),
+            width: (PANEL_WIDTH - BUTTON_GAP) / 2.,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        account::create_account_from_fields();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
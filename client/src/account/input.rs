@@ -0,0 +1,86 @@
+use crate::account::{self, AccountField, MAX_PASSWORD_LENGTH, MAX_USERNAME_LENGTH};
+use crate::button::RectangularButton;
+use crate::input::{ClickHandler, ClickResult, HoverHandler, HoverResult, KeyPressResult};
+use crate::map::RenderCoord;
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use raylib::RaylibHandle;
+use raylib::consts::KeyboardKey;
+use std::sync::{RwLock, RwLockWriteGuard};
+
+pub fn click(rl: &mut RaylibHandle, mouse_position: RenderCoord) -> ClickResult {
+    for button_l in STATE.stage.account.buttons() {
+        let mut button: RwLockWriteGuard<RectangularButton> = button_l.write().unwrap();
+        if let ClickResult::Consume = button.click(rl, mouse_position) {
+            return ClickResult::Consume;
+        }
+    }
+
+    for field in [AccountField::Username, AccountField::Password] {
+        if account::field_rectangle(rl.get_screen_width(), field).check_collision_point_rec(mouse_position) {
+            *STATE.stage.account.focus.write().unwrap() = field;
+            return ClickResult::Consume;
+        }
+    }
+    ClickResult::Pass
+}
+
+pub fn hover(rl: &mut RaylibHandle, mouse_position: RenderCoord) -> HoverResult {
+    let mut result: HoverResult = HoverResult::Pass;
+    for button_l in STATE.stage.account.buttons() {
+        let mut button: RwLockWriteGuard<RectangularButton> = button_l.write().unwrap();
+        // Every button is hovered so that each can clear its highlight
+        if let HoverResult::Consume = button.hover(rl, mouse_position) {
+            result = HoverResult::Consume;
+        }
+    }
+    result
+}
+
+/// Typed characters edit the focused field, and Tab moves focus to the other.
+/// Escape returns to the title and Enter logs in.
+pub fn key_press(rl: &mut RaylibHandle, key: KeyboardKey) -> KeyPressResult {
+    match key {
+        KeyboardKey::KEY_ESCAPE => {
+            stage::register_next(StageType::Title);
+            return KeyPressResult::Consume;
+        }
+        KeyboardKey::KEY_ENTER => {
+            account::log_in_from_fields();
+            return KeyPressResult::Consume;
+        }
+        KeyboardKey::KEY_TAB => {
+            let mut focus: RwLockWriteGuard<AccountField> = STATE.stage.account.focus.write().unwrap();
+            *focus = focus.next();
+            return KeyPressResult::Consume;
+        }
+        _ => {}
+    }
+
+    let focus: AccountField = *STATE.stage.account.focus.read().unwrap();
+    let (field_l, max_length, accepts): (&RwLock<String>, usize, fn(char) -> bool) = match focus {
+        AccountField::Username => (&STATE.stage.account.username, MAX_USERNAME_LENGTH, is_username_char),
+        AccountField::Password => (&STATE.stage.account.password, MAX_PASSWORD_LENGTH, is_password_char),
+    };
+
+    let mut field: RwLockWriteGuard<String> = field_l.write().unwrap();
+    if key == KeyboardKey::KEY_BACKSPACE {
+        field.pop();
+    }
+    while let Some(character) = rl.get_char_pressed() {
+        if accepts(character) && field.chars().count() < max_length {
+            field.push(character);
+        }
+    }
+    KeyPressResult::Consume
+}
+
+/// Mirrors the server's validation
+fn is_username_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+fn is_password_char(character: char) -> bool {
+    !character.is_control()
+}
@@ -0,0 +1,17 @@
+mod account;
+pub use account::*;
+
+mod draw;
+pub use draw::*;
+
+mod input;
+pub use input::*;
+
+mod state;
+pub use state::*;
+
+mod init;
+pub use init::*;
+
+mod request;
+pub use request::*;
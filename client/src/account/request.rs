@@ -0,0 +1,63 @@
+//! Account requests made from the Account stage.
+//! A successful login is saved to the profile and opens a session for the account.
+
+use crate::account;
+use crate::stage::{self, StageType};
+use crate::{connect, profile, route, session};
+use shared::error::AppError;
+use shared::network::protocol::{CreateAccount, Frame, LoggedIn, Login, Operation, OperationType, Request};
+use shared::network::request::Requester;
+
+pub fn log_in(username: String, password: String) {
+    let Some(requester) = connect::requester_or_show_error() else {
+        return;
+    };
+    let message: Login = Login {
+        request_id: 0, // Assigned by the requester
+        username,
+        password,
+    };
+    tokio::spawn(submit(requester, message));
+}
+
+pub fn create_account(username: String, password: String) {
+    let Some(requester) = connect::requester_or_show_error() else {
+        return;
+    };
+    let message: CreateAccount = CreateAccount {
+        request_id: 0, // Assigned by the requester
+        username,
+        password,
+    };
+    tokio::spawn(submit(requester, message));
+}
+
+/// Send a request answered with [LoggedIn], replacing any current session with one for the account.
+async fn submit<T: Request>(requester: Requester, message: T) {
+    account::set_pending(true);
+    let response_r: Result<Frame, AppError> = requester.request(message).await;
+    account::set_pending(false);
+    let Ok(response) = response_r.inspect_err(|e| connect::show_request_error("Failed to log in", e)) else {
+        return;
+    };
+
+    match response.head.op_type {
+        OperationType::LoggedIn => match LoggedIn::from_frame(&response) {
+            Ok(logged_in) => {
+                log::info!("Logged in; [{}] [user: {}]", logged_in.username, logged_in.user_id);
+                account::clear_password();
+                profile::log_in(logged_in.username, logged_in.user_id, logged_in.account_token);
+                session::close();
+                connect::open_session(requester);
+                stage::register_next(StageType::Title);
+            }
+            Err(e) => log::error!("Failed to parse LoggedIn; {:#}", e),
+        },
+        OperationType::Error => route::show_error(&response),
+        _ => log::error!(
+            "Unexpected response to {}; [{}]",
+            OperationType::from_op_code_or_unknown(&T::OP_CODE),
+            response
+        ),
+    }
+}
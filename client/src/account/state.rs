@@ -0,0 +1,34 @@
+use crate::account::AccountField;
+use crate::button::RectangularButton;
+use std::sync::RwLock;
+
+#[derive(Debug)]
+pub struct AccountState {
+    pub username: RwLock<String>,
+    /// Cleared once submitted
+    pub password: RwLock<String>,
+    /// The field which receives typed characters
+    pub focus: RwLock<AccountField>,
+    /// A log in or create account request is awaiting its response
+    pub pending: RwLock<bool>,
+    pub back_button: RwLock<RectangularButton>,
+    pub log_in_button: RwLock<RectangularButton>,
+    pub create_button: RwLock<RectangularButton>,
+}
+
+impl AccountState {
+    pub const DEFAULT: AccountState = AccountState {
+        username: RwLock::new(String::new()),
+        password: RwLock::new(String::new()),
+        focus: RwLock::new(AccountField::Username),
+        pending: RwLock::new(false),
+        back_button: RwLock::new(RectangularButton::DEFAULT),
+        log_in_button: RwLock::new(RectangularButton::DEFAULT),
+        create_button: RwLock::new(RectangularButton::DEFAULT),
+    };
+
+    /// The buttons, in the order they receive input
+    pub fn buttons(&self) -> [&RwLock<RectangularButton>; 3] {
+        [&self.back_button, &self.log_in_button, &self.create_button]
+    }
+}
@@ -1 +1,15 @@
+use std::env;
+use std::path::PathBuf;
+
 pub const APPLICATION_NAME: &str = "singularity";
+
+/// Where the player profile is stored.
+/// May be overridden with `PROFILE_PATH`; otherwise a dot directory in the home directory, falling back to the
+/// working directory.
+pub fn profile_path() -> PathBuf {
+    if let Ok(path) = env::var("PROFILE_PATH") {
+        return PathBuf::from(path);
+    }
+    let directory: PathBuf = env::var("HOME").map(PathBuf::from).unwrap_or_default();
+    directory.join(format!(".{}", APPLICATION_NAME)).join("profile")
+}
@@ -1,64 +1,225 @@
 use std::net;
 use std::net::SocketAddr;
-use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::state::STATE;
+use crate::{games, profile, route, session, window};
 use shared::error::AppError;
-use shared::network::connection::{Connection, ConnectionReader, ConnectionWriter, WriteBufferT};
-use shared::network::protocol::{Operation, Register};
-use shared::network::ring_buffer::RingBuffer;
+use shared::network;
+use shared::network::backoff::Backoff;
+use shared::network::connection::{self, Connection};
+use shared::network::handshake;
+use shared::network::monitor::HEARTBEAT_INTERVAL;
+use shared::network::protocol::{
+    Error, ErrorCode, Frame, Operation, OperationType, Register, Resume, ResumeReject, Resumed, SessionGranted,
+};
+use shared::network::request::Requester;
 use shared::network::socket;
-use shared::{network, random};
+use shared::network::transport::PeerAddr;
 use socket2::{SockAddr, Socket};
-use tokio::net::TcpStream;
-use tokio::sync::RwLock;
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::time;
+use uuid::Uuid;
 
-pub fn connect() -> Result<WriteBufferT, AppError> {
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(500);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// A connection which lasts this long resets the backoff
+const STABLE_CONNECTION_DURATION: Duration = Duration::from_secs(60);
+
+/// Maintain a connection to the server for the life of the process, reconnecting with backoff whenever it drops.
+pub fn spawn_connection() {
+    tokio::spawn(async {
+        let mut backoff: Backoff = Backoff::new(RECONNECT_BACKOFF_MIN, RECONNECT_BACKOFF_MAX);
+        loop {
+            let connection_o: Option<Connection> =
+                connect().await.inspect_err(|e| log::warn!("Failed to connect to the server; {:#}", e)).ok();
+
+            if let Some(connection) = connection_o {
+                let connected_at: Instant = Instant::now();
+                run(connection).await;
+                log::warn!("Disconnected from the server");
+                if connected_at.elapsed() >= STABLE_CONNECTION_DURATION {
+                    backoff.reset();
+                }
+            }
+
+            let delay: Duration = backoff.next_delay();
+            log::info!("Reconnecting in {:?}; [attempt: {}]", delay, backoff.attempts());
+            time::sleep(delay).await;
+        }
+    });
+}
+
+/// The requester of the current connection, if connected
+pub fn requester() -> Option<Requester> {
+    STATE.connection.read().unwrap().clone()
+}
+
+/// The current connection's requester, telling the user that they are not connected if there is none.
+pub fn requester_or_show_error() -> Option<Requester> {
+    let requester_o: Option<Requester> = requester();
+    if requester_o.is_none() {
+        window::show_error("Not connected", "The server is unavailable. Reconnecting...");
+    }
+    requester_o
+}
+
+/// Log a request which failed without a response, and show it to the user.
+pub fn show_request_error(title: &str, error: &AppError) {
+    log::error!("{}; {:#}", title, error);
+    window::show_error(title, error.summary());
+}
+
+async fn connect() -> Result<Connection, AppError> {
     let sock_addr: SockAddr = socket::get_sock_addr()?;
+    let socket_addr: SocketAddr =
+        sock_addr.as_socket().ok_or_else(|| AppError::new("Server address is not an IP address"))?;
     let socket: Socket = socket::create_socket()?;
-    socket.connect_timeout(&sock_addr, Duration::from_secs(3))?;
+    socket.set_nonblocking(true)?; // Required for Tokio
 
-    let std_tcp_stream: net::TcpStream = net::TcpStream::from(socket);
-    std_tcp_stream.set_nonblocking(true)?; // Required for Tokio
+    let tcp_socket: TcpSocket = TcpSocket::from_std_stream(net::TcpStream::from(socket));
+    let tcp_stream: TcpStream = match time::timeout(CONNECT_TIMEOUT, tcp_socket.connect(socket_addr)).await {
+        Ok(connect_r) => connect_r?,
+        Err(_) => return Err(AppError::new("Timed out connecting to the server")),
+    };
 
-    let tcp_stream: TcpStream = TcpStream::from_std(std_tcp_stream)?;
     let peer_addr: SocketAddr = tcp_stream.peer_addr()?;
-    let connection: Connection = Connection::new(tcp_stream, peer_addr);
-    let write_buffer: Arc<RwLock<RingBuffer<u8, 4096>>> = connection.writer.buffer.clone();
+    log::info!("Connected to the server; [{}]", peer_addr);
+    Ok(Connection::new(tcp_stream, PeerAddr::from(peer_addr)))
+}
 
-    send_register(write_buffer.clone());
-    spawn_reader(connection.reader);
-    spawn_writer(connection.writer);
+/// Run the connection until it drops.
+/// Heartbeats begin one interval after connecting, by which point the handshake has completed.
+async fn run(connection: Connection) {
+    let requester: Requester = connection.requester();
+    let Connection { reader, mut writer, .. } = connection;
 
-    Ok(write_buffer)
+    if let Err(e) = connection::send(&requester.write_buffer, &handshake::hello()).await {
+        log::error!("Failed to send Hello; {:#}", e);
+        return;
+    }
+    *STATE.connection.write().unwrap() = Some(requester.clone());
+
+    let incoming_f =
+        network::monitor::monitor_incoming_frames(reader, |_, frame| route::route_frame(requester.clone(), frame));
+    let heartbeat_f = network::monitor::send_heartbeats(requester.write_buffer.clone(), HEARTBEAT_INTERVAL);
+    tokio::select! {
+        _ = incoming_f => {}
+        outgoing_r = network::monitor::monitor_outgoing_frames(&mut writer) => {
+            if let Err(e) = outgoing_r {
+                log::error!("Error writing frame to the network; {:#}", e);
+            }
+        }
+        heartbeat_r = heartbeat_f => {
+            if let Err(e) = heartbeat_r {
+                log::error!("Error sending heartbeat; {:#}", e);
+            }
+        }
+    }
+
+    *STATE.connection.write().unwrap() = None;
+    writer.buffer.close();
+    requester.pending_requests.cancel_all();
 }
 
-fn spawn_reader(reader: ConnectionReader) {
+/// Resume the previous session if there is one, otherwise register a new session for the logged in account.
+pub fn open_session(requester: Requester) {
     tokio::spawn(async move {
-        network::monitor::monitor_incoming_frames(reader, |w, frame| async move {
-            // todo: route frames
-        })
-        .await;
+        if let Some(session_token) = session::token()
+            && resume(&requester, session_token).await
+        {
+            return;
+        }
+        let Some(account_token) = profile::account_token() else {
+            log::info!("Not logged in; a session is opened once logged in");
+            return;
+        };
+        register(&requester, account_token).await;
     });
 }
 
-fn spawn_writer(writer: ConnectionWriter) {
-    tokio::spawn(async move {
-        match network::monitor::monitor_outgoing_frames(writer).await {
-            Ok(_) => {}
+/// Returns whether the session was resumed.
+async fn resume(requester: &Requester, session_token: Uuid) -> bool {
+    let message: Resume = Resume {
+        request_id: 0, // Assigned by the requester
+        session_token,
+        last_sequence: session::last_sequence(),
+    };
+    let response_o: Option<Frame> =
+        requester.request(message).await.inspect_err(|e| log::error!("Resume failed; {:#}", e)).ok();
+    let Some(response) = response_o else {
+        return false;
+    };
+
+    match response.head.op_type {
+        OperationType::Resumed => match Resumed::from_frame(&response) {
+            Ok(resumed) => {
+                log::info!(
+                    "Session resumed; [games: {}] [replayed: {}]",
+                    resumed.games.len(),
+                    resumed.replayed
+                );
+                if resumed.incomplete {
+                    log::warn!("Some missed frames are no longer available; fetching the games' state again");
+                    games::resync_games(requester.clone(), resumed.games.clone());
+                }
+                session::set_games(resumed.games);
+                true
+            }
             Err(e) => {
-                log::error!("Error writing frame to the network; {:#}", e);
+                log::error!("Failed to parse Resumed; {:#}", e);
+                false
+            }
+        },
+        OperationType::ResumeReject => {
+            match ResumeReject::from_frame(&response) {
+                Ok(resume_reject) => log::info!("Session could not be resumed; [{}]", resume_reject.reason),
+                Err(e) => log::error!("Failed to parse ResumeReject; {:#}", e),
             }
+            session::close();
+            false
         }
-    });
+        OperationType::Error => {
+            route::show_error(&response);
+            false
+        }
+        _ => {
+            log::error!("Unexpected response to Resume; [{}]", response);
+            false
+        }
+    }
 }
 
-fn send_register(write_buffer: WriteBufferT) {
-    let message = Register {
-        op_code: Register::OP_CODE,
-        user_id: random::random_uuid(),
+async fn register(requester: &Requester, account_token: Uuid) {
+    let message: Register = Register {
+        request_id: 0, // Assigned by the requester
+        account_token,
     };
-    tokio::spawn(async move {
-        write_buffer.write().await.push(message.as_bytes().as_slice()).expect("Register message failed");
-    });
+    let response_o: Option<Frame> =
+        requester.request(message).await.inspect_err(|e| log::error!("Register failed; {:#}", e)).ok();
+    let Some(response) = response_o else {
+        return;
+    };
+
+    match response.head.op_type {
+        OperationType::SessionGranted => match SessionGranted::from_frame(&response) {
+            Ok(session_granted) => {
+                log::info!("Session opened; [games: {}]", session_granted.games.len());
+                session::open(session_granted.session_token);
+                session::set_games(session_granted.games.clone());
+                if !session_granted.games.is_empty() {
+                    games::restore_games(requester.clone(), session_granted.games);
+                }
+            }
+            Err(e) => log::error!("Failed to parse SessionGranted; {:#}", e),
+        },
+        OperationType::Error => {
+            if Error::from_frame(&response).is_ok_and(|error| error.code == ErrorCode::Unauthorized) {
+                profile::log_out();
+            }
+            route::show_error(&response);
+        }
+        _ => log::error!("Unexpected response to Register; [{}]", response),
+    }
 }
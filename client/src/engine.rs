@@ -2,7 +2,7 @@ use crate::color::{MAP_BACKGROUND_COLOR, TEXT_COLOR};
 use crate::config::APPLICATION_NAME;
 use crate::stage::StageType;
 use crate::state::STATE;
-use crate::{connect, input, map, player, shader, stage, texture, title};
+use crate::{account, connect, games, input, profile, shader, stage, texture, title, window};
 use raylib::callbacks::TraceLogLevel;
 use raylib::consts::KeyboardKey;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
@@ -10,11 +10,9 @@ use raylib::ffi::rlGetVersion;
 use raylib::{RaylibHandle, RaylibThread};
 use shared::environment::RuntimeEnvironment;
 use shared::error::AppError;
-use shared::network::ring_buffer::RingBuffer;
-use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
 use std::time;
 use time::Instant;
-use tokio::sync::RwLock;
 
 pub const TARGET_FPS: u8 = 60;
 pub const DISPLAY_WIDTH: u16 = 1600;
@@ -23,6 +21,7 @@ pub const DISPLAY_HEIGHT: u16 = 900;
 fn update(rl: &mut RaylibHandle, rl_thread: &RaylibThread) {
     texture::update(rl, rl_thread);
     stage::update();
+    window::update_error_window(rl);
     input::handle_user_input(rl);
 }
 
@@ -37,7 +36,10 @@ fn draw(rl_draw: &mut RaylibDrawHandle, rl_thread: &RaylibThread) {
 }
 
 pub fn init() -> Result<(RaylibHandle, RaylibThread), AppError> {
-    let _: Arc<RwLock<RingBuffer<u8, 4096>>> = connect::connect()?;
+    if let Err(e) = profile::load() {
+        log::error!("Failed to load profile; {:#}", e);
+    }
+    connect::spawn_connection();
 
     unsafe {
         log::info!("OpenGL version: {}", rlGetVersion());
@@ -72,8 +74,8 @@ pub fn init() -> Result<(RaylibHandle, RaylibThread), AppError> {
     texture::init(&mut rl, &rl_thread);
     shader::init(&mut rl, &rl_thread);
     title::init_title(&mut rl);
-    map::init_map();
-    player::init_players(4);
+    account::init_account(&mut rl);
+    games::init_games(&mut rl);
 
     Ok((rl, rl_thread))
 }
@@ -1,6 +1,6 @@
 use crate::color::{FACILITY_DESTROYED_COLOR, FACILITY_OPERATING_COLOR, FACILITY_PLACING_COLOR};
 use crate::facility::{Facility, FacilityState, FacilityTrait};
-use crate::map::{HexCoord, RenderCoord};
+use crate::map::{self, HexCoord, RenderCoord};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
 
@@ -37,6 +37,6 @@ impl ControlCenter {
     pub const INFLUENCE_RADIUS_STEP: i16 = 4;
 
     pub fn within_influence(&self, hex_coord: HexCoord) -> bool {
-        self.location.step_distance_le(hex_coord, Self::INFLUENCE_RADIUS_STEP)
+        self.location.distance(hex_coord, map::map_size()) <= Self::INFLUENCE_RADIUS_STEP
     }
 }
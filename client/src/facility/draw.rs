@@ -1,5 +1,5 @@
 use crate::facility::Facility;
-use crate::map::{MapCoord, RenderCoord};
+use crate::map::{HexGeometry, MapCoord, RenderCoord};
 use raylib::drawing::RaylibDrawHandle;
 
 pub fn draw_facility(rl_draw: &mut RaylibDrawHandle, facility: Facility, map_origin: &MapCoord) {
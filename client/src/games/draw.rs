@@ -0,0 +1,205 @@
+use crate::button::RectangularButton;
+use crate::color::{DIFF_HOVER_BUTTON, MAP_BACKGROUND_COLOR, TEXT_COLOR, WINDOW_BACKGROUND_COLOR, WINDOW_BORDER_COLOR};
+use crate::font::DEFAULT_FONT_SPACING;
+use crate::games::{self, CONTENT_TOP, FIELD_HEIGHT, HEADING_FONT_SIZE, MAX_ROWS, PANEL_WIDTH, TEXT_FONT_SIZE};
+use crate::math;
+use crate::profile;
+use crate::state::STATE;
+use crate::title::{self, SCREEN_MARGIN};
+use raylib::color::Color;
+use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+use raylib::math::{Rectangle, Vector2};
+use raylib::text::RaylibFont;
+use shared::network::protocol::{GameSettings, GameSummary};
+use std::sync::RwLockReadGuard;
+use uuid::Uuid;
+
+const ROW_INTERNAL_MARGIN: f32 = 14.;
+
+pub fn draw(rl_draw: &mut RaylibDrawHandle) {
+    rl_draw.clear_background(MAP_BACKGROUND_COLOR);
+
+    draw_headings(rl_draw);
+    draw_game_rows(rl_draw);
+    draw_create_panel(rl_draw);
+    for button_l in STATE.stage.games.buttons() {
+        let button: RwLockReadGuard<RectangularButton> = button_l.read().unwrap();
+        title::draw_button(rl_draw, &button);
+    }
+}
+
+fn draw_headings(rl_draw: &mut RaylibDrawHandle) {
+    let y: f32 = CONTENT_TOP - HEADING_FONT_SIZE - 16.;
+    draw_text(rl_draw, "Games", Vector2 { x: SCREEN_MARGIN, y }, HEADING_FONT_SIZE);
+
+    let x: f32 = games::panel_x(rl_draw.get_screen_width());
+    draw_text(rl_draw, "Create game", Vector2 { x, y }, HEADING_FONT_SIZE);
+}
+
+fn draw_game_rows(rl_draw: &mut RaylibDrawHandle) {
+    let games_l: RwLockReadGuard<Vec<GameSummary>> = STATE.stage.games.games.read().unwrap();
+    if games_l.is_empty() {
+        let origin: Vector2 = math::rect_origin(games::row_rectangle(0));
+        draw_text(
+            rl_draw,
+            "No open games",
+            Vector2 {
+                x: origin.x + ROW_INTERNAL_MARGIN,
+                y: origin.y + ROW_INTERNAL_MARGIN,
+            },
+            TEXT_FONT_SIZE,
+        );
+        return;
+    }
+
+    let hovered_row: Option<usize> = *STATE.stage.games.hovered_row.read().unwrap();
+    let user_id_o: Option<Uuid> = profile::user_id();
+    for (index, game) in games_l.iter().take(MAX_ROWS).enumerate() {
+        let rectangle: Rectangle = games::row_rectangle(index);
+        let mut bg_color: Color = WINDOW_BACKGROUND_COLOR;
+        if hovered_row == Some(index) {
+            bg_color = math::color_add(&bg_color, &DIFF_HOVER_BUTTON);
+        }
+        rl_draw.draw_rectangle_rec(rectangle, bg_color);
+
+        let text_y: f32 = rectangle.y + (rectangle.height - TEXT_FONT_SIZE) / 2.;
+        draw_text(
+            rl_draw,
+            &game.settings.name,
+            Vector2 {
+                x: rectangle.x + ROW_INTERNAL_MARGIN,
+                y: text_y,
+            },
+            TEXT_FONT_SIZE,
+        );
+
+        let mut players: String = format!("{}/{} players", game.players.len(), game.settings.max_players);
+        // Joined games are listed so that they can be entered again
+        if user_id_o.is_some_and(|user_id| game.players.contains(&user_id)) {
+            players = format!("Joined, {}", players);
+        }
+        let players_width: f32 =
+            rl_draw.get_font_default().measure_text(&players, TEXT_FONT_SIZE, DEFAULT_FONT_SPACING).x;
+        draw_text(
+            rl_draw,
+            &players,
+            Vector2 {
+                x: rectangle.x + rectangle.width - ROW_INTERNAL_MARGIN - players_width,
+                y: text_y,
+            },
+            TEXT_FONT_SIZE,
+        );
+    }
+}
+
+fn draw_create_panel(rl_draw: &mut RaylibDrawHandle) {
+    let settings: RwLockReadGuard<GameSettings> = STATE.stage.games.settings.read().unwrap();
+    let x: f32 = games::panel_x(rl_draw.get_screen_width());
+
+    draw_text(rl_draw, "Name", Vector2 { x, y: CONTENT_TOP }, TEXT_FONT_SIZE);
+    let field: Rectangle = games::name_field_rectangle(rl_draw.get_screen_width());
+    rl_draw.draw_rectangle_rec(field, WINDOW_BACKGROUND_COLOR);
+    rl_draw.draw_rectangle_lines_ex(field, 1., WINDOW_BORDER_COLOR);
+    let name: String = format!("{}_", settings.name);
+    draw_text(
+        rl_draw,
+        &name,
+        Vector2 {
+            x: field.x + ROW_INTERNAL_MARGIN,
+            y: field.y + (field.height - TEXT_FONT_SIZE) / 2.,
+        },
+        TEXT_FONT_SIZE,
+    );
+
+    let players_y: f32 = games::players_row_y();
+    draw_text(
+        rl_draw,
+        "Players",
+        Vector2 {
+            x,
+            y: players_y - TEXT_FONT_SIZE - 8.,
+        },
+        TEXT_FONT_SIZE,
+    );
+    let max_players: String = settings.max_players.to_string();
+    rl_draw.draw_text_ex(
+        rl_draw.get_font_default(),
+        &max_players,
+        math::centered_text_origin(
+            Vector2 {
+                x: x + PANEL_WIDTH / 2.,
+                y: players_y + FIELD_HEIGHT / 2.,
+            },
+            &max_players,
+            rl_draw.get_font_default(),
+            TEXT_FONT_SIZE,
+            DEFAULT_FONT_SPACING,
+        ),
+        TEXT_FONT_SIZE,
+        DEFAULT_FONT_SPACING,
+        TEXT_COLOR,
+    );
+
+    draw_choice_field(
+        rl_draw,
+        "Map",
+        &settings.map_generator.to_string(),
+        x,
+        games::map_row_y(),
+    );
+    draw_choice_field(rl_draw, "Size", &settings.map_size.to_string(), x, games::size_row_y());
+
+    if games::is_pending() {
+        let create: Rectangle = STATE.stage.games.create_button.read().unwrap().rectangle;
+        draw_text(
+            rl_draw,
+            "Joining...",
+            Vector2 {
+                x,
+                y: create.y + create.height + 16.,
+            },
+            TEXT_FONT_SIZE,
+        );
+    }
+}
+
+/// A labelled field showing the current choice, beside the button that cycles it
+fn draw_choice_field(rl_draw: &mut RaylibDrawHandle, label: &str, choice: &str, x: f32, y: f32) {
+    draw_text(
+        rl_draw,
+        label,
+        Vector2 {
+            x,
+            y: y - TEXT_FONT_SIZE - 8.,
+        },
+        TEXT_FONT_SIZE,
+    );
+    let field: Rectangle = Rectangle {
+        x,
+        y,
+        width: PANEL_WIDTH - FIELD_HEIGHT,
+        height: FIELD_HEIGHT,
+    };
+    rl_draw.draw_rectangle_rec(field, WINDOW_BACKGROUND_COLOR);
+    rl_draw.draw_rectangle_lines_ex(field, 1., WINDOW_BORDER_COLOR);
+    draw_text(
+        rl_draw,
+        choice,
+        Vector2 {
+            x: field.x + ROW_INTERNAL_MARGIN,
+            y: field.y + (field.height - TEXT_FONT_SIZE) / 2.,
+        },
+        TEXT_FONT_SIZE,
+    );
+}
+
+fn draw_text(rl_draw: &mut RaylibDrawHandle, text: &str, position: Vector2, font_size: f32) {
+    rl_draw.draw_text_ex(
+        rl_draw.get_font_default(),
+        text,
+        position,
+        font_size,
+        DEFAULT_FONT_SPACING,
+        TEXT_COLOR,
+    );
+}
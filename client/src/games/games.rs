@@ -0,0 +1,127 @@
+use crate::profile;
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use crate::title::SCREEN_MARGIN;
+use crate::window;
+use raylib::math::Rectangle;
+use shared::network::protocol::{GameSettings, GameStatus, GameSummary};
+use std::sync::RwLockWriteGuard;
+use uuid::Uuid;
+
+pub const HEADING_FONT_SIZE: f32 = 32.;
+pub const TEXT_FONT_SIZE: f32 = 18.;
+/// Top of the game list and of the create panel
+pub const CONTENT_TOP: f32 = 130.;
+pub const LIST_WIDTH: f32 = 640.;
+pub const ROW_HEIGHT: f32 = 44.;
+pub const ROW_GAP: f32 = 8.;
+/// Rows beyond this are not shown
+pub const MAX_ROWS: usize = 12;
+pub const PANEL_WIDTH: f32 = 360.;
+pub const FIELD_HEIGHT: f32 = 40.;
+pub const MAX_NAME_LENGTH: usize = 24;
+pub const DEFAULT_MAX_PLAYERS: u8 = 4;
+/// Mirrors the server's limit
+pub const MAX_PLAYERS_LIMIT: u8 = 8;
+
+/// Show the Games stage and fetch the current list of games.
+pub fn enter() {
+    stage::register_next(StageType::Games);
+    super::refresh_games();
+}
+
+/// Create a game with the settings entered, unless a request is already pending.
+pub fn create_from_settings() {
+    if is_pending() {
+        return;
+    }
+    let settings: GameSettings = STATE.stage.games.settings.read().unwrap().clone();
+    if settings.name.trim().is_empty() {
+        window::show_error("Invalid settings", "Enter a name for the game.");
+        return;
+    }
+    super::create_game(settings);
+}
+
+/// Join the game listed in the row, unless a request is already pending.
+pub fn join_row(index: usize) {
+    if is_pending() {
+        return;
+    }
+    let game_id_o: Option<Uuid> = STATE.stage.games.games.read().unwrap().get(index).map(|game| game.id);
+    if let Some(game_id) = game_id_o {
+        super::join_game(game_id);
+    }
+}
+
+pub fn set_games(games: Vec<GameSummary>) {
+    let mut current: RwLockWriteGuard<Vec<GameSummary>> = STATE.stage.games.games.write().unwrap();
+    *current = games;
+    *STATE.stage.games.hovered_row.write().unwrap() = None;
+}
+
+/// Apply a game's new lobby state to the list, following the server's rule for which games are listed:
+/// open games which can still be joined, and the unfinished games which the user has joined.
+pub fn update_game(game: GameSummary) {
+    let joined: bool = profile::user_id().is_some_and(|user_id| game.players.contains(&user_id));
+    let listed: bool =
+        (game.status == GameStatus::Open && !game.is_full()) || (game.status != GameStatus::Finished && joined);
+
+    let mut current: RwLockWriteGuard<Vec<GameSummary>> = STATE.stage.games.games.write().unwrap();
+    match current.iter().position(|listed_game| listed_game.id == game.id) {
+        Some(index) if listed => current[index] = game,
+        Some(index) => {
+            current.remove(index);
+            *STATE.stage.games.hovered_row.write().unwrap() = None;
+        }
+        None if listed => current.push(game),
+        None => {}
+    }
+}
+
+pub fn set_pending(pending: bool) {
+    *STATE.stage.games.pending.write().unwrap() = pending;
+}
+
+pub fn is_pending() -> bool {
+    *STATE.stage.games.pending.read().unwrap()
+}
+
+pub fn row_rectangle(index: usize) -> Rectangle {
+    Rectangle {
+        x: SCREEN_MARGIN,
+        y: CONTENT_TOP + index as f32 * (ROW_HEIGHT + ROW_GAP),
+        width: LIST_WIDTH,
+        height: ROW_HEIGHT,
+    }
+}
+
+/// Left edge of the create panel, which is aligned to the right of the screen
+pub fn panel_x(screen_width: i32) -> f32 {
+    screen_width as f32 - SCREEN_MARGIN - PANEL_WIDTH
+}
+
+pub fn name_field_rectangle(screen_width: i32) -> Rectangle {
+    Rectangle {
+        x: panel_x(screen_width),
+        y: CONTENT_TOP + TEXT_FONT_SIZE + 8.,
+        width: PANEL_WIDTH,
+        height: FIELD_HEIGHT,
+    }
+}
+
+/// Top of the row holding the fewer and more players buttons
+pub fn players_row_y() -> f32 {
+    CONTENT_TOP + (TEXT_FONT_SIZE + 8.) * 2. + FIELD_HEIGHT + 16.
+}
+
+/// Top of the row holding the map generator button
+pub fn map_row_y() -> f32 {
+    players_row_y() + (TEXT_FONT_SIZE + 8.) + FIELD_HEIGHT + 16.
+}
+
+/// Top of the row holding the map size button
+pub fn size_row_y() -> f32 {
+    map_row_y() + (TEXT_FONT_SIZE + 8.) + FIELD_HEIGHT + 16.
+}
@@ -0,0 +1,178 @@
+use crate::button::RectangularButton;
+use crate::games::{self, CONTENT_TOP, FIELD_HEIGHT, LIST_WIDTH, MAX_PLAYERS_LIMIT, PANEL_WIDTH, ROW_GAP, ROW_HEIGHT};
+use crate::input::ClickResult;
+use crate::map::RenderCoord;
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use crate::title::SCREEN_MARGIN;
+use raylib::RaylibHandle;
+use raylib::math::{Rectangle, Vector2};
+use shared::map::MapSize;
+use shared::network::protocol::GameSettings;
+use std::sync::RwLockWriteGuard;
+
+const DEFAULT_NAME: &str = "New game";
+const BUTTON_DIMENSIONS: Vector2 = Vector2 { x: 120., y: 40. };
+
+pub fn init_games(rl: &mut RaylibHandle) {
+    let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+    settings.name = String::from(DEFAULT_NAME);
+    drop(settings);
+
+    *STATE.stage.games.back_button.write().unwrap() = create_back_button();
+    *STATE.stage.games.refresh_button.write().unwrap() = create_refresh_button();
+    *STATE.stage.games.fewer_players_button.write().unwrap() = create_fewer_players_button(rl);
+    *STATE.stage.games.more_players_button.write().unwrap() = create_more_players_button(rl);
+    *STATE.stage.games.map_generator_button.write().unwrap() = create_map_generator_button(rl);
+    *STATE.stage.games.map_size_button.write().unwrap() = create_map_size_button(rl);
+    *STATE.stage.games.create_button.write().unwrap() = create_create_button(rl);
+}
+
+fn create_back_button() -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Back",
+        Rectangle {
+            x: SCREEN_MARGIN,
+            y: SCREEN_MARGIN,
+            width: BUTTON_DIMENSIONS.x,
+            height: BUTTON_DIMENSIONS.y,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        stage::register_next(StageType::Title);
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_refresh_button() -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Refresh",
+        Rectangle {
+            x: SCREEN_MARGIN + LIST_WIDTH - BUTTON_DIMENSIONS.x,
+            y: CONTENT_TOP - BUTTON_DIMENSIONS.y - ROW_GAP,
+            width: BUTTON_DIMENSIONS.x,
+            height: BUTTON_DIMENSIONS.y,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        games::refresh_games();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_fewer_players_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "-",
+        Rectangle {
+            x: games::panel_x(rl.get_screen_width()),
+            y: games::players_row_y(),
+            width: FIELD_HEIGHT,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+        settings.max_players = settings.max_players.saturating_sub(1).max(1);
+        settings.map_size = MapSize::for_players(settings.max_players);
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_more_players_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "+",
+        Rectangle {
+            x: games::panel_x(rl.get_screen_width()) + PANEL_WIDTH - FIELD_HEIGHT,
+            y: games::players_row_y(),
+            width: FIELD_HEIGHT,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+        settings.max_players = (settings.max_players + 1).min(MAX_PLAYERS_LIMIT);
+        settings.map_size = MapSize::for_players(settings.max_players);
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+/// Cycles through the map generators
+fn create_map_generator_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        ">",
+        Rectangle {
+            x: games::panel_x(rl.get_screen_width()) + PANEL_WIDTH - FIELD_HEIGHT,
+            y: games::map_row_y(),
+            width: FIELD_HEIGHT,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+        settings.map_generator = settings.map_generator.next();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+/// Cycles through the map size presets. Changing the number of players picks a size to suit them.
+fn create_map_size_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        ">",
+        Rectangle {
+            x: games::panel_x(rl.get_screen_width()) + PANEL_WIDTH - FIELD_HEIGHT,
+            y: games::size_row_y(),
+            width: FIELD_HEIGHT,
+            height: FIELD_HEIGHT,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+        settings.map_size = settings.map_size.next();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
+
+fn create_create_button(rl: &mut RaylibHandle) -> RectangularButton {
+    let mut button: RectangularButton = RectangularButton::new_with_text(
+        "Create",
+        Rectangle {
+            x: games::panel_x(rl.get_screen_width()),
+            y: games::size_row_y() + FIELD_HEIGHT + ROW_HEIGHT / 2.,
+            width: PANEL_WIDTH,
+            height: BUTTON_DIMENSIONS.y,
+        },
+    );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        games::create_from_settings();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
+    button
+}
@@ -0,0 +1,81 @@
+use crate::button::RectangularButton;
+use crate::games::{self, MAX_NAME_LENGTH, MAX_ROWS};
+use crate::input::{ClickHandler, ClickResult, HoverHandler, HoverResult, KeyPressResult};
+use crate::map::RenderCoord;
+use crate::stage;
+use crate::stage::StageType;
+use crate::state::STATE;
+use raylib::RaylibHandle;
+use raylib::consts::KeyboardKey;
+use shared::network::protocol::GameSettings;
+use std::sync::RwLockWriteGuard;
+
+pub fn click(rl: &mut RaylibHandle, mouse_position: RenderCoord) -> ClickResult {
+    for button_l in STATE.stage.games.buttons() {
+        let mut button: RwLockWriteGuard<RectangularButton> = button_l.write().unwrap();
+        if let ClickResult::Consume = button.click(rl, mouse_position) {
+            return ClickResult::Consume;
+        }
+    }
+
+    if let Some(index) = row_at(mouse_position) {
+        games::join_row(index);
+        return ClickResult::Consume;
+    }
+    ClickResult::Pass
+}
+
+pub fn hover(rl: &mut RaylibHandle, mouse_position: RenderCoord) -> HoverResult {
+    let mut result: HoverResult = HoverResult::Pass;
+    for button_l in STATE.stage.games.buttons() {
+        let mut button: RwLockWriteGuard<RectangularButton> = button_l.write().unwrap();
+        // Every button is hovered so that each can clear its highlight
+        if let HoverResult::Consume = button.hover(rl, mouse_position) {
+            result = HoverResult::Consume;
+        }
+    }
+
+    let hovered_row: Option<usize> = row_at(mouse_position);
+    *STATE.stage.games.hovered_row.write().unwrap() = hovered_row;
+    if hovered_row.is_some() {
+        result = HoverResult::Consume;
+    }
+    result
+}
+
+/// Typed characters edit the game name.
+/// Escape returns to the title, Enter creates a game and F5 refreshes the list.
+pub fn key_press(rl: &mut RaylibHandle, key: KeyboardKey) -> KeyPressResult {
+    match key {
+        KeyboardKey::KEY_ESCAPE => {
+            stage::register_next(StageType::Title);
+            return KeyPressResult::Consume;
+        }
+        KeyboardKey::KEY_ENTER => {
+            games::create_from_settings();
+            return KeyPressResult::Consume;
+        }
+        KeyboardKey::KEY_F5 => {
+            games::refresh_games();
+            return KeyPressResult::Consume;
+        }
+        _ => {}
+    }
+
+    let mut settings: RwLockWriteGuard<GameSettings> = STATE.stage.games.settings.write().unwrap();
+    if key == KeyboardKey::KEY_BACKSPACE {
+        settings.name.pop();
+    }
+    while let Some(character) = rl.get_char_pressed() {
+        if (character.is_alphanumeric() || character == ' ') && settings.name.chars().count() < MAX_NAME_LENGTH {
+            settings.name.push(character);
+        }
+    }
+    KeyPressResult::Consume
+}
+
+/// Index of the listed game under the mouse
+fn row_at(mouse_position: RenderCoord) -> Option<usize> {
+    let count: usize = STATE.stage.games.games.read().unwrap().len().min(MAX_ROWS);
+    (0..count).find(|index| games::row_rectangle(*index).check_collision_point_rec(mouse_position))
+}
@@ -0,0 +1,17 @@
+mod games;
+pub use games::*;
+
+mod draw;
+pub use draw::*;
+
+mod input;
+pub use input::*;
+
+mod state;
+pub use state::*;
+
+mod init;
+pub use init::*;
+
+mod request;
+pub use request::*;
@@ -0,0 +1,150 @@
+//! Lobby requests made from the Games stage.
+//! Each request runs on its own task; the stage is updated once the response arrives.
+
+use crate::stage::{self, StageType};
+use crate::{connect, games, map, route, session};
+use shared::error::AppError;
+use shared::map::Map;
+use shared::network::protocol::{
+    CreateGame, Frame, GameJoined, GameList, GameMap, GameSettings, GetMap, JoinGame, ListGames, Operation,
+    OperationType, Request,
+};
+use shared::network::request::Requester;
+use uuid::Uuid;
+
+/// Fetch the games which can be joined.
+pub fn refresh_games() {
+    let Some(requester) = connect::requester_or_show_error() else {
+        return;
+    };
+    tokio::spawn(async move { list_games(&requester).await });
+}
+
+/// Fetch the state of the games again after frames delivered for them were lost while disconnected.
+/// The map shown is reloaded if its game is one of them; other games' maps are fetched once entered.
+pub fn resync_games(requester: Requester, game_ids: Vec<Uuid>) {
+    tokio::spawn(async move {
+        list_games(&requester).await;
+        for game_id in game_ids {
+            if map::game_id() != Some(game_id) {
+                continue;
+            }
+            if let Some(map) = fetch_map(&requester, game_id).await {
+                map::set_map(game_id, &map);
+            }
+        }
+    });
+}
+
+/// Put the user back in their games once a new session has been granted, e.g. after the server restarted.
+/// The game shown is reloaded if the user is still a player in it, otherwise the first of their games is entered.
+pub fn restore_games(requester: Requester, game_ids: Vec<Uuid>) {
+    tokio::spawn(async move {
+        list_games(&requester).await;
+        let game_id_o: Option<Uuid> =
+            map::game_id().filter(|game_id| game_ids.contains(game_id)).or(game_ids.first().copied());
+        if let Some(game_id) = game_id_o {
+            load_map(&requester, game_id).await;
+        }
+    });
+}
+
+async fn list_games(requester: &Requester) {
+    let response_r: Result<Frame, AppError> = requester.request(ListGames { request_id: 0 }).await;
+    let Ok(response) = response_r.inspect_err(|e| connect::show_request_error("Failed to list games", e)) else {
+        return;
+    };
+
+    match response.head.op_type {
+        OperationType::GameList => match GameList::from_frame(&response) {
+            Ok(game_list) => games::set_games(game_list.games),
+            Err(e) => log::error!("Failed to parse GameList; {:#}", e),
+        },
+        OperationType::Error => route::show_error(&response),
+        _ => log::error!("Unexpected response to ListGames; [{}]", response),
+    }
+}
+
+/// Create a game with the settings and join it.
+pub fn create_game(settings: GameSettings) {
+    let Some(requester) = connect::requester_or_show_error() else {
+        return;
+    };
+    let message: CreateGame = CreateGame {
+        request_id: 0, // Assigned by the requester
+        settings,
+    };
+    tokio::spawn(enter_game(requester, message));
+}
+
+pub fn join_game(game_id: Uuid) {
+    let Some(requester) = connect::requester_or_show_error() else {
+        return;
+    };
+    let message: JoinGame = JoinGame {
+        request_id: 0, // Assigned by the requester
+        game_id,
+    };
+    tokio::spawn(enter_game(requester, message));
+}
+
+/// Send a request answered with [GameJoined], then load the game's map and enter the game stage.
+async fn enter_game<T: Request>(requester: Requester, message: T) {
+    games::set_pending(true);
+    let response_r: Result<Frame, AppError> = requester.request(message).await;
+    games::set_pending(false);
+    let Ok(response) = response_r.inspect_err(|e| connect::show_request_error("Failed to join game", e)) else {
+        return;
+    };
+
+    match response.head.op_type {
+        OperationType::GameJoined => match GameJoined::from_frame(&response) {
+            Ok(game_joined) => {
+                log::info!(
+                    "Joined game; [{}] [{}]",
+                    game_joined.game.id,
+                    game_joined.game.settings.name
+                );
+                session::join_game(game_joined.game.id);
+                load_map(&requester, game_joined.game.id).await;
+            }
+            Err(e) => log::error!("Failed to parse GameJoined; {:#}", e),
+        },
+        OperationType::Error => route::show_error(&response),
+        _ => log::error!(
+            "Unexpected response to {}; [{}]",
+            OperationType::from_op_code_or_unknown(&T::OP_CODE),
+            response
+        ),
+    }
+}
+
+/// Fetch the joined game's map, entering the game stage once it has arrived.
+async fn load_map(requester: &Requester, game_id: Uuid) {
+    if let Some(map) = fetch_map(requester, game_id).await {
+        map::set_map(game_id, &map);
+        stage::register_next(StageType::Game);
+    }
+}
+
+/// The joined game's map, which the server generates
+async fn fetch_map(requester: &Requester, game_id: Uuid) -> Option<Map> {
+    let message: GetMap = GetMap {
+        request_id: 0, // Assigned by the requester
+        game_id,
+    };
+    let response_r: Result<Frame, AppError> = requester.request(message).await;
+    let Ok(response) = response_r.inspect_err(|e| connect::show_request_error("Failed to load map", e)) else {
+        return None;
+    };
+
+    match response.head.op_type {
+        OperationType::GameMap => match GameMap::from_frame(&response) {
+            Ok(game_map) => return Some(game_map.map),
+            Err(e) => log::error!("Failed to parse GameMap; {:#}", e),
+        },
+        OperationType::Error => route::show_error(&response),
+        _ => log::error!("Unexpected response to GetMap; [{}]", response),
+    }
+    None
+}
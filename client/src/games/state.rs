@@ -0,0 +1,58 @@
+use crate::button::RectangularButton;
+use crate::games::DEFAULT_MAX_PLAYERS;
+use shared::map::{MapGeneratorType, MapSize};
+use shared::network::protocol::{GameSettings, GameSummary};
+use std::sync::RwLock;
+
+#[derive(Debug)]
+pub struct GamesState {
+    /// Joinable games, as last listed by the server
+    pub games: RwLock<Vec<GameSummary>>,
+    /// Index into [GamesState::games] of the row under the mouse
+    pub hovered_row: RwLock<Option<usize>>,
+    /// Settings for the next game created
+    pub settings: RwLock<GameSettings>,
+    /// A create or join request is awaiting its response
+    pub pending: RwLock<bool>,
+    pub back_button: RwLock<RectangularButton>,
+    pub refresh_button: RwLock<RectangularButton>,
+    pub fewer_players_button: RwLock<RectangularButton>,
+    pub more_players_button: RwLock<RectangularButton>,
+    pub map_generator_button: RwLock<RectangularButton>,
+    pub map_size_button: RwLock<RectangularButton>,
+    pub create_button: RwLock<RectangularButton>,
+}
+
+impl GamesState {
+    pub const DEFAULT: GamesState = GamesState {
+        games: RwLock::new(Vec::new()),
+        hovered_row: RwLock::new(None),
+        settings: RwLock::new(GameSettings {
+            name: String::new(),
+            max_players: DEFAULT_MAX_PLAYERS,
+            map_generator: MapGeneratorType::DEFAULT,
+            map_size: MapSize::DEFAULT,
+        }),
+        pending: RwLock::new(false),
+        back_button: RwLock::new(RectangularButton::DEFAULT),
+        refresh_button: RwLock::new(RectangularButton::DEFAULT),
+        fewer_players_button: RwLock::new(RectangularButton::DEFAULT),
+        more_players_button: RwLock::new(RectangularButton::DEFAULT),
+        map_generator_button: RwLock::new(RectangularButton::DEFAULT),
+        map_size_button: RwLock::new(RectangularButton::DEFAULT),
+        create_button: RwLock::new(RectangularButton::DEFAULT),
+    };
+
+    /// The buttons, in the order they receive input
+    pub fn buttons(&self) -> [&RwLock<RectangularButton>; 7] {
+        [
+            &self.back_button,
+            &self.refresh_button,
+            &self.fewer_players_button,
+            &self.more_players_button,
+            &self.map_generator_button,
+            &self.map_size_button,
+            &self.create_button,
+        ]
+    }
+}
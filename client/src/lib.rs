@@ -1,3 +1,4 @@
+pub mod account;
 pub mod button;
 pub mod color;
 pub mod config;
@@ -6,10 +7,14 @@ pub mod engine;
 pub mod facility;
 pub mod font;
 pub mod game;
+pub mod games;
 pub mod input;
 pub mod map;
 pub mod math;
 pub mod player;
+pub mod profile;
+pub mod route;
+pub mod session;
 pub mod shader;
 pub mod stage;
 pub mod state;
@@ -1,13 +1,14 @@
-use crate::map::config::{HEX_COUNT_SQRT, HEX_HEIGHT, HEX_RADIUS, HEX_SIDE_LENGTH};
+use crate::map::config::{HEX_HEIGHT, HEX_RADIUS, HEX_SIDE_LENGTH};
 use crate::map::state::Hex;
-use crate::math::{SIN_FRAC_PI_3, SIN_FRAC_PI_6, TAN_FRAC_PI_6};
+use crate::math::{SIN_FRAC_PI_6, TAN_FRAC_PI_6};
 use crate::state::STATE;
 use raylib::prelude::Vector2;
 use shared::error::AppError;
-use std::f32::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_6};
+pub use shared::map::HexCoord;
+use shared::map::{Map, MapSize};
 use std::mem;
-use std::ops::{Add, Deref, DerefMut, Rem, Sub};
-use std::sync::LazyLock;
+use std::ops::{Deref, DerefMut, Rem, Sub};
+use std::sync::{LazyLock, RwLockReadGuard};
 
 #[derive(Debug, Copy, Clone)]
 pub struct MapCoord(pub Vector2);
@@ -42,11 +43,12 @@ impl MapCoord {
         let even_row: bool = j % 2 == 0;
         let mut i: i16 = ((self.x - if even_row { 0. } else { *HEX_HEIGHT / 2. }) / *HEX_HEIGHT) as i16;
 
+        let map_size: MapSize = map_size();
         while i < 0 {
-            i += HEX_COUNT_SQRT;
+            i += map_size.width;
         }
         while j < 0 {
-            j += HEX_COUNT_SQRT;
+            j += map_size.height;
         }
 
         HexCoord { i, j }
@@ -55,20 +57,21 @@ impl MapCoord {
     pub fn containing_hex(&self) -> Hex {
         // Rather than check the entire map, limit search to a subset of possible candidates based on the truncated hex coord conversion
         let hex_coord_rect: HexCoord = self.hex_coord_rect();
+        let map_size: MapSize = map_size();
         const N_CANDIDATES: usize = 4;
         let candidate_hex_coords: [HexCoord; N_CANDIDATES] = [
             hex_coord_rect,
             HexCoord {
-                i: (hex_coord_rect.i + 1).rem(HEX_COUNT_SQRT),
+                i: (hex_coord_rect.i + 1).rem(map_size.width),
                 j: hex_coord_rect.j,
             },
             HexCoord {
                 i: hex_coord_rect.i,
-                j: (hex_coord_rect.j + 1).rem(HEX_COUNT_SQRT),
+                j: (hex_coord_rect.j + 1).rem(map_size.height),
             },
             HexCoord {
-                i: (hex_coord_rect.i + 1).rem(HEX_COUNT_SQRT),
-                j: (hex_coord_rect.j + 1).rem(HEX_COUNT_SQRT),
+                i: (hex_coord_rect.i + 1).rem(map_size.width),
+                j: (hex_coord_rect.j + 1).rem(map_size.height),
             },
         ];
 
@@ -118,9 +121,9 @@ impl MapCoord {
 
         let matched_i: usize = matched_i.unwrap();
 
-        let hexes = STATE.stage.game.map.hexes.read().expect("poisoned global state");
-        let matched_hex: Hex = hexes[candidate_hex_coords[matched_i].map_index()];
-        drop(hexes);
+        let map: RwLockReadGuard<Map> = STATE.stage.game.map.map.read().expect("poisoned global state");
+        let matched_hex: Hex = map.hexes()[candidate_hex_coords[matched_i].map_index(map.size())];
+        drop(map);
 
         matched_hex
     }
@@ -129,33 +132,31 @@ impl MapCoord {
         let mut x: f32 = self.x - map_origin.x;
         let mut y: f32 = self.y - map_origin.y;
 
+        let map_size: MapSize = map_size();
         if x < -*HEX_HEIGHT / 2. {
-            x += get_map_width_pixels();
+            x += get_map_width_pixels(map_size);
         }
         if y < -HEX_RADIUS {
-            y += get_map_height_pixels();
+            y += get_map_height_pixels(map_size);
         }
 
         RenderCoord(Vector2 { x, y })
     }
 
     pub fn overflow_adjusted(&mut self) -> Self {
+        let map_size: MapSize = map_size();
         MapCoord(Vector2 {
-            x: self.x.rem_euclid(get_map_width_pixels()),
-            y: self.y.rem_euclid(get_map_height_pixels()),
+            x: self.x.rem_euclid(get_map_width_pixels(map_size)),
+            y: self.y.rem_euclid(get_map_height_pixels(map_size)),
         })
     }
 
-    pub fn toroidal_distance(&self, other: MapCoord) -> f32 {
-        let toroidal_diff: MapCoord = self.toroidal_diff(other);
-        (toroidal_diff.x.powi(2) + toroidal_diff.y.powi(2)).sqrt()
-    }
-
     pub fn toroidal_diff(&self, other: MapCoord) -> MapCoord {
+        let map_size: MapSize = map_size();
         let dx: f32 = (self.x - other.x).abs();
         let dy: f32 = (self.y - other.y).abs();
-        let min_dx: f32 = dx.min((get_map_width_pixels() - dx).abs());
-        let min_dy: f32 = dy.min((get_map_height_pixels() - dy).abs());
+        let min_dx: f32 = dx.min((get_map_width_pixels(map_size) - dx).abs());
+        let min_dy: f32 = dy.min((get_map_height_pixels(map_size) - dy).abs());
         MapCoord(Vector2 { x: min_dx, y: min_dy })
     }
 }
@@ -209,200 +210,63 @@ impl RenderCoord {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub struct HexCoord {
-    pub i: i16,
-    pub j: i16,
-}
-
-impl Default for HexCoord {
-    fn default() -> Self {
-        Self::DEFAULT
-    }
-}
-
-impl Add for HexCoord {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        HexCoord {
-            i: (self.i + rhs.i) % HEX_COUNT_SQRT,
-            j: (self.j + rhs.j) % HEX_COUNT_SQRT,
-        }
-    }
-}
-
-impl Sub for HexCoord {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        HexCoord {
-            i: self.i - rhs.i,
-            j: self.j - rhs.j,
-        }
-    }
+static VERTEX_DIFF: LazyLock<[Vector2; 6]> = LazyLock::new(|| {
+    [
+        Vector2 { x: 0., y: -HEX_RADIUS },
+        Vector2 {
+            x: *HEX_HEIGHT / 2.,
+            y: -HEX_RADIUS / 2.,
+        },
+        Vector2 {
+            x: *HEX_HEIGHT / 2.,
+            y: HEX_RADIUS / 2.,
+        },
+        Vector2 { x: 0., y: HEX_RADIUS },
+        Vector2 {
+            x: -*HEX_HEIGHT / 2.,
+            y: HEX_RADIUS / 2.,
+        },
+        Vector2 {
+            x: -*HEX_HEIGHT / 2.,
+            y: -HEX_RADIUS / 2.,
+        },
+    ]
+});
+
+/// Where a [HexCoord] lies when drawn, and the client's copy of the hex there.
+/// [HexCoord] itself is shared with the server, which has no notion of pixels.
+pub trait HexGeometry {
+    fn clone_map_hex(&self) -> Option<Hex>;
+    fn map_coord(&self) -> MapCoord;
+    fn hex_vertices(&self) -> [MapCoord; 6];
+    /// Find the two vertices shared between two hexes.
+    /// Returns [None] iff the hexes are not adjacent.
+    fn shared_vertices(&self, other: HexCoord) -> Option<[MapCoord; 2]>;
 }
 
-impl HexCoord {
-    pub const DEFAULT: HexCoord = HexCoord { i: 0, j: 0 };
-
-    /// Note: Order is important
-    const NEIGHBOR_DIFF_EVEN: [HexCoord; 6] = [
-        HexCoord { i: -1, j: -1 },
-        HexCoord { i: -1, j: 1 },
-        HexCoord { i: -1, j: 0 },
-        HexCoord { i: 1, j: 0 },
-        HexCoord { i: 0, j: -1 },
-        HexCoord { i: 0, j: 1 },
-    ];
-    /// Note: Order is important
-    const NEIGHBOR_DIFF_ODD: [HexCoord; 6] = [
-        HexCoord { i: 1, j: 1 },
-        HexCoord { i: 1, j: -1 },
-        HexCoord { i: -1, j: 0 },
-        HexCoord { i: 0, j: -1 },
-        HexCoord { i: 0, j: 1 },
-        HexCoord { i: 1, j: 0 },
-    ];
-    const VERTEX_DIFF: LazyLock<[Vector2; 6]> = LazyLock::new(|| {
-        [
-            Vector2 { x: 0., y: -HEX_RADIUS },
-            Vector2 {
-                x: *HEX_HEIGHT / 2.,
-                y: -HEX_RADIUS / 2.,
-            },
-            Vector2 {
-                x: *HEX_HEIGHT / 2.,
-                y: HEX_RADIUS / 2.,
-            },
-            Vector2 { x: 0., y: HEX_RADIUS },
-            Vector2 {
-                x: -*HEX_HEIGHT / 2.,
-                y: HEX_RADIUS / 2.,
-            },
-            Vector2 {
-                x: -*HEX_HEIGHT / 2.,
-                y: -HEX_RADIUS / 2.,
-            },
-        ]
-    });
-
-    pub fn clone_map_hex(&self) -> Option<Hex> {
-        let hexes = STATE.stage.game.map.hexes.read().expect("global state poisoned");
-        hexes.get(self.map_index()).map(|hex| hex.clone())
-    }
-
-    pub const fn map_index(&self) -> usize {
-        (self.i + self.j * HEX_COUNT_SQRT) as usize
+impl HexGeometry for HexCoord {
+    fn clone_map_hex(&self) -> Option<Hex> {
+        let map: RwLockReadGuard<Map> = STATE.stage.game.map.map.read().expect("global state poisoned");
+        map.get(*self).map(|hex| hex.clone())
     }
 
-    pub fn map_coord(&self) -> MapCoord {
+    fn map_coord(&self) -> MapCoord {
         let x: f32 = (f32::from(self.i) * *HEX_HEIGHT) + (if self.even_row() { 0_f32 } else { *HEX_HEIGHT / 2. });
         let y: f32 = f32::from(self.j) * (HEX_RADIUS + HEX_SIDE_LENGTH / 2.);
         MapCoord(Vector2 { x, y })
     }
 
-    pub fn even_row(&self) -> bool {
-        self.j % 2 == 0
-    }
-
-    pub fn neighbors(&self) -> [HexCoord; 6] {
-        let diffs: &[HexCoord; 6] = match self.even_row() {
-            true => &Self::NEIGHBOR_DIFF_EVEN,
-            false => &Self::NEIGHBOR_DIFF_ODD,
-        };
-
-        let mut neighbors: [HexCoord; 6] = [Self::DEFAULT; 6];
-        for i in 0..diffs.len() {
-            neighbors[i] = self.add(diffs[i])
-        }
-        neighbors
-    }
-
-    /// This method is slightly more efficient than checking if [other] would be contained by [Self::neighbors()].
-    pub fn is_neighbor(&self, other: HexCoord) -> bool {
-        let toroidal_diff: HexCoord = self.toroidal_diff(other);
-        if toroidal_diff.i + toroidal_diff.j == 1 {
-            return true;
-        }
-
-        let diffs: &[HexCoord] = match self.even_row() {
-            true => &Self::NEIGHBOR_DIFF_EVEN[0..2],
-            false => &Self::NEIGHBOR_DIFF_ODD[0..2],
-        };
-
-        for diff in diffs {
-            if self.add(*diff) == other {
-                return true;
-            }
-        }
-        false
-    }
-
-    pub fn step_distance(&self, other: HexCoord) -> i16 {
-        if other == *self {
-            return 0;
-        } else if self.is_neighbor(other) {
-            return 1;
-        }
-
-        let self_coord: MapCoord = self.map_coord();
-        let other_coord: MapCoord = other.map_coord();
-        let toroidal_distance: f32 = self_coord.toroidal_distance(other_coord);
-        let toroidal_diff: MapCoord = self_coord.toroidal_diff(other_coord);
-
-        let mut k: i16 = (toroidal_distance / HEX_RADIUS).ceil() as i16;
-        let theta: f32 = (toroidal_diff.y / toroidal_diff.x).atan();
-        debug_assert!(0. <= theta && theta <= FRAC_PI_2);
-
-        loop {
-            let p: f32 = if theta < FRAC_PI_3 {
-                (*HEX_HEIGHT * f32::from(k) * *SIN_FRAC_PI_3 as f32) / (FRAC_PI_6 - theta).cos()
-            } else {
-                (*HEX_HEIGHT * f32::from(k) * *SIN_FRAC_PI_3 as f32) / theta.sin()
-            };
-
-            if (p - toroidal_distance).abs() < 0.001 || p < toroidal_distance {
-                break;
-            } else {
-                k -= 1;
-            }
-        }
-
-        k
-    }
-
-    /// Determine if a hex's [HexCoord::step_distance()] is less than or equal to the given `step_distance`.
-    /// This implementation is more efficient than a simple inequality.
-    pub fn step_distance_le(&self, other: HexCoord, step_distance: i16) -> bool {
-        let toroidal_diff: HexCoord = self.toroidal_diff(other);
-        if toroidal_diff.i > step_distance || toroidal_diff.j > step_distance {
-            return false; // Discard the vast majority of hexes which lie outside a rectangular boundary
-        }
-
-        let self_coord: MapCoord = self.map_coord();
-        let other_coord: MapCoord = other.map_coord();
-        let toroidal_distance: f32 = self_coord.toroidal_distance(other_coord);
-
-        let max_euclidean_distance: f32 = f32::from(step_distance) * *HEX_HEIGHT;
-        if toroidal_distance > max_euclidean_distance + 0.001 {
-            // return false; // Discard a smaller number of hexes which lie outside a circular boundary
-        }
-
-        self.step_distance(other) <= step_distance
-    }
-
-    pub fn hex_vertices(&self) -> [MapCoord; 6] {
+    fn hex_vertices(&self) -> [MapCoord; 6] {
         let center: MapCoord = self.map_coord();
         let mut vertices: [MapCoord; 6] = unsafe { mem::zeroed() };
         for i in 0..vertices.len() {
-            vertices[i] = MapCoord(center.0 + Self::VERTEX_DIFF[i]);
+            vertices[i] = MapCoord(center.0 + VERTEX_DIFF[i]);
         }
         vertices
     }
 
-    /// Find the two vertices shared between two hexes.
-    /// Returns [None] iff the hexes are not adjacent.
-    pub fn shared_vertices(&self, other: HexCoord) -> Option<[MapCoord; 2]> {
-        if !self.is_neighbor(other) {
+    fn shared_vertices(&self, other: HexCoord) -> Option<[MapCoord; 2]> {
+        if !self.is_neighbor(other, map_size()) {
             return None;
         }
 
@@ -426,15 +290,6 @@ impl HexCoord {
 
         unreachable!()
     }
-
-    pub fn toroidal_diff(&self, other: HexCoord) -> HexCoord {
-        let di: i16 = self.i.abs_diff(other.i) as i16;
-        let dj: i16 = self.j.abs_diff(other.j) as i16;
-        HexCoord {
-            i: di.min(HEX_COUNT_SQRT - di),
-            j: dj.min(HEX_COUNT_SQRT - dj),
-        }
-    }
 }
 
 pub fn get_hex_count_width(pixels: f32) -> u16 {
@@ -457,10 +312,18 @@ pub fn get_hex_height_pixels(hex_count: i16) -> f32 {
     f32::from(hex_count) * (HEX_RADIUS + HEX_SIDE_LENGTH / 2.)
 }
 
-pub fn get_map_width_pixels() -> f32 {
-    get_hex_width_pixels(HEX_COUNT_SQRT)
+/// Size of the current game's map
+pub fn map_size() -> MapSize {
+    STATE.stage.game.map.map.read().expect("global state poisoned").size()
+}
+
+/// The distance after which the map repeats horizontally.
+/// Unlike [get_hex_width_pixels], the half hex by which odd rows stick out is included, as it wraps onto the first
+/// column.
+pub fn get_map_width_pixels(map_size: MapSize) -> f32 {
+    f32::from(map_size.width) * *HEX_HEIGHT
 }
 
-pub fn get_map_height_pixels() -> f32 {
-    get_hex_height_pixels(HEX_COUNT_SQRT)
+pub fn get_map_height_pixels(map_size: MapSize) -> f32 {
+    get_hex_height_pixels(map_size.height)
 }
@@ -1,16 +1,17 @@
 use crate::color::{
     DIFF_HOVER_HEX, DIFF_WITHIN_INFLUENCE, HEX_OUTLINE_ACCENTED_COLOR, HEX_OUTLINE_COLOR, MAP_BACKGROUND_COLOR,
 };
-use crate::map::config::{HEX_COUNT_SQRT, HEX_RADIUS, HEX_ROTATION};
+use crate::map::config::{HEX_RADIUS, HEX_ROTATION};
 use crate::map::coordinate;
-use crate::map::coordinate::HexCoord;
+use crate::map::coordinate::{HexCoord, HexGeometry};
 use crate::map::coordinate::{MapCoord, RenderCoord};
-use crate::map::state::{Hex, ResourceType};
+use crate::map::state::{Hex, ResourceColor, ResourceType};
 use crate::player::Player;
 use crate::state::STATE;
 use crate::{facility, math};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+use shared::map::MapSize;
 use std::sync::RwLockReadGuard;
 
 const HEX_SIDES: u8 = 6;
@@ -34,15 +35,16 @@ where
 {
     let screen_width: i32 = rl_draw.get_screen_width();
     let screen_height: i32 = rl_draw.get_screen_height();
+    let map_size: MapSize = coordinate::map_size();
     let origin_hex_coord: HexCoord = map_origin.hex_coord_rect();
     let min_hex_coord: HexCoord = HexCoord {
         i: if origin_hex_coord.i - 1 < 0 {
-            HEX_COUNT_SQRT - 1
+            map_size.width - 1
         } else {
             origin_hex_coord.i - 1
         },
         j: if origin_hex_coord.j - 1 < 0 {
-            HEX_COUNT_SQRT - 1
+            map_size.height - 1
         } else {
             origin_hex_coord.j - 1
         },
@@ -56,14 +58,14 @@ where
             callback(rl_draw, map_origin, hex_coord);
 
             hex_coord.i += 1;
-            if hex_coord.i >= HEX_COUNT_SQRT {
+            if hex_coord.i >= map_size.width {
                 hex_coord.i = 0;
             }
         }
 
         hex_coord.i = min_hex_coord.i;
         hex_coord.j += 1;
-        if hex_coord.j >= HEX_COUNT_SQRT {
+        if hex_coord.j >= map_size.height {
             hex_coord.j = 0;
         }
     }
@@ -78,14 +80,20 @@ fn draw_hex(rl_draw: &mut RaylibDrawHandle, map_origin: &MapCoord, hex_coord: He
 
     let selected_player_i: RwLockReadGuard<usize> = STATE.stage.game.player.selected.read().unwrap();
     let players: RwLockReadGuard<Vec<Player>> = STATE.stage.game.player.players.read().unwrap();
-    let selected_player: &Player = &players[*selected_player_i];
+    // There are no players until a game's map is loaded
+    let selected_player_o: Option<&Player> = players.get(*selected_player_i);
     drop(selected_player_i);
 
-    draw_hex_background(rl_draw, &hex, render_coord, selected_player);
+    draw_hex_background(rl_draw, &hex, render_coord, selected_player_o);
     draw_hex_outline(rl_draw, render_coord);
 }
 
-fn draw_hex_background(rl_draw: &mut RaylibDrawHandle, hex: &Hex, render_coord: RenderCoord, selected_player: &Player) {
+fn draw_hex_background(
+    rl_draw: &mut RaylibDrawHandle,
+    hex: &Hex,
+    render_coord: RenderCoord,
+    selected_player_o: Option<&Player>,
+) {
     let mut color: Color = hex.resource_type.color();
     let mut hovered: bool = false;
 
@@ -99,7 +107,7 @@ fn draw_hex_background(rl_draw: &mut RaylibDrawHandle, hex: &Hex, render_coord:
     drop(hovered_hex_coord);
 
     let mut influenced: bool = false;
-    if selected_player.within_influence(hex.hex_coord) {
+    if selected_player_o.is_some_and(|selected_player| selected_player.within_influence(hex.hex_coord)) {
         color = math::color_add(&color, &DIFF_WITHIN_INFLUENCE);
         influenced = true;
     }
@@ -123,11 +131,13 @@ fn draw_hex_outline(rl_draw: &mut RaylibDrawHandle, render_coord: RenderCoord) {
 fn draw_player_influence_outlines(rl_draw: &mut RaylibDrawHandle, map_origin: &MapCoord, hex_coord: HexCoord) {
     let selected_player_i: RwLockReadGuard<usize> = STATE.stage.game.player.selected.read().unwrap();
     let players: RwLockReadGuard<Vec<Player>> = STATE.stage.game.player.players.read().unwrap();
-    let selected_player: &Player = &players[*selected_player_i];
+    let Some(selected_player): Option<&Player> = players.get(*selected_player_i) else {
+        return;
+    };
     drop(selected_player_i);
 
     if selected_player.within_influence(hex_coord) {
-        let neighbors: [HexCoord; 6] = hex_coord.neighbors();
+        let neighbors: [HexCoord; 6] = hex_coord.neighbors(coordinate::map_size());
         for i in 0..neighbors.len() {
             let neighbor: HexCoord = neighbors[i];
             if selected_player.within_influence(neighbor) {
@@ -1,35 +1,26 @@
-use crate::map;
-use crate::map::config::HEX_COUNT_SQRT;
-use crate::map::coordinate::HexCoord;
-use crate::map::state::{Hex, ResourceType};
+use crate::map::coordinate::MapCoord;
+use crate::player;
 use crate::state::STATE;
-use map::config::HEX_COUNT;
+use shared::map::Map;
 use std::sync::RwLockWriteGuard;
+use uuid::Uuid;
 
-pub fn init_map() {
-    let mut hexes: RwLockWriteGuard<[Hex; HEX_COUNT as usize]> =
-        STATE.stage.game.map.hexes.write().expect("global state poisoned");
-    for i in 0..HEX_COUNT_SQRT {
-        for j in 0..HEX_COUNT_SQRT {
-            let hex_coord: HexCoord = HexCoord { i, j };
-            let resource_type: ResourceType = init_resource_type_from_hex_coord(&hex_coord);
-            let hex: Hex = Hex {
-                hex_coord,
-                resource_type,
-            };
-            let i: usize = hex.hex_coord.map_index();
-            hexes[i] = hex;
-        }
-    }
+/// Replace the displayed map with the game's map received from the server, and place the players at its start
+/// positions
+pub fn set_map(game_id: Uuid, map: &Map) {
+    *STATE.stage.game.map.map.write().expect("global state poisoned") = map.clone();
+    *STATE.stage.game.map.game_id.write().expect("global state poisoned") = Some(game_id);
+
+    // The view may lie beyond the edges of a smaller map than the last
+    let mut map_origin: RwLockWriteGuard<MapCoord> =
+        STATE.stage.game.map.map_origin.write().expect("global state poisoned");
+    *map_origin = map_origin.overflow_adjusted();
+    drop(map_origin);
+
+    player::init_players(map.starts());
 }
 
-// todo: implement planned strategy (plan.md)
-const fn init_resource_type_from_hex_coord(hex_coord: &HexCoord) -> ResourceType {
-    if (hex_coord.i % (HEX_COUNT_SQRT / 4)) == 10 && hex_coord.j % (HEX_COUNT_SQRT / 4) == 4 {
-        ResourceType::Metal
-    } else if hex_coord.i % (HEX_COUNT_SQRT / 4) == 2 && hex_coord.j % (HEX_COUNT_SQRT / 4) == 12 {
-        ResourceType::Oil
-    } else {
-        ResourceType::None
-    }
+/// The game whose map is shown, if any
+pub fn game_id() -> Option<Uuid> {
+    *STATE.stage.game.map.game_id.read().expect("global state poisoned")
 }
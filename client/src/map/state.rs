@@ -1,43 +1,37 @@
 use crate::color::{MAP_BACKGROUND_COLOR, METAL_BACKGROUND_COLOR, OIL_BACKGROUND_COLOR};
-use crate::map;
 use crate::map::{HexCoord, MapCoord};
 use raylib::color::Color;
-use std::sync::RwLock;
-use map::config::HEX_COUNT;
+use shared::map::{Map, MapSize};
+use std::sync::{LazyLock, RwLock};
+use uuid::Uuid;
+pub use shared::map::{Hex, ResourceType};
 
 #[derive(Debug)]
 pub struct MapState {
     pub map_origin: RwLock<MapCoord>,
-    pub hexes: RwLock<[Hex; HEX_COUNT as usize]>,
+    /// The current game's map, as received from the server. Empty until then.
+    pub map: LazyLock<RwLock<Map>>,
+    /// The game whose map is shown, if any
+    pub game_id: RwLock<Option<Uuid>>,
     pub hovered_hex_coord: RwLock<Option<HexCoord>>,
 }
 
 impl MapState {
     pub const DEFAULT: MapState = MapState {
         map_origin: RwLock::new(MapCoord::DEFAULT),
-        hexes: RwLock::new([Hex::DEFAULT; HEX_COUNT as usize]),
+        map: LazyLock::new(|| RwLock::new(Map::empty(MapSize::DEFAULT))),
+        game_id: RwLock::new(None),
         hovered_hex_coord: RwLock::new(None),
     };
 }
 
-#[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum ResourceType {
-    None = 0,
-    Metal,
-    Oil,
+/// Resource colors are the client's concern, so they are added to the shared [ResourceType]
+pub trait ResourceColor {
+    fn color(&self) -> Color;
 }
 
-impl Default for ResourceType {
-    fn default() -> Self {
-        Self::DEFAULT
-    }
-}
-
-impl ResourceType {
-    pub const DEFAULT: ResourceType = ResourceType::None;
-
-    pub const fn color(&self) -> Color {
+impl ResourceColor for ResourceType {
+    fn color(&self) -> Color {
         match self {
             ResourceType::None => MAP_BACKGROUND_COLOR,
             ResourceType::Metal => METAL_BACKGROUND_COLOR,
@@ -45,22 +39,3 @@ impl ResourceType {
         }
     }
 }
-
-#[derive(Debug, Copy, Clone)]
-pub struct Hex {
-    pub hex_coord: HexCoord,
-    pub resource_type: ResourceType,
-}
-
-impl Default for Hex {
-    fn default() -> Self {
-        Self::DEFAULT
-    }
-}
-
-impl Hex {
-    pub const DEFAULT: Hex = Hex {
-        hex_coord: HexCoord::DEFAULT,
-        resource_type: ResourceType::DEFAULT,
-    };
-}
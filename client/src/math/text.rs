@@ -11,3 +11,28 @@ pub fn centered_text_origin(center: Vector2, text: &str, font: WeakFont, font_si
         y: center.y - measure.y / 2.,
     }
 }
+
+/// Split text into lines no wider than the maximum width, breaking between words where possible.
+/// A single word wider than the maximum width is given its own line.
+pub fn wrap_text(text: &str, font: &WeakFont, font_size: f32, spacing: f32, max_width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for paragraph in text.lines() {
+        let mut line: String = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate: String = if line.is_empty() {
+                String::from(word)
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if !line.is_empty() && font.measure_text(&candidate, font_size, spacing).x > max_width {
+                lines.push(line);
+                line = String::from(word);
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -1,30 +1,29 @@
 use crate::facility::ControlCenter;
 use crate::facility::{FacilityCollection, FacilityState};
-use crate::map::HEX_COUNT_SQRT;
 use crate::map::HexCoord;
 use crate::player::Player;
 use crate::state::STATE;
 use std::sync::RwLockWriteGuard;
 
-pub fn init_players(player_count: u8) {
+/// Replace the players with one per start position, each with a control center there
+pub fn init_players(starts: &[HexCoord]) {
     let mut players: RwLockWriteGuard<Vec<Player>> =
         STATE.stage.game.player.players.write().expect("poisoned game state");
-    players.reserve_exact(player_count as usize);
+    players.clear();
+    players.reserve_exact(starts.len());
 
-    for p in 0..player_count {
+    for (p, start) in starts.iter().enumerate() {
         let mut player: Player = Player {
-            id: p,
+            id: p as u8,
             facilities: FacilityCollection::default(),
         };
-        let facility_location: HexCoord = HexCoord {
-            i: HEX_COUNT_SQRT / i16::from(player_count) * i16::from(p),
-            j: HEX_COUNT_SQRT / i16::from(player_count) * i16::from(p),
-        };
         let facility = ControlCenter {
-            location: facility_location,
+            location: *start,
             state: FacilityState::default(),
         };
         player.facilities.control_center_vec.push(facility);
         players.push(player);
     }
+    // Games may have fewer players than were previously shown
+    *STATE.stage.game.player.selected.write().expect("poisoned game state") = 0;
 }
@@ -11,7 +11,7 @@ pub struct PlayerState {
 impl PlayerState {
     pub const DEFAULT: PlayerState = PlayerState {
         players: RwLock::new(Vec::new()),
-        selected: RwLock::new(1),
+        selected: RwLock::new(0),
     };
 }
 
@@ -0,0 +1,167 @@
+//! The local player profile, which keeps the player logged in across launches.
+//! Stored as `key=value` lines at [crate::config::profile_path].
+
+use crate::config;
+use crate::state::STATE;
+use shared::error::AppError;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::RwLockWriteGuard;
+use uuid::Uuid;
+
+const USERNAME_KEY: &str = "username";
+const USER_ID_KEY: &str = "user_id";
+const ACCOUNT_TOKEN_KEY: &str = "account_token";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub username: Option<String>,
+    pub user_id: Option<Uuid>,
+    /// Issued by the server on login and presented when registering a session
+    pub account_token: Option<Uuid>,
+}
+
+impl Profile {
+    pub const DEFAULT: Profile = Profile {
+        username: None,
+        user_id: None,
+        account_token: None,
+    };
+
+    /// Unknown keys and malformed lines are skipped, so that older clients can read newer profiles.
+    pub fn parse(contents: &str) -> Profile {
+        let mut profile: Profile = Profile::DEFAULT;
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                USERNAME_KEY => profile.username = Some(value.trim().to_string()),
+                USER_ID_KEY => profile.user_id = Uuid::parse_str(value.trim()).ok(),
+                ACCOUNT_TOKEN_KEY => profile.account_token = Uuid::parse_str(value.trim()).ok(),
+                _ => log::debug!("Unknown profile key; [{}]", key),
+            }
+        }
+        profile
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut contents: String = String::new();
+        if let Some(username) = &self.username {
+            contents.push_str(&format!("{}={}\n", USERNAME_KEY, username));
+        }
+        if let Some(user_id) = &self.user_id {
+            contents.push_str(&format!("{}={}\n", USER_ID_KEY, user_id));
+        }
+        if let Some(account_token) = &self.account_token {
+            contents.push_str(&format!("{}={}\n", ACCOUNT_TOKEN_KEY, account_token));
+        }
+        contents
+    }
+}
+
+/// Read the profile from disk. A missing profile is left empty.
+pub fn load() -> Result<(), AppError> {
+    let path: PathBuf = config::profile_path();
+    let contents: String = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            log::info!("No profile found; [{}]", path.display());
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let profile: Profile = Profile::parse(&contents);
+    log::info!(
+        "Profile loaded; [{}] [username: {:?}]",
+        path.display(),
+        profile.username
+    );
+    *STATE.profile.write().unwrap() = profile;
+    Ok(())
+}
+
+fn save(profile: &Profile) -> Result<(), AppError> {
+    let path: PathBuf = config::profile_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file: File = open_private(&path)?;
+    file.write_all(profile.serialize().as_bytes())?;
+    Ok(())
+}
+
+/// The profile holds the bearer account token, so only the owner may read it
+#[cfg(unix)]
+fn open_private(path: &PathBuf) -> Result<File, AppError> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let file: File = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    // The mode only applies when the file is created, so profiles saved by older clients are restricted here
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &PathBuf) -> Result<File, AppError> {
+    Ok(OpenOptions::new().write(true).create(true).truncate(true).open(path)?)
+}
+
+pub fn username() -> Option<String> {
+    STATE.profile.read().unwrap().username.clone()
+}
+
+pub fn user_id() -> Option<Uuid> {
+    STATE.profile.read().unwrap().user_id
+}
+
+pub fn account_token() -> Option<Uuid> {
+    STATE.profile.read().unwrap().account_token
+}
+
+/// Remember the login, persisting it for future launches.
+pub fn log_in(username: String, user_id: Uuid, account_token: Uuid) {
+    let mut profile: RwLockWriteGuard<Profile> = STATE.profile.write().unwrap();
+    profile.username = Some(username);
+    profile.user_id = Some(user_id);
+    profile.account_token = Some(account_token);
+    if let Err(e) = save(&profile) {
+        log::error!("Failed to save profile; {:#}", e);
+    }
+}
+
+/// Forget the account token, e.g. once the server no longer accepts it.
+/// The username is kept to prefill the next login.
+pub fn log_out() {
+    let mut profile: RwLockWriteGuard<Profile> = STATE.profile.write().unwrap();
+    profile.user_id = None;
+    profile.account_token = None;
+    if let Err(e) = save(&profile) {
+        log::error!("Failed to save profile; {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let profile: Profile = Profile {
+            username: Some(String::from("player")),
+            user_id: Some(Uuid::from_u128(1)),
+            account_token: Some(Uuid::from_u128(2)),
+        };
+        assert_eq!(profile, Profile::parse(&profile.serialize()));
+        assert_eq!(Profile::DEFAULT, Profile::parse(&Profile::DEFAULT.serialize()));
+    }
+
+    #[test]
+    fn parse_skips_unknown_lines() {
+        let profile: Profile = Profile::parse("theme=dark\nnot a pair\naccount_token=invalid\nusername = player\n");
+        assert_eq!(Some(String::from("player")), profile.username);
+        assert_eq!(None, profile.account_token);
+    }
+}
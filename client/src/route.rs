@@ -0,0 +1,130 @@
+use crate::{connect, games, profile, session, window};
+use shared::error::AppError;
+use shared::network::monitor::RouteResult;
+use shared::network::protocol::{
+    Deliver, Error, Frame, GameUpdated, HelloAck, HelloReject, Operation, OperationType, ProtocolError,
+};
+use shared::network::request::Requester;
+use uuid::Uuid;
+
+pub async fn route_frame(requester: Requester, frame: Frame) -> RouteResult {
+    match frame.head.op_type {
+        OperationType::HelloAck => {
+            log::trace!("HelloAck received; [{}]", frame);
+            hello_ack(requester, frame)
+        }
+        OperationType::HelloReject => {
+            log::trace!("HelloReject received; [{}]", frame);
+            hello_reject(frame)
+        }
+        OperationType::Deliver => {
+            log::trace!("Deliver received; [{}]", frame);
+            deliver(requester, frame).await
+        }
+        OperationType::GameUpdated => {
+            log::trace!("GameUpdated received; [{}]", frame);
+            game_updated(frame)
+        }
+        OperationType::Error => {
+            log::trace!("Error received; [{}]", frame);
+            error(frame)
+        }
+        OperationType::ProtocolError => {
+            log::trace!("ProtocolError received; [{}]", frame);
+            protocol_error(frame)
+        }
+        _ => {
+            log::debug!("Unhandled frame; [{}]", frame);
+            RouteResult::Continue
+        }
+    }
+}
+
+fn hello_ack(requester: Requester, frame: Frame) -> RouteResult {
+    let hello_ack_r: Result<HelloAck, AppError> = HelloAck::from_frame(&frame);
+    let Ok(hello_ack) = hello_ack_r.inspect_err(|e| log::error!("Failed to parse HelloAck; {:#}", e)) else {
+        return RouteResult::Close;
+    };
+    log::info!(
+        "Handshake complete; [version: {}] [{}]",
+        hello_ack.protocol_version,
+        hello_ack.capabilities
+    );
+
+    connect::open_session(requester);
+    RouteResult::Continue
+}
+
+fn hello_reject(frame: Frame) -> RouteResult {
+    match HelloReject::from_frame(&frame) {
+        Ok(hello_reject) => log::error!(
+            "Server rejected connection; [server version: {}] [{}]",
+            hello_reject.protocol_version,
+            hello_reject.reason
+        ),
+        Err(e) => log::error!("Failed to parse HelloReject; {:#}", e),
+    }
+    RouteResult::Close
+}
+
+fn game_updated(frame: Frame) -> RouteResult {
+    let game_updated_r: Result<GameUpdated, AppError> = GameUpdated::from_frame(&frame);
+    let Ok(game_updated) = game_updated_r.inspect_err(|e| log::error!("Failed to parse GameUpdated; {:#}", e)) else {
+        return RouteResult::Continue;
+    };
+
+    let game_id: Uuid = game_updated.game.id;
+    if profile::user_id().is_some_and(|user_id| game_updated.game.players.contains(&user_id)) {
+        session::join_game(game_id);
+    } else {
+        session::leave_game(&game_id);
+    }
+    games::update_game(game_updated.game);
+    RouteResult::Continue
+}
+
+fn error(frame: Frame) -> RouteResult {
+    show_error(&frame);
+    RouteResult::Continue
+}
+
+/// Show an [Error] frame to the user, e.g. one received in response to a request.
+pub fn show_error(frame: &Frame) {
+    match Error::from_frame(frame) {
+        Ok(error) => {
+            log::error!("Server error; [{}] [{}]", error.code, error.message);
+            window::show_error(&error.code.to_string(), &error.message);
+        }
+        Err(e) => log::error!("Failed to parse Error; {:#}", e),
+    }
+}
+
+fn protocol_error(frame: Frame) -> RouteResult {
+    match ProtocolError::from_frame(&frame) {
+        Ok(protocol_error) => log::error!(
+            "Server did not accept frame; [op_code: {}] [{}]",
+            protocol_error.op_code,
+            protocol_error.reason
+        ),
+        Err(e) => log::error!("Failed to parse ProtocolError; {:#}", e),
+    }
+    RouteResult::Continue
+}
+
+/// Route the frame nested within a [Deliver], unless it was already received before reconnecting.
+async fn deliver(requester: Requester, frame: Frame) -> RouteResult {
+    let deliver_r: Result<Deliver, AppError> = Deliver::from_frame(&frame);
+    let Ok(deliver) = deliver_r.inspect_err(|e| log::error!("Failed to parse Deliver; {:#}", e)) else {
+        return RouteResult::Continue;
+    };
+    if !session::receive(deliver.sequence) {
+        log::debug!("Ignoring duplicate delivery; [sequence: {}]", deliver.sequence);
+        return RouteResult::Continue;
+    }
+
+    let inner_r: Result<Frame, AppError> = Frame::from_bytes(deliver.frame);
+    let Ok(inner) = inner_r.inspect_err(|e| log::error!("Failed to parse delivered frame; {:#}", e)) else {
+        return RouteResult::Continue;
+    };
+    Box::pin(route_frame(requester, inner)).await
+}
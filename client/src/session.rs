@@ -0,0 +1,69 @@
+use crate::state::STATE;
+use shared::network::protocol::Sequence;
+use std::sync::{RwLock, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// Survives reconnection so that the session can be resumed.
+#[derive(Debug)]
+pub struct SessionState {
+    /// Issued by the server on registration
+    pub token: RwLock<Option<Uuid>>,
+    /// The sequence of the last [shared::network::protocol::Deliver] frame received
+    pub last_sequence: RwLock<Sequence>,
+    pub games: RwLock<Vec<Uuid>>,
+}
+
+impl SessionState {
+    pub const DEFAULT: SessionState = SessionState {
+        token: RwLock::new(None),
+        last_sequence: RwLock::new(0),
+        games: RwLock::new(Vec::new()),
+    };
+}
+
+pub fn token() -> Option<Uuid> {
+    *STATE.session.token.read().unwrap()
+}
+
+pub fn last_sequence() -> Sequence {
+    *STATE.session.last_sequence.read().unwrap()
+}
+
+/// Begin a new session. Frames delivered to any previous session are no longer expected.
+pub fn open(token: Uuid) {
+    *STATE.session.token.write().unwrap() = Some(token);
+    *STATE.session.last_sequence.write().unwrap() = 0;
+    STATE.session.games.write().unwrap().clear();
+}
+
+pub fn close() {
+    *STATE.session.token.write().unwrap() = None;
+}
+
+pub fn set_games(games: Vec<Uuid>) {
+    *STATE.session.games.write().unwrap() = games;
+}
+
+/// Record that the user is now a player in the game.
+pub fn join_game(game_id: Uuid) {
+    let mut games: RwLockWriteGuard<Vec<Uuid>> = STATE.session.games.write().unwrap();
+    if !games.contains(&game_id) {
+        games.push(game_id);
+    }
+}
+
+/// Record that the user is no longer a player in the game.
+pub fn leave_game(game_id: &Uuid) {
+    STATE.session.games.write().unwrap().retain(|id| id != game_id);
+}
+
+/// Record a delivered frame's sequence.
+/// Returns false if the frame was already received, in which case it should be ignored.
+pub fn receive(sequence: Sequence) -> bool {
+    let mut last_sequence: RwLockWriteGuard<Sequence> = STATE.session.last_sequence.write().unwrap();
+    if sequence <= *last_sequence {
+        return false;
+    }
+    *last_sequence = sequence;
+    true
+}
@@ -1,9 +1,14 @@
+use crate::account::AccountState;
 use crate::game::GameState;
-use crate::input::{ClickResult, HoverResult, KeyPressResult, ScrollResult};
+use crate::games::GamesState;
+use crate::input::{
+    ClickHandler, ClickResult, HoverHandler, HoverResult, KeyPressHandler, KeyPressResult, ScrollHandler, ScrollResult,
+};
 use crate::map::RenderCoord;
 use crate::state::STATE;
 use crate::title::TitleState;
-use crate::{game, title};
+use crate::window::{ErrorWindow, Window};
+use crate::{account, game, games, title, window};
 use raylib::consts::KeyboardKey;
 use raylib::drawing::RaylibDrawHandle;
 use raylib::math::Vector2;
@@ -14,7 +19,11 @@ use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 pub struct StageState {
     pub current: RwLock<StageType>,
     pub next: RwLock<Option<StageType>>,
+    /// Shown over every stage; see [crate::window::show_error]
+    pub error: RwLock<ErrorWindow>,
     pub title: TitleState,
+    pub account: AccountState,
+    pub games: GamesState,
     pub game: GameState,
 }
 
@@ -22,7 +31,10 @@ impl StageState {
     pub const DEFAULT: StageState = StageState {
         current: RwLock::new(StageType::Title),
         next: RwLock::new(None),
+        error: RwLock::new(ErrorWindow::DEFAULT),
         title: TitleState::DEFAULT,
+        account: AccountState::DEFAULT,
+        games: GamesState::DEFAULT,
         game: GameState::DEFAULT,
     };
 }
@@ -30,11 +42,19 @@ impl StageState {
 #[derive(Debug, Copy, Clone)]
 pub enum StageType {
     Title,
+    Account,
+    Games,
     Game,
 }
 
 impl StageType {
     pub fn scroll(&self, rl: &mut RaylibHandle, scroll_v: Vector2) -> ScrollResult {
+        let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+        if let ScrollResult::Consume = error.scroll(rl, scroll_v) {
+            return ScrollResult::Consume;
+        }
+        drop(error);
+
         match self {
             StageType::Game => game::scroll(rl, scroll_v),
             _ => ScrollResult::Consume,
@@ -42,31 +62,59 @@ impl StageType {
     }
 
     pub fn click(&self, rl: &mut RaylibHandle, mouse_position: RenderCoord) -> ClickResult {
+        let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+        if let ClickResult::Consume = error.click(rl, mouse_position) {
+            return ClickResult::Consume;
+        }
+        drop(error);
+
         match self {
             StageType::Title => title::click(rl, mouse_position),
+            StageType::Account => account::click(rl, mouse_position),
+            StageType::Games => games::click(rl, mouse_position),
             StageType::Game => game::click(rl, mouse_position),
         }
     }
 
     pub fn hover(&self, rl: &mut RaylibHandle, mouse_position: RenderCoord) -> HoverResult {
+        let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+        if error.hover(rl, mouse_position) == HoverResult::Consume || error.is_open() {
+            return HoverResult::Consume;
+        }
+        drop(error);
+
         match self {
             StageType::Title => title::hover(rl, mouse_position),
+            StageType::Account => account::hover(rl, mouse_position),
+            StageType::Games => games::hover(rl, mouse_position),
             StageType::Game => game::hover(rl, mouse_position),
         }
     }
 
     pub fn key_press(&self, rl: &mut RaylibHandle, key: KeyboardKey) -> KeyPressResult {
+        let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+        if let KeyPressResult::Consume = error.key_press(rl, key) {
+            return KeyPressResult::Consume;
+        }
+        drop(error);
+
         match self {
+            StageType::Account => account::key_press(rl, key),
+            StageType::Games => games::key_press(rl, key),
             StageType::Game => game::key_press(rl, key),
-            _ => KeyPressResult::Pass,
+            StageType::Title => KeyPressResult::Pass,
         }
     }
 
+    /// The error window is drawn last so that it covers every stage
     pub fn draw(&self, rl_draw: &mut RaylibDrawHandle, rl_thread: &RaylibThread) {
         match self {
             StageType::Title => title::draw(rl_draw),
+            StageType::Account => account::draw(rl_draw),
+            StageType::Games => games::draw(rl_draw),
             StageType::Game => game::draw(rl_draw, rl_thread),
         }
+        window::draw_error_window(rl_draw, rl_thread);
     }
 }
 
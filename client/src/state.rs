@@ -1,10 +1,16 @@
+use crate::profile::Profile;
+use crate::session::SessionState;
 use crate::stage::StageState;
 use crate::texture::ScreenRenderTexture;
+use shared::network::request::Requester;
 use std::mem;
 use std::sync::RwLock;
 
 pub static STATE: State = State {
     stage: StageState::DEFAULT,
+    session: SessionState::DEFAULT,
+    profile: RwLock::new(Profile::DEFAULT),
+    connection: RwLock::new(None),
     frame_counter: RwLock::new(0),
     screen_texture: RwLock::new(unsafe { mem::zeroed() }),
 };
@@ -12,6 +18,10 @@ pub static STATE: State = State {
 #[derive(Debug)]
 pub struct State {
     pub stage: StageState,
+    pub session: SessionState,
+    pub profile: RwLock<Profile>,
+    /// The current connection to the server; see [crate::connect::spawn_connection]
+    pub connection: RwLock<Option<Requester>>,
     pub frame_counter: RwLock<u64>,
     pub screen_texture: RwLock<ScreenRenderTexture>,
 }
@@ -64,7 +64,7 @@ fn draw_main_buttons(rl_draw: &mut RaylibDrawHandle) {
     }
 }
 
-fn draw_button(rl_draw: &mut RaylibDrawHandle, button: &RectangularButton) {
+pub fn draw_button(rl_draw: &mut RaylibDrawHandle, button: &RectangularButton) {
     let position: Vector2 = math::rect_origin(button.rectangle);
     let dimensions: Vector2 = math::rect_dimensions(button.rectangle);
     let mut bg_color: Color = WINDOW_BACKGROUND_COLOR;
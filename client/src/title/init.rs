@@ -2,13 +2,13 @@ use crate::button::RectangularButton;
 use crate::font::DEFAULT_FONT_SPACING;
 use crate::input::ClickResult;
 use crate::map::RenderCoord;
-use crate::stage;
 use crate::stage::StageType;
 use crate::state::STATE;
 use crate::title::{
     BUTTON_FONT_SIZE, BUTTON_INTERNAL_MARGIN, BUTTON_TEXT_ARRAY, BUTTON_VERTICAL_MARGIN, SCREEN_MARGIN,
     TITLE_VERTICAL_MARGIN,
 };
+use crate::{account, games, stage};
 use raylib::RaylibHandle;
 use raylib::ffi::GetFontDefault;
 use raylib::math::{Rectangle, Vector2};
@@ -76,7 +76,7 @@ fn create_games_button(rl: &mut RaylibHandle) -> RectangularButton {
         y: rl.get_screen_height() as f32 / 2. - BUTTON_DIMENSIONS.y / 2. + TITLE_VERTICAL_MARGIN / 2.,
     };
 
-    let button: RectangularButton = RectangularButton::new_with_text(
+    let mut button: RectangularButton = RectangularButton::new_with_text(
         BUTTON_TEXT_ARRAY[0],
         Rectangle {
             x: position.x,
@@ -85,6 +85,13 @@ fn create_games_button(rl: &mut RaylibHandle) -> RectangularButton {
             height: BUTTON_DIMENSIONS.y,
         },
     );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        games::enter();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
     button
 }
 
@@ -97,7 +104,7 @@ fn create_account_button(rl: &mut RaylibHandle) -> RectangularButton {
             + BUTTON_VERTICAL_MARGIN,
     };
 
-    let button: RectangularButton = RectangularButton::new_with_text(
+    let mut button: RectangularButton = RectangularButton::new_with_text(
         BUTTON_TEXT_ARRAY[1],
         Rectangle {
             x: position.x,
@@ -106,5 +113,12 @@ fn create_account_button(rl: &mut RaylibHandle) -> RectangularButton {
             height: BUTTON_DIMENSIONS.y,
         },
     );
+
+    fn on_click(_rl: &mut RaylibHandle, _mouse_position: RenderCoord) -> ClickResult {
+        account::enter();
+        ClickResult::Consume
+    }
+    button.on_click = on_click;
+
     button
 }
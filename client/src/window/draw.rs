@@ -32,9 +32,12 @@ pub fn draw_overlay_windows(rl_draw: &mut RaylibDrawHandle, rl_thread: &RaylibTh
     let pause: RwLockReadGuard<PauseWindow> = STATE.stage.game.window.pause.read().unwrap();
     pause.draw(rl_draw, rl_thread);
     drop(pause);
+}
 
-    let error: RwLockReadGuard<ErrorWindow> = STATE.stage.game.window.error.read().unwrap();
-    // error.draw(rl_draw, rl_thread);
+/// The error window may open during any stage and is drawn over it; see [crate::window::show_error]
+pub fn draw_error_window(rl_draw: &mut RaylibDrawHandle, rl_thread: &RaylibThread) {
+    let error: RwLockReadGuard<ErrorWindow> = STATE.stage.error.read().unwrap();
+    error.draw(rl_draw, rl_thread);
     drop(error);
 }
 
@@ -1,16 +1,38 @@
 use crate::button::RectangularButton;
+use crate::font::DEFAULT_FONT_SPACING;
+use crate::input::{ClickHandler, ClickResult, HoverHandler, HoverResult};
 use crate::map::RenderCoord;
+use crate::math;
+use crate::state::STATE;
+use crate::window;
 use crate::window::state::WindowLayer;
-use crate::window::Window;
+use crate::window::{BORDER_GAP, BUTTON_WIDTH, Window};
 use raylib::drawing::RaylibDrawHandle;
-use raylib::math::Vector2;
-use raylib::RaylibThread;
+use raylib::math::{Rectangle, Vector2};
+use raylib::{RaylibHandle, RaylibThread};
+use std::sync::RwLockWriteGuard;
+
+const ERROR_WIDTH: f32 = 460.;
+const ERROR_INTERNAL_MARGIN: f32 = 14.;
+const TITLE_FONT_SIZE: f32 = 24.;
+const MESSAGE_FONT_SIZE: f32 = 16.;
+const MESSAGE_LINE_HEIGHT: f32 = 20.;
+const DISMISS_TEXT: &str = "Dismiss";
+const DISMISS_FONT_SIZE: f32 = 18.;
+const DISMISS_DIMENSIONS: Vector2 = Vector2 { x: 110., y: 38. };
 
 #[derive(Debug)]
 pub struct ErrorWindow {
     pub origin: Option<RenderCoord>,
     pub dimensions: Vector2,
     pub close_button: RectangularButton,
+    pub dismiss_button: RectangularButton,
+    pub title: String,
+    pub message: String,
+    /// The message wrapped to the width of the window
+    pub lines: Vec<String>,
+    /// Set by [show_error]; the window opens during the next update, which has access to the [RaylibHandle]
+    pending: bool,
 }
 
 impl Window for ErrorWindow {
@@ -20,6 +42,7 @@ impl Window for ErrorWindow {
 
     fn close(&mut self) {
         self.origin = None;
+        self.pending = false;
     }
 
     fn origin(&self) -> Option<RenderCoord> {
@@ -42,7 +65,22 @@ impl Window for ErrorWindow {
         &mut self.close_button
     }
 
-    fn draw_content(&self, _rl_draw: &mut RaylibDrawHandle, _rl_thread: &RaylibThread) {}
+    fn draw_content(&self, rl_draw: &mut RaylibDrawHandle, _rl_thread: &RaylibThread) {
+        self.draw_title(rl_draw);
+        self.draw_message(rl_draw);
+        self.draw_dismiss_button(rl_draw);
+    }
+
+    fn handle_window_click(&mut self, rl: &mut RaylibHandle, mouse_position: RenderCoord) -> ClickResult {
+        if let ClickResult::Consume = self.dismiss_button.click(rl, mouse_position) {
+            self.close();
+        }
+        ClickResult::Consume
+    }
+
+    fn handle_window_hover(&mut self, rl: &mut RaylibHandle, mouse_position: RenderCoord) -> HoverResult {
+        self.dismiss_button.hover(rl, mouse_position)
+    }
 }
 
 impl ErrorWindow {
@@ -50,5 +88,143 @@ impl ErrorWindow {
         origin: None,
         dimensions: Vector2 { x: 0., y: 0. },
         close_button: RectangularButton::DEFAULT,
+        dismiss_button: RectangularButton::DEFAULT,
+        title: String::new(),
+        message: String::new(),
+        lines: Vec::new(),
+        pending: false,
+    };
+
+    pub fn open(&mut self, rl: &mut RaylibHandle) {
+        let text_width: f32 = ERROR_WIDTH - (BORDER_GAP + ERROR_INTERNAL_MARGIN) * 2. - BUTTON_WIDTH;
+        self.lines = math::wrap_text(
+            &self.message,
+            &rl.get_font_default(),
+            MESSAGE_FONT_SIZE,
+            DEFAULT_FONT_SPACING,
+            text_width,
+        );
+
+        let height: f32 = (BORDER_GAP + ERROR_INTERNAL_MARGIN) * 2.
+            + TITLE_FONT_SIZE
+            + ERROR_INTERNAL_MARGIN
+            + self.lines.len() as f32 * MESSAGE_LINE_HEIGHT
+            + ERROR_INTERNAL_MARGIN
+            + DISMISS_DIMENSIONS.y;
+        self.dimensions = Vector2 {
+            x: ERROR_WIDTH,
+            y: height.max(BORDER_GAP * 2. + BUTTON_WIDTH),
+        };
+        let origin: Vector2 = Vector2 {
+            x: (rl.get_screen_width() as f32 - self.dimensions.x) / 2.,
+            y: (rl.get_screen_height() as f32 - self.dimensions.y) / 2.,
+        };
+        self.origin = Some(RenderCoord(origin));
+        self.close_button = RectangularButton::new(window::side_button_rectangle(self, 0));
+        self.dismiss_button = RectangularButton::new_with_text(
+            DISMISS_TEXT,
+            Rectangle {
+                x: origin.x + (self.dimensions.x - DISMISS_DIMENSIONS.x) / 2.,
+                y: origin.y + self.dimensions.y - BORDER_GAP - ERROR_INTERNAL_MARGIN - DISMISS_DIMENSIONS.y,
+                width: DISMISS_DIMENSIONS.x,
+                height: DISMISS_DIMENSIONS.y,
+            },
+        );
+        self.pending = false;
+    }
+}
+
+/// Show an error to the user. May be called from any thread, e.g. while routing a frame.
+/// A newer error replaces any error already shown.
+pub fn show_error(title: &str, message: &str) {
+    let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+    error.title = String::from(title);
+    error.message = String::from(message);
+    error.origin = None;
+    error.pending = true;
+}
+
+/// Open the error window if an error was shown since the last update.
+pub fn update_error_window(rl: &mut RaylibHandle) {
+    let mut error: RwLockWriteGuard<ErrorWindow> = STATE.stage.error.write().unwrap();
+    if error.pending {
+        error.open(rl);
+    }
+}
+
+mod draw {
+    use crate::color::TEXT_COLOR;
+    use crate::font::DEFAULT_FONT_SPACING;
+    use crate::math;
+    use crate::window;
+    use crate::window::error::{
+        DISMISS_FONT_SIZE, ERROR_INTERNAL_MARGIN, MESSAGE_FONT_SIZE, MESSAGE_LINE_HEIGHT, TITLE_FONT_SIZE,
     };
+    use crate::window::{BORDER_GAP, ErrorWindow};
+    use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+    use raylib::math::{Rectangle, Vector2};
+
+    impl ErrorWindow {
+        pub fn draw_title(&self, rl_draw: &mut RaylibDrawHandle) {
+            let position: Vector2 = self.origin.unwrap().0
+                + Vector2 {
+                    x: BORDER_GAP + ERROR_INTERNAL_MARGIN,
+                    y: BORDER_GAP + ERROR_INTERNAL_MARGIN,
+                };
+            rl_draw.draw_text_ex(
+                rl_draw.get_font_default(),
+                &self.title,
+                position,
+                TITLE_FONT_SIZE,
+                DEFAULT_FONT_SPACING,
+                TEXT_COLOR,
+            );
+        }
+
+        pub fn draw_message(&self, rl_draw: &mut RaylibDrawHandle) {
+            let origin: Vector2 = self.origin.unwrap().0
+                + Vector2 {
+                    x: BORDER_GAP + ERROR_INTERNAL_MARGIN,
+                    y: BORDER_GAP + ERROR_INTERNAL_MARGIN * 2. + TITLE_FONT_SIZE,
+                };
+            for (i, line) in self.lines.iter().enumerate() {
+                rl_draw.draw_text_ex(
+                    rl_draw.get_font_default(),
+                    line,
+                    origin
+                        + Vector2 {
+                            x: 0.,
+                            y: i as f32 * MESSAGE_LINE_HEIGHT,
+                        },
+                    MESSAGE_FONT_SIZE,
+                    DEFAULT_FONT_SPACING,
+                    TEXT_COLOR,
+                );
+            }
+        }
+
+        pub fn draw_dismiss_button(&self, rl_draw: &mut RaylibDrawHandle) {
+            window::draw_side_button(rl_draw, &self.dismiss_button);
+
+            let rectangle: Rectangle = self.dismiss_button.rectangle;
+            let text: &str = self.dismiss_button.text.as_deref().unwrap_or_default();
+            rl_draw.draw_text_ex(
+                rl_draw.get_font_default(),
+                text,
+                math::centered_text_origin(
+                    Vector2 {
+                        x: rectangle.x + rectangle.width / 2.,
+                        y: rectangle.y + rectangle.height / 2.,
+                    },
+                    text,
+                    rl_draw.get_font_default(),
+                    DISMISS_FONT_SIZE,
+                    DEFAULT_FONT_SPACING,
+                ),
+                DISMISS_FONT_SIZE,
+                DEFAULT_FONT_SPACING,
+                TEXT_COLOR,
+            );
+        }
+    }
 }
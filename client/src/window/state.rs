@@ -1,12 +1,11 @@
 use crate::state::STATE;
-use crate::window::error::ErrorWindow;
 use crate::window::hex::HexWindow;
 use crate::window::pause::PauseWindow;
 use crate::window::Window;
 use std::sync::RwLock;
 
-pub const WINDOW_LAYERS: [&'static RwLock<dyn Window>; 3] = [
-    &STATE.stage.game.window.error,
+/// The game windows below the error window, which every stage handles first; see [crate::stage::StageState]
+pub const WINDOW_LAYERS: [&'static RwLock<dyn Window>; 2] = [
     &STATE.stage.game.window.pause,
     &STATE.stage.game.window.hex,
 ];
@@ -21,14 +20,12 @@ pub enum WindowLayer {
 
 #[derive(Debug)]
 pub struct WindowState {
-    pub error: RwLock<ErrorWindow>,
     pub pause: RwLock<PauseWindow>,
     pub hex: RwLock<HexWindow>,
 }
 
 impl WindowState {
     pub const DEFAULT: WindowState = WindowState {
-        error: RwLock::new(ErrorWindow::DEFAULT),
         pause: RwLock::new(PauseWindow::DEFAULT),
         hex: RwLock::new(HexWindow::DEFAULT),
     };
@@ -41,7 +38,7 @@ mod tests {
     #[test]
     fn window_layers() {
         for i in 0..WINDOW_LAYERS.len() {
-            assert_eq!(i, WINDOW_LAYERS[i].read().unwrap().layer() as usize);
+            assert_eq!(i + 1, WINDOW_LAYERS[i].read().unwrap().layer() as usize);
         }
     }
 }
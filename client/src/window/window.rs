@@ -116,7 +116,7 @@ mod tests {
     fn validate_window_layers() {
         for i in 0..WINDOW_LAYERS.len() {
             let window: RwLockReadGuard<dyn Window> = WINDOW_LAYERS[i].read().unwrap();
-            debug_assert_eq!(i as u8, window.layer() as u8);
+            debug_assert_eq!(i as u8 + 1, window.layer() as u8);
         }
     }
 }
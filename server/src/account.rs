@@ -0,0 +1,332 @@
+//! Player accounts. A username and password are exchanged for an account token, which the client keeps across
+//! launches and presents when registering a session.
+//! Accounts are held in memory and written through to [Storage], from which they are restored on startup.
+
+use crate::storage::Storage;
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use shared::error::AppError;
+use shared::random::{random_bytes, random_uuid};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::{Arc, LazyLock, RwLock};
+use tokio::sync::{OnceCell, Semaphore, SemaphorePermit};
+use uuid::Uuid;
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 24;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Bounds the work done hashing a single request
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+/// Bounds the hashing work which unauthenticated peers can cause at once; further requests wait for a permit
+pub const MAX_CONCURRENT_HASHES: usize = 4;
+
+pub static ACCOUNTS: LazyLock<Accounts> = LazyLock::new(Accounts::default);
+
+static HASHING_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_HASHES);
+/// Verified against when the username is unknown, so that the response takes as long as for a known username
+static DUMMY_PASSWORD_HASH: OnceCell<String> = OnceCell::const_new();
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccountError {
+    InvalidUsername,
+    InvalidPassword,
+    UsernameTaken,
+    /// The username is unknown or the password does not match.
+    /// The two are not distinguished so that usernames cannot be probed.
+    InvalidCredentials,
+    Hashing,
+    Storage,
+}
+
+impl Display for AccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountError::InvalidUsername => write!(
+                f,
+                "Usernames must be {} to {} letters, digits or underscores",
+                MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH
+            ),
+            AccountError::InvalidPassword => write!(
+                f,
+                "Passwords must be {} to {} characters",
+                MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+            ),
+            AccountError::UsernameTaken => write!(f, "Username is taken"),
+            AccountError::InvalidCredentials => write!(f, "Incorrect username or password"),
+            AccountError::Hashing => write!(f, "Failed to hash password"),
+            AccountError::Storage => write!(f, "Failed to save account"),
+        }
+    }
+}
+
+pub type AccountResult<T> = Result<T, AccountError>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub user_id: Uuid,
+    /// As entered when the account was created
+    pub username: String,
+    /// PHC string, including the salt and parameters
+    pub(crate) password_hash: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Accounts {
+    state: RwLock<AccountsState>,
+    /// Absent until [Accounts::restore], in which case accounts are held in memory only
+    storage: RwLock<Option<Arc<dyn Storage>>>,
+}
+
+#[derive(Debug, Default)]
+struct AccountsState {
+    by_user_id: HashMap<Uuid, Account>,
+    /// Keyed by the lowercase username, so that names differing only in case cannot coexist
+    user_ids_by_username: HashMap<String, Uuid>,
+    user_ids_by_token: HashMap<Uuid, Uuid>,
+}
+
+impl Accounts {
+    /// Load the saved accounts and tokens, then write every change through to the storage.
+    /// Returns the number of accounts loaded.
+    pub fn restore(&self, storage: Arc<dyn Storage>) -> Result<usize, AppError> {
+        let accounts: Vec<Account> = storage.load_accounts()?;
+        let account_tokens: Vec<(Uuid, Uuid)> = storage.load_account_tokens()?;
+
+        let mut state = self.state.write().unwrap();
+        for account in accounts {
+            state.user_ids_by_username.insert(account.username.to_lowercase(), account.user_id);
+            state.by_user_id.insert(account.user_id, account);
+        }
+        state.user_ids_by_token.extend(account_tokens);
+        *self.storage.write().unwrap() = Some(storage);
+        Ok(state.by_user_id.len())
+    }
+
+    /// Create an account and log in to it.
+    /// Returns the account and a new account token.
+    pub async fn create(&self, username: &str, password: &str) -> AccountResult<(Account, Uuid)> {
+        validate_username(username)?;
+        validate_password(password)?;
+        // Fail fast, before the expensive hash; checked again once it has been computed
+        if self.state.read().unwrap().user_ids_by_username.contains_key(&username.to_lowercase()) {
+            return Err(AccountError::UsernameTaken);
+        }
+
+        let password_hash: String = hash_password(password.to_string()).await?;
+        let account: Account = Account {
+            user_id: random_uuid(),
+            username: username.to_string(),
+            password_hash,
+        };
+
+        let mut state = self.state.write().unwrap();
+        if state.user_ids_by_username.contains_key(&username.to_lowercase()) {
+            return Err(AccountError::UsernameTaken);
+        }
+        self.persist(|storage| storage.insert_account(&account))?;
+        state.user_ids_by_username.insert(username.to_lowercase(), account.user_id);
+        state.by_user_id.insert(account.user_id, account.clone());
+        let account_token: Uuid = self.issue_token(&mut state, account.user_id)?;
+        Ok((account, account_token))
+    }
+
+    /// Returns the account and a new account token.
+    /// Tokens issued by earlier logins remain valid, so that a player may use several clients.
+    pub async fn login(&self, username: &str, password: &str) -> AccountResult<(Account, Uuid)> {
+        let account_o: Option<Account> = {
+            let state = self.state.read().unwrap();
+            state
+                .user_ids_by_username
+                .get(&username.to_lowercase())
+                .and_then(|user_id| state.by_user_id.get(user_id))
+                .cloned()
+        };
+        let Some(account) = account_o else {
+            let dummy_hash: &String = DUMMY_PASSWORD_HASH.get_or_try_init(dummy_password_hash).await?;
+            verify_password(password.to_string(), dummy_hash.clone()).await?;
+            return Err(AccountError::InvalidCredentials);
+        };
+
+        if !verify_password(password.to_string(), account.password_hash.clone()).await? {
+            return Err(AccountError::InvalidCredentials);
+        }
+        let account_token: Uuid = self.issue_token(&mut self.state.write().unwrap(), account.user_id)?;
+        Ok((account, account_token))
+    }
+
+    /// The account which was issued the token
+    pub fn authenticate(&self, account_token: &Uuid) -> Option<Account> {
+        let state = self.state.read().unwrap();
+        state.user_ids_by_token.get(account_token).and_then(|user_id| state.by_user_id.get(user_id)).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.read().unwrap().by_user_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.read().unwrap().by_user_id.is_empty()
+    }
+
+    fn issue_token(&self, state: &mut AccountsState, user_id: Uuid) -> AccountResult<Uuid> {
+        let account_token: Uuid = random_uuid();
+        self.persist(|storage| storage.insert_account_token(&account_token, &user_id))?;
+        state.user_ids_by_token.insert(account_token, user_id);
+        Ok(account_token)
+    }
+
+    /// Write a change to the storage, if there is one, before it is applied in memory.
+    fn persist(&self, write: impl FnOnce(&dyn Storage) -> Result<(), AppError>) -> AccountResult<()> {
+        let Some(storage) = self.storage.read().unwrap().clone() else {
+            return Ok(());
+        };
+        write(storage.as_ref()).map_err(|e| {
+            log::error!("Failed to save account; {:#}", e);
+            AccountError::Storage
+        })
+    }
+}
+
+fn validate_username(username: &str) -> AccountResult<()> {
+    let length: usize = username.chars().count();
+    let valid_length: bool = (MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&length);
+    if !valid_length || !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AccountError::InvalidUsername);
+    }
+    Ok(())
+}
+
+fn validate_password(password: &str) -> AccountResult<()> {
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password.chars().count()) {
+        return Err(AccountError::InvalidPassword);
+    }
+    Ok(())
+}
+
+async fn acquire_hashing_permit() -> AccountResult<SemaphorePermit<'static>> {
+    HASHING_PERMITS.acquire().await.map_err(|e| {
+        log::error!("Failed to acquire hashing permit; {}", e);
+        AccountError::Hashing
+    })
+}
+
+async fn dummy_password_hash() -> AccountResult<String> {
+    hash_password(random_uuid().to_string()).await
+}
+
+/// Hashing is deliberately slow, so it runs on the blocking thread pool.
+async fn hash_password(password: String) -> AccountResult<String> {
+    let _permit_g: SemaphorePermit = acquire_hashing_permit().await?;
+    let hash_r = tokio::task::spawn_blocking(move || {
+        let salt: SaltString = SaltString::encode_b64(&random_bytes::<16>())?;
+        Argon2::default().hash_password(password.as_bytes(), &salt).map(|hash| hash.to_string())
+    })
+    .await;
+
+    match hash_r {
+        Ok(Ok(password_hash)) => Ok(password_hash),
+        Ok(Err(e)) => {
+            log::error!("Failed to hash password; {}", e);
+            Err(AccountError::Hashing)
+        }
+        Err(e) => {
+            log::error!("Password hashing task failed; {}", e);
+            Err(AccountError::Hashing)
+        }
+    }
+}
+
+async fn verify_password(password: String, password_hash: String) -> AccountResult<bool> {
+    let _permit_g: SemaphorePermit = acquire_hashing_permit().await?;
+    let verify_r = tokio::task::spawn_blocking(move || {
+        let password_hash: PasswordHash = PasswordHash::new(&password_hash)?;
+        Ok::<bool, argon2::password_hash::Error>(
+            Argon2::default().verify_password(password.as_bytes(), &password_hash).is_ok(),
+        )
+    })
+    .await;
+
+    match verify_r {
+        Ok(Ok(verified)) => Ok(verified),
+        Ok(Err(e)) => {
+            log::error!("Stored password hash is invalid; {}", e);
+            Err(AccountError::Hashing)
+        }
+        Err(e) => {
+            log::error!("Password verification task failed; {}", e);
+            Err(AccountError::Hashing)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sqlite::SqliteStorage;
+
+    #[tokio::test]
+    async fn create_and_login() {
+        let accounts: Accounts = Accounts::default();
+        let (account, account_token): (Account, Uuid) = accounts.create("Player_1", "correct horse").await.unwrap();
+        assert_eq!("Player_1", account.username);
+        assert_ne!("correct horse", account.password_hash);
+        assert_eq!(Some(account.clone()), accounts.authenticate(&account_token));
+
+        let (logged_in, login_token): (Account, Uuid) = accounts.login("player_1", "correct horse").await.unwrap();
+        assert_eq!(account.user_id, logged_in.user_id);
+        assert_ne!(account_token, login_token);
+        assert_eq!(Some(account), accounts.authenticate(&login_token));
+        assert!(accounts.authenticate(&random_uuid()).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_requests() {
+        let accounts: Accounts = Accounts::default();
+        accounts.create("player", "correct horse").await.unwrap();
+
+        assert_eq!(
+            Err(AccountError::UsernameTaken),
+            accounts.create("PLAYER", "other password").await
+        );
+        assert_eq!(
+            Err(AccountError::InvalidUsername),
+            accounts.create("no", "correct horse").await
+        );
+        assert_eq!(
+            Err(AccountError::InvalidUsername),
+            accounts.create("white space", "correct horse").await
+        );
+        assert_eq!(
+            Err(AccountError::InvalidPassword),
+            accounts.create("other", "short").await
+        );
+        assert_eq!(
+            Err(AccountError::InvalidCredentials),
+            accounts.login("player", "wrong password").await
+        );
+        assert_eq!(
+            Err(AccountError::InvalidCredentials),
+            accounts.login("stranger", "correct horse").await
+        );
+        assert!(DUMMY_PASSWORD_HASH.initialized());
+        assert_eq!(1, accounts.len());
+    }
+
+    #[tokio::test]
+    async fn restores_from_storage() {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open_in_memory().unwrap());
+        let accounts: Accounts = Accounts::default();
+        assert_eq!(0, accounts.restore(storage.clone()).unwrap());
+        let (account, account_token): (Account, Uuid) = accounts.create("player", "correct horse").await.unwrap();
+
+        let restored: Accounts = Accounts::default();
+        assert_eq!(1, restored.restore(storage).unwrap());
+        assert_eq!(Some(account), restored.authenticate(&account_token));
+        assert!(restored.login("PLAYER", "correct horse").await.is_ok());
+        assert_eq!(
+            Err(AccountError::UsernameTaken),
+            restored.create("Player", "other password").await
+        );
+    }
+}
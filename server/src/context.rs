@@ -0,0 +1,49 @@
+use crate::manager::ManagerChannel;
+use crate::session::Session;
+use shared::network::handshake::Capabilities;
+use shared::network::transport::PeerAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::time::Instant;
+
+/// Per-connection state shared between the frame routes of a single client.
+#[derive(Debug)]
+pub struct ConnectionContext {
+    pub peer_addr: Arc<PeerAddr>,
+    /// Capabilities negotiated during the handshake. None until the handshake has completed.
+    pub capabilities: RwLock<Option<Capabilities>>,
+    /// When a frame was last received from the client
+    last_seen: Mutex<Instant>,
+    /// The session opened by [shared::network::protocol::Register] or [shared::network::protocol::Resume]
+    pub session: RwLock<Option<Arc<Session>>>,
+    /// Used to create, list, join and leave games
+    pub manager: ManagerChannel,
+}
+
+impl ConnectionContext {
+    pub fn new(peer_addr: Arc<PeerAddr>, manager: ManagerChannel) -> Self {
+        ConnectionContext {
+            peer_addr,
+            capabilities: RwLock::new(None),
+            last_seen: Mutex::new(Instant::now()),
+            session: RwLock::new(None),
+            manager,
+        }
+    }
+
+    /// Record that the client is alive.
+    pub fn touch(&self) {
+        *self.last_seen.lock().unwrap() = Instant::now();
+    }
+
+    pub fn last_seen(&self) -> Instant {
+        *self.last_seen.lock().unwrap()
+    }
+
+    pub fn session(&self) -> Option<Arc<Session>> {
+        self.session.read().unwrap().clone()
+    }
+
+    pub fn handshake_complete(&self) -> bool {
+        self.capabilities.read().unwrap().is_some()
+    }
+}
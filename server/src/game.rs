@@ -0,0 +1,451 @@
+//! Each game runs as an isolated task which owns its authoritative state.
+//! Players' commands reach it through a [GameChannel]; the simulation advances once per tick.
+//! Given [Storage], every change is appended to the game's journal and the state is snapshotted periodically and on
+//! shutdown, so that the game can be restored; see [crate::journal].
+
+use crate::journal::{GameEvent, JournalEntry, Snapshot};
+use crate::storage::Storage;
+use shared::clock::{GameClock, Ticker};
+use shared::error::AppError;
+use shared::network::codec::{ByteReader, Decode, Encode};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+pub const GAME_CHANNEL_CAPACITY: usize = 128;
+/// Ticks between snapshots of a game's state
+pub const SNAPSHOT_INTERVAL_TICKS: u64 = 300;
+/// Ticks between writes of the journal entries buffered since the last write.
+/// Bounds the commands and ticks lost if the server crashes.
+pub const JOURNAL_FLUSH_INTERVAL_TICKS: u64 = 10;
+
+/// Authoritative state of a single game
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameState {
+    /// Number of ticks simulated so far
+    pub tick: u64,
+    /// User IDs, in order of joining
+    pub players: Vec<Uuid>,
+}
+
+impl Encode for GameState {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.tick.encode(buffer);
+        self.players.encode(buffer);
+    }
+}
+
+impl Decode for GameState {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameState {
+            tick: u64::decode(reader)?,
+            players: Vec::<Uuid>::decode(reader)?,
+        })
+    }
+}
+
+impl GameState {
+    /// The only way the state changes besides the tick advancing, so that replaying a journal reproduces it exactly
+    pub fn apply(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PlayerAdded { user_id } => self.players.push(*user_id),
+            GameEvent::PlayerRemoved { user_id } => self.players.retain(|id| id != user_id),
+            GameEvent::Checkpoint => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GameCommand {
+    AddPlayer { user_id: Uuid },
+    RemovePlayer { user_id: Uuid },
+    Snapshot { reply: oneshot::Sender<GameState> },
+}
+
+/// Handle to a game task. The task stops once every handle has been dropped.
+#[derive(Debug, Clone)]
+pub struct GameChannel {
+    sender: mpsc::Sender<GameCommand>,
+}
+
+impl GameChannel {
+    pub async fn add_player(&self, user_id: Uuid) -> Result<(), AppError> {
+        self.send(GameCommand::AddPlayer { user_id }).await
+    }
+
+    pub async fn remove_player(&self, user_id: Uuid) -> Result<(), AppError> {
+        self.send(GameCommand::RemovePlayer { user_id }).await
+    }
+
+    pub async fn snapshot(&self) -> Result<GameState, AppError> {
+        let (reply, receiver): (oneshot::Sender<GameState>, oneshot::Receiver<GameState>) = oneshot::channel();
+        self.send(GameCommand::Snapshot { reply }).await?;
+        receiver.await.map_err(|_| AppError::new("Game dropped the command"))
+    }
+
+    async fn send(&self, command: GameCommand) -> Result<(), AppError> {
+        self.sender.send(command).await.map_err(|_| AppError::new("Game is not running"))
+    }
+}
+
+pub struct Game {
+    pub id: Uuid,
+    clock: Arc<dyn GameClock>,
+    /// Clock time between ticks
+    tick_interval: Duration,
+    receiver: mpsc::Receiver<GameCommand>,
+    snapshot: Snapshot,
+    storage: Option<Arc<dyn Storage>>,
+    /// Entries not yet written to the storage
+    journal: Vec<JournalEntry>,
+}
+
+impl Game {
+    pub fn new(
+        id: Uuid,
+        clock: Arc<dyn GameClock>,
+        tick_interval: Duration,
+        storage: Option<Arc<dyn Storage>>,
+    ) -> (Game, GameChannel) {
+        Self::restore(id, clock, tick_interval, storage, Snapshot::default())
+    }
+
+    /// Resume a game from a previous state, e.g. its latest snapshot with the journal since replayed onto it.
+    pub fn restore(
+        id: Uuid,
+        clock: Arc<dyn GameClock>,
+        tick_interval: Duration,
+        storage: Option<Arc<dyn Storage>>,
+        snapshot: Snapshot,
+    ) -> (Game, GameChannel) {
+        let (sender, receiver) = mpsc::channel(GAME_CHANNEL_CAPACITY);
+        let game: Game = Game {
+            id,
+            clock,
+            tick_interval,
+            receiver,
+            snapshot,
+            storage,
+            journal: Vec::new(),
+        };
+        (game, GameChannel { sender })
+    }
+
+    /// Process commands and advance the simulation until shutdown is signalled or every [GameChannel] has been dropped.
+    /// The journal is written and a final snapshot taken on shutdown, but not once the channels are dropped, as the game
+    /// has then been deleted.
+    pub async fn run(mut self, mut cancellation_receiver: broadcast::Receiver<()>) {
+        // Missed ticks are caught up, so that the simulation keeps pace with the clock
+        let mut ticker: Ticker = Ticker::new(self.clock.clone(), self.tick_interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.tick(),
+                command = self.receiver.recv() => match command {
+                    Some(command) => self.handle(command),
+                    None => break,
+                },
+                _ = cancellation_receiver.recv() => {
+                    self.save_snapshot();
+                    break;
+                }
+            }
+        }
+
+        log::debug!("Game terminated; [{}] [tick: {}]", self.id, self.snapshot.state.tick);
+    }
+
+    /// Add and remove players so that they match the game's memberships, journaling the changes.
+    pub fn sync_players(&mut self, players: &[Uuid]) {
+        let removed: Vec<Uuid> =
+            self.snapshot.state.players.iter().filter(|id| !players.contains(id)).copied().collect();
+        for user_id in removed {
+            self.record(GameEvent::PlayerRemoved { user_id });
+        }
+        for user_id in players {
+            if !self.snapshot.state.players.contains(user_id) {
+                self.record(GameEvent::PlayerAdded { user_id: *user_id });
+            }
+        }
+    }
+
+    /// Ticks are not journaled; replay advances to the tick of each entry.
+    fn tick(&mut self) {
+        self.snapshot.state.tick += 1;
+        let tick: u64 = self.snapshot.state.tick;
+        if tick.is_multiple_of(SNAPSHOT_INTERVAL_TICKS) {
+            self.save_snapshot();
+        } else if tick.is_multiple_of(JOURNAL_FLUSH_INTERVAL_TICKS) {
+            self.flush_journal();
+        }
+    }
+
+    fn record(&mut self, event: GameEvent) {
+        let entry: JournalEntry = self.snapshot.record(event);
+        // Without storage there is nowhere to write the journal
+        if self.storage.is_some() {
+            self.journal.push(entry);
+        }
+    }
+
+    /// Write the buffered journal entries, ending with a checkpoint at the current tick.
+    /// They are kept for the next attempt if the write fails.
+    fn flush_journal(&mut self) {
+        if self.storage.is_none() {
+            return;
+        }
+        let tick: u64 = self.snapshot.state.tick;
+        if self.journal.last().is_none_or(|entry| entry.tick < tick) {
+            self.record(GameEvent::Checkpoint);
+        }
+
+        let Some(storage) = &self.storage else {
+            return;
+        };
+        match storage.append_journal(&self.id, &self.journal) {
+            Ok(_) => self.journal.clear(),
+            Err(e) => log::error!("Failed to append to game journal; [{}] {:#}", self.id, e),
+        }
+    }
+
+    /// The journal is written first, so that it is never behind the snapshot.
+    fn save_snapshot(&mut self) {
+        self.flush_journal();
+        let Some(storage) = &self.storage else {
+            return;
+        };
+        match storage.save_snapshot(&self.id, &self.snapshot) {
+            Ok(_) => log::trace!(
+                "Game snapshot saved; [{}] [tick: {}]",
+                self.id,
+                self.snapshot.state.tick
+            ),
+            Err(e) => log::error!("Failed to save game snapshot; [{}] {:#}", self.id, e),
+        }
+    }
+
+    fn handle(&mut self, command: GameCommand) {
+        log::trace!("Game command; [{}] [{:?}]", self.id, command);
+        match command {
+            GameCommand::AddPlayer { user_id } => {
+                if !self.snapshot.state.players.contains(&user_id) {
+                    self.record(GameEvent::PlayerAdded { user_id });
+                }
+            }
+            GameCommand::RemovePlayer { user_id } => {
+                if self.snapshot.state.players.contains(&user_id) {
+                    self.record(GameEvent::PlayerRemoved { user_id });
+                }
+            }
+            GameCommand::Snapshot { reply } => {
+                let _ = reply.send(self.snapshot.state.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal;
+    use crate::storage::sqlite::SqliteStorage;
+    use shared::clock::{RealTimeClock, SimulatedClock};
+    use shared::map::{MapGeneratorType, MapSize};
+    use shared::network::protocol::{GameSettings, GameStatus, GameSummary};
+    use shared::random::{GameSeed, random_uuid};
+    use tokio::task::JoinHandle;
+    use tokio::time;
+
+    const TICK_INTERVAL: Duration = Duration::from_millis(5);
+
+    fn clock() -> Arc<dyn GameClock> {
+        Arc::new(RealTimeClock::new())
+    }
+
+    #[tokio::test]
+    async fn ticks_and_handles_commands() {
+        let (_cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(random_uuid(), clock(), TICK_INTERVAL, None);
+        tokio::spawn(game.run(cancellation_receiver));
+
+        let user_id: Uuid = random_uuid();
+        channel.add_player(user_id).await.unwrap();
+        channel.add_player(user_id).await.unwrap();
+        assert_eq!(vec![user_id], channel.snapshot().await.unwrap().players);
+
+        let tick: u64 = channel.snapshot().await.unwrap().tick;
+        time::sleep(TICK_INTERVAL * 4).await;
+        assert!(channel.snapshot().await.unwrap().tick > tick);
+
+        channel.remove_player(user_id).await.unwrap();
+        assert!(channel.snapshot().await.unwrap().players.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stops_on_cancellation() {
+        let (cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(random_uuid(), clock(), TICK_INTERVAL, None);
+        let task: JoinHandle<()> = tokio::spawn(game.run(cancellation_receiver));
+
+        cancellation_sender.send(()).unwrap();
+        time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+        assert!(channel.snapshot().await.is_err());
+    }
+
+    fn saved_game(storage: &Arc<dyn Storage>) -> Uuid {
+        let game_id: Uuid = random_uuid();
+        storage
+            .save_game(
+                &GameSummary {
+                    id: game_id,
+                    settings: GameSettings {
+                        name: String::from("Saved"),
+                        max_players: 2,
+                        map_generator: MapGeneratorType::DEFAULT,
+                        map_size: MapSize::DEFAULT,
+                    },
+                    players: Vec::new(),
+                    status: GameStatus::InProgress,
+                },
+                GameSeed(1),
+            )
+            .unwrap();
+        game_id
+    }
+
+    #[tokio::test]
+    async fn snapshots_on_cancellation() {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open_in_memory().unwrap());
+        let game_id: Uuid = saved_game(&storage);
+
+        let (cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let snapshot: Snapshot = Snapshot {
+            sequence: 7,
+            state: GameState {
+                tick: 7,
+                players: vec![random_uuid()],
+            },
+        };
+        let (game, channel): (Game, GameChannel) = Game::restore(
+            game_id,
+            clock(),
+            Duration::from_secs(60),
+            Some(storage.clone()),
+            snapshot.clone(),
+        );
+        let task: JoinHandle<()> = tokio::spawn(game.run(cancellation_receiver));
+        assert_eq!(snapshot.state.players, channel.snapshot().await.unwrap().players);
+
+        cancellation_sender.send(()).unwrap();
+        time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+        let saved: Snapshot = storage.load_snapshot(&game_id).unwrap().unwrap();
+        assert_eq!(snapshot.state.players, saved.state.players);
+        // The interval's first tick completes immediately, but may race the cancellation
+        assert!(saved.state.tick >= snapshot.state.tick);
+        assert_eq!(
+            saved.sequence,
+            snapshot.sequence + storage.load_journal(&game_id, 7).unwrap().len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn journal_replays_to_state() {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open_in_memory().unwrap());
+        let game_id: Uuid = saved_game(&storage);
+
+        let (cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(game_id, clock(), TICK_INTERVAL, Some(storage.clone()));
+        let task: JoinHandle<()> = tokio::spawn(game.run(cancellation_receiver));
+        let (first, second): (Uuid, Uuid) = (random_uuid(), random_uuid());
+        channel.add_player(first).await.unwrap();
+        time::sleep(TICK_INTERVAL * 2).await;
+        channel.add_player(second).await.unwrap();
+        channel.remove_player(first).await.unwrap();
+        // Refused commands are not journaled
+        channel.remove_player(first).await.unwrap();
+        time::sleep(TICK_INTERVAL * 2).await;
+        let state: GameState = channel.snapshot().await.unwrap();
+
+        cancellation_sender.send(()).unwrap();
+        time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+        let entries: Vec<JournalEntry> = storage.load_journal(&game_id, 0).unwrap();
+        let removed: usize =
+            entries.iter().filter(|entry| matches!(entry.event, GameEvent::PlayerRemoved { .. })).count();
+        assert_eq!(1, removed);
+        assert_eq!(
+            state,
+            journal::replay_game(storage.as_ref(), &game_id, state.tick).unwrap()
+        );
+        let saved: GameState = storage.load_snapshot(&game_id).unwrap().unwrap().state;
+        assert_eq!(
+            saved,
+            journal::replay_game(storage.as_ref(), &game_id, saved.tick).unwrap()
+        );
+        // Besides the commands, only checkpoints are journaled
+        let commands: usize = entries.iter().filter(|entry| entry.event != GameEvent::Checkpoint).count();
+        assert_eq!(3, commands);
+    }
+
+    #[tokio::test]
+    async fn checkpoints_ticks_without_commands() {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open_in_memory().unwrap());
+        let game_id: Uuid = saved_game(&storage);
+
+        let (_cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(game_id, clock(), TICK_INTERVAL, Some(storage.clone()));
+        let task: JoinHandle<()> = tokio::spawn(game.run(cancellation_receiver));
+        channel.add_player(random_uuid()).await.unwrap();
+        while storage.load_journal(&game_id, 1).unwrap().len() < 2 {
+            time::sleep(TICK_INTERVAL).await;
+        }
+        // A crash takes no final snapshot
+        task.abort();
+
+        let entries: Vec<JournalEntry> = storage.load_journal(&game_id, 0).unwrap();
+        assert!(entries[1..].iter().all(|entry| entry.event == GameEvent::Checkpoint));
+        let restored: Snapshot = journal::replay(Snapshot::default(), &entries, u64::MAX).unwrap();
+        assert!(restored.state.tick >= 2 * JOURNAL_FLUSH_INTERVAL_TICKS);
+        assert!(restored.state.tick.is_multiple_of(JOURNAL_FLUSH_INTERVAL_TICKS));
+    }
+
+    #[tokio::test]
+    async fn stops_when_channels_dropped() {
+        let (_cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(random_uuid(), clock(), TICK_INTERVAL, None);
+        let task: JoinHandle<()> = tokio::spawn(game.run(cancellation_receiver));
+
+        drop(channel);
+        time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+    }
+
+    async fn wait_for_tick(channel: &GameChannel, tick: u64) {
+        time::timeout(Duration::from_secs(5), async {
+            while channel.snapshot().await.unwrap().tick < tick {
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fast_forwards_a_week() {
+        const TICK_INTERVAL: Duration = Duration::from_secs(60);
+        const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+        let clock: Arc<SimulatedClock> = Arc::new(SimulatedClock::paused());
+        let (_cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (game, channel): (Game, GameChannel) = Game::new(random_uuid(), clock.clone(), TICK_INTERVAL, None);
+        tokio::spawn(game.run(cancellation_receiver));
+
+        // The first tick completes immediately, once the game is running
+        wait_for_tick(&channel, 1).await;
+        clock.step(WEEK);
+        let expected: u64 = WEEK.as_secs() / TICK_INTERVAL.as_secs() + 1;
+        wait_for_tick(&channel, expected).await;
+
+        // Paused, the game does not advance past the clock
+        time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(expected, channel.snapshot().await.unwrap().tick);
+    }
+}
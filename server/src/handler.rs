@@ -0,0 +1,93 @@
+//! Registry of typed frame handlers, keyed by op code.
+//! Each handler receives its operation already decoded; see [Handlers::register].
+
+use crate::account::AccountError;
+use crate::context::ConnectionContext;
+use crate::manager::ManagerError;
+use futures::future::BoxFuture;
+use shared::error::AppError;
+use shared::network::connection::WriteBufferT;
+use shared::network::monitor::RouteResult;
+use shared::network::protocol::{ErrorCode, Frame, OpCode, Operation};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+pub type HandlerResult = Result<RouteResult, HandlerError>;
+
+/// A failed handler, reported to the client as an [shared::network::protocol::Error] with the code.
+#[derive(Debug)]
+pub struct HandlerError {
+    pub code: ErrorCode,
+    pub error: AppError,
+}
+
+impl HandlerError {
+    pub fn new(code: ErrorCode, error: AppError) -> Self {
+        HandlerError { code, error }
+    }
+}
+
+impl From<AppError> for HandlerError {
+    fn from(error: AppError) -> Self {
+        HandlerError::new(ErrorCode::Internal, error)
+    }
+}
+
+impl From<AccountError> for HandlerError {
+    fn from(error: AccountError) -> Self {
+        let code: ErrorCode = match error {
+            AccountError::Hashing | AccountError::Storage => ErrorCode::Internal,
+            _ => ErrorCode::Rejected,
+        };
+        HandlerError::new(code, AppError::new(&error.to_string()))
+    }
+}
+
+impl From<ManagerError> for HandlerError {
+    fn from(error: ManagerError) -> Self {
+        HandlerError::new(ErrorCode::Rejected, AppError::new(&error.to_string()))
+    }
+}
+
+/// Decodes a frame and runs the handler registered for its operation.
+pub type Handler =
+    Box<dyn Fn(Frame, Arc<ConnectionContext>, WriteBufferT) -> BoxFuture<'static, HandlerResult> + Send + Sync>;
+
+#[derive(Default)]
+pub struct Handlers {
+    by_op_code: HashMap<OpCode, Handler>,
+}
+
+impl Handlers {
+    /// Register the handler for operations of type `T`.
+    /// Panics if a handler is already registered for the operation.
+    pub fn register<T, F, Fut>(&mut self, handler: F) -> &mut Self
+    where
+        T: Operation + Debug + Send + 'static,
+        F: Fn(T, Arc<ConnectionContext>, WriteBufferT) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        let handler: Arc<F> = Arc::new(handler);
+        let boxed: Handler = Box::new(move |frame, context, write_buffer| {
+            let handler: Arc<F> = handler.clone();
+            Box::pin(async move {
+                let operation: T = T::from_frame(&frame).map_err(|e| HandlerError::new(ErrorCode::Malformed, e))?;
+                log::debug!("parsed frame; [{:?}]", operation);
+                handler(operation, context, write_buffer).await
+            })
+        });
+
+        let previous: Option<Handler> = self.by_op_code.insert(T::OP_CODE, boxed);
+        assert!(
+            previous.is_none(),
+            "Handler already registered; [op_code: {}]",
+            T::OP_CODE
+        );
+        self
+    }
+
+    pub fn get(&self, op_code: &OpCode) -> Option<&Handler> {
+        self.by_op_code.get(op_code)
+    }
+}
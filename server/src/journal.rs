@@ -0,0 +1,199 @@
+//! Every change to a game's state is an event, applied by [GameState::apply] and appended to the game's journal.
+//! Ticks are not journaled individually: each entry records the tick it was applied at, and replay advances the tick
+//! to it. A [GameEvent::Checkpoint] on each journal write records the ticks which passed without commands.
+//! Replaying the journal from a [Snapshot] deterministically rebuilds the state at any later tick, which recovers
+//! the changes made since the latest snapshot and reproduces a game exactly when debugging.
+
+use crate::game::GameState;
+use crate::storage::Storage;
+use shared::error::AppError;
+use shared::network::codec::{ByteReader, Decode, Encode};
+use uuid::Uuid;
+
+const PLAYER_ADDED: u8 = 1;
+const PLAYER_REMOVED: u8 = 2;
+const CHECKPOINT: u8 = 3;
+
+/// An accepted player command, or a checkpoint recording that the game reached the entry's tick.
+/// Checkpoints stop a restored game from rewinding to the tick of its last command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    PlayerAdded { user_id: Uuid },
+    PlayerRemoved { user_id: Uuid },
+    Checkpoint,
+}
+
+impl Encode for GameEvent {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        match self {
+            GameEvent::PlayerAdded { user_id } => {
+                PLAYER_ADDED.encode(buffer);
+                user_id.encode(buffer);
+            }
+            GameEvent::PlayerRemoved { user_id } => {
+                PLAYER_REMOVED.encode(buffer);
+                user_id.encode(buffer);
+            }
+            GameEvent::Checkpoint => CHECKPOINT.encode(buffer),
+        }
+    }
+}
+
+impl Decode for GameEvent {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        match u8::decode(reader)? {
+            PLAYER_ADDED => Ok(GameEvent::PlayerAdded {
+                user_id: Uuid::decode(reader)?,
+            }),
+            PLAYER_REMOVED => Ok(GameEvent::PlayerRemoved {
+                user_id: Uuid::decode(reader)?,
+            }),
+            CHECKPOINT => Ok(GameEvent::Checkpoint),
+            tag => Err(AppError::new(&format!("Invalid game event; [{}]", tag))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Position in the game's journal, starting from 1
+    pub sequence: u64,
+    /// The game's tick when the event was applied
+    pub tick: u64,
+    pub event: GameEvent,
+}
+
+/// A game's state together with the number of journal entries applied to reach it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub sequence: u64,
+    pub state: GameState,
+}
+
+impl Snapshot {
+    /// Apply the next event, returning the entry to append to the journal.
+    pub fn record(&mut self, event: GameEvent) -> JournalEntry {
+        self.state.apply(&event);
+        self.sequence += 1;
+        JournalEntry {
+            sequence: self.sequence,
+            tick: self.state.tick,
+            event,
+        }
+    }
+
+    /// Advance to the entry's tick and apply it, as read back from the journal.
+    /// Fails if the entry does not follow on from this snapshot or precedes its tick, either of which means the journal
+    /// and the simulation have diverged.
+    pub fn replay(&mut self, entry: &JournalEntry) -> Result<(), AppError> {
+        if entry.sequence != self.sequence + 1 {
+            return Err(AppError::new(&format!(
+                "Journal entry out of sequence; [expected: {}] [found: {}]",
+                self.sequence + 1,
+                entry.sequence
+            )));
+        }
+        if entry.tick < self.state.tick {
+            return Err(AppError::new(&format!(
+                "Replay diverged from journal; [sequence: {}] [entry tick: {}] [replayed tick: {}]",
+                entry.sequence, entry.tick, self.state.tick
+            )));
+        }
+        self.state.tick = entry.tick;
+        self.state.apply(&entry.event);
+        self.sequence = entry.sequence;
+        Ok(())
+    }
+}
+
+/// Replay entries onto the snapshot, stopping before the first entry past `until_tick`.
+pub fn replay(mut snapshot: Snapshot, entries: &[JournalEntry], until_tick: u64) -> Result<Snapshot, AppError> {
+    for entry in entries.iter().take_while(|entry| entry.tick <= until_tick) {
+        snapshot.replay(entry)?;
+    }
+    Ok(snapshot)
+}
+
+/// Rebuild a game's state at the tick from its whole journal, ignoring any snapshot.
+pub fn replay_game(storage: &dyn Storage, game_id: &Uuid, tick: u64) -> Result<GameState, AppError> {
+    let entries: Vec<JournalEntry> = storage.load_journal(game_id, 0)?;
+    let mut state: GameState = replay(Snapshot::default(), &entries, tick)?.state;
+    // The ticks since the last entry passed without events
+    state.tick = tick;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::random::random_uuid;
+
+    /// Record each event at its tick
+    fn record(events: Vec<(u64, GameEvent)>) -> Vec<JournalEntry> {
+        let mut snapshot: Snapshot = Snapshot::default();
+        events
+            .into_iter()
+            .map(|(tick, event)| {
+                snapshot.state.tick = tick;
+                snapshot.record(event)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn event_round_trip() {
+        for event in [
+            GameEvent::PlayerAdded { user_id: random_uuid() },
+            GameEvent::PlayerRemoved { user_id: random_uuid() },
+            GameEvent::Checkpoint,
+        ] {
+            let mut buffer: Vec<u8> = Vec::new();
+            event.encode(&mut buffer);
+            let mut reader: ByteReader = ByteReader::new(&buffer);
+            assert_eq!(event, GameEvent::decode(&mut reader).unwrap());
+            reader.finish().unwrap();
+        }
+        assert!(GameEvent::decode(&mut ByteReader::new(&[0])).is_err());
+        assert!(GameEvent::decode(&mut ByteReader::new(&[4])).is_err());
+    }
+
+    #[test]
+    fn replays_until_tick() {
+        let (first, second): (Uuid, Uuid) = (random_uuid(), random_uuid());
+        let entries: Vec<JournalEntry> = record(vec![
+            (0, GameEvent::PlayerAdded { user_id: first }),
+            (1, GameEvent::PlayerAdded { user_id: second }),
+            (1, GameEvent::PlayerRemoved { user_id: first }),
+            (5, GameEvent::PlayerAdded { user_id: first }),
+        ]);
+
+        let at_zero: Snapshot = replay(Snapshot::default(), &entries, 0).unwrap();
+        assert_eq!(1, at_zero.sequence);
+        assert_eq!(vec![first], at_zero.state.players);
+
+        let at_four: Snapshot = replay(Snapshot::default(), &entries, 4).unwrap();
+        assert_eq!(3, at_four.sequence);
+        assert_eq!(1, at_four.state.tick);
+        assert_eq!(vec![second], at_four.state.players);
+
+        // Resuming from an intermediate snapshot matches replaying from the start
+        let resumed: Snapshot = replay(at_four, &entries[3..], u64::MAX).unwrap();
+        assert_eq!(replay(Snapshot::default(), &entries, u64::MAX).unwrap(), resumed);
+        assert_eq!(5, resumed.state.tick);
+        assert_eq!(vec![second, first], resumed.state.players);
+    }
+
+    #[test]
+    fn detects_divergence() {
+        let user_id: Uuid = random_uuid();
+        let entries: Vec<JournalEntry> = record(vec![
+            (2, GameEvent::PlayerAdded { user_id }),
+            (3, GameEvent::PlayerRemoved { user_id }),
+        ]);
+        assert!(replay(Snapshot::default(), &entries[1..], u64::MAX).is_err());
+
+        let mut diverged: JournalEntry = entries[1].clone();
+        diverged.tick = 1;
+        assert!(replay(Snapshot::default(), &[entries[0].clone(), diverged], u64::MAX).is_err());
+    }
+}
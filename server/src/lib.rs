@@ -1,3 +1,12 @@
+pub mod account;
+pub mod context;
+pub mod game;
+pub mod handler;
+pub mod journal;
 pub mod listen;
+pub mod manager;
 pub mod monitor;
+pub mod registry;
 pub mod route;
+pub mod session;
+pub mod storage;
@@ -1,37 +1,59 @@
 use std::error::Error;
+use server::account::ACCOUNTS;
 use server::listen;
+use server::manager::Manager;
 use server::monitor;
-use shared::environment::{self};
+use server::storage::Storage;
+use server::storage::sqlite::SqliteStorage;
+use shared::clock::RealTimeClock;
+use shared::environment::{self, RuntimeEnvironment};
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync;
+use tokio::time;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     environment::load_env()?;
     env_logger::builder().filter_level(log::LevelFilter::Debug).format_source_path(true).try_init()?;
 
+    let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open(&RuntimeEnvironment::default().get_database_path())?);
+    log::info!("Accounts restored; [count: {}]", ACCOUNTS.restore(storage.clone())?);
+
     let listener: TcpListener = listen::listen().await?;
     let (cancellation_sender, cancellation_receiver) = sync::broadcast::channel::<()>(1);
 
-    tokio::spawn(monitor::monitor_listener(cancellation_receiver.resubscribe(), listener));
-    tokio::spawn(monitor::monitor_manager(cancellation_receiver.resubscribe()));
+    let (mut manager, manager_channel) = Manager::new(
+        cancellation_receiver.resubscribe(),
+        Arc::new(RealTimeClock::new()),
+        RuntimeEnvironment::default().get_tick_interval(),
+        storage,
+    );
+    log::info!("Games restored; [count: {}]", manager.restore()?);
+    tokio::spawn(monitor::monitor_listener(
+        cancellation_receiver.resubscribe(),
+        listener,
+        manager_channel,
+    ));
+    tokio::spawn(monitor::monitor_manager(cancellation_receiver.resubscribe(), manager));
+    tokio::spawn(monitor::monitor_sessions(cancellation_receiver.resubscribe()));
     drop(cancellation_receiver);
 
     match tokio::signal::ctrl_c().await {
         Ok(_) => {
             log::info!("Received <C-C> signal");
-            graceful_shutdown(cancellation_sender)
+            graceful_shutdown(cancellation_sender).await
         }
         Err(err) => {
             log::error!("Failed to await <C-C> signal. Shutting down. [{:#}]", err);
-            graceful_shutdown(cancellation_sender);
+            graceful_shutdown(cancellation_sender).await;
         }
     };
 
     Ok(())
 }
 
-fn graceful_shutdown(cancellation_sender: sync::broadcast::Sender<()>) {
+async fn graceful_shutdown(cancellation_sender: sync::broadcast::Sender<()>) {
     match cancellation_sender.send(()) {
         Ok(val) => {
             log::debug!("Shutdown message sent to {} receivers", val);
@@ -40,4 +62,12 @@ fn graceful_shutdown(cancellation_sender: sync::broadcast::Sender<()>) {
             log::error!("Failed to send shutdown message [{:#}]", err);
         }
     }
+
+    // Every task holds a receiver until it has finished
+    if time::timeout(monitor::SHUTDOWN_TIMEOUT, cancellation_sender.closed()).await.is_err() {
+        log::warn!(
+            "Timed out waiting for tasks to finish; [timeout: {:?}]",
+            monitor::SHUTDOWN_TIMEOUT
+        );
+    }
 }
@@ -0,0 +1,736 @@
+//! The manager actor owns every game and serializes changes to them.
+//! Connection tasks send it [ManagerCommand]s through a [ManagerChannel].
+//! Each game it creates runs as its own task; see [crate::game].
+//! Every change to a game is written through to [Storage], from which the games are restored on startup.
+
+use crate::game::{Game, GameChannel};
+use crate::journal::{JournalEntry, Snapshot};
+use crate::registry::{GameFilter, GameRegistry, RegisteredGame};
+use crate::storage::Storage;
+use shared::clock::{GameClock, Ticker};
+use shared::error::AppError;
+use shared::map::{Map, MapSize, generate_map};
+use shared::network::protocol::{GameSettings, GameStatus, GameSummary};
+use shared::random::{GameSeed, random_uuid};
+use std::fmt::{self, Display};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task;
+use uuid::Uuid;
+
+pub const MANAGER_CHANNEL_CAPACITY: usize = 128;
+pub const MAX_PLAYERS_LIMIT: u8 = 8;
+/// Finished games remain listed until the next sweep
+const FINISHED_GAME_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reasons the manager refuses a command
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ManagerError {
+    InvalidSettings,
+    GameNotFound,
+    GameFull,
+    GameNotOpen,
+    GameNotInProgress,
+    NoPlayers,
+    NotJoined,
+}
+
+impl Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string: &'static str = match self {
+            ManagerError::InvalidSettings => "Invalid game settings",
+            ManagerError::GameNotFound => "Game not found",
+            ManagerError::GameFull => "Game is full",
+            ManagerError::GameNotOpen => "Game is no longer open",
+            ManagerError::GameNotInProgress => "Game is not in progress",
+            ManagerError::NoPlayers => "Game has no players",
+            ManagerError::NotJoined => "Not a player in the game",
+        };
+        write!(f, "{}", string)
+    }
+}
+
+pub type ManagerResult<T> = Result<T, ManagerError>;
+
+/// Each command carries the sender for its reply.
+/// Replies are dropped if the requester has gone away.
+#[derive(Debug)]
+pub enum ManagerCommand {
+    CreateGame {
+        settings: GameSettings,
+        reply: oneshot::Sender<ManagerResult<GameSummary>>,
+    },
+    GetGame {
+        game_id: Uuid,
+        reply: oneshot::Sender<Option<GameSummary>>,
+    },
+    ListGames {
+        filter: GameFilter,
+        reply: oneshot::Sender<Vec<GameSummary>>,
+    },
+    JoinGame {
+        game_id: Uuid,
+        user_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<GameSummary>>,
+    },
+    LeaveGame {
+        game_id: Uuid,
+        user_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<GameSummary>>,
+    },
+    StartGame {
+        game_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<GameSummary>>,
+    },
+    FinishGame {
+        game_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<GameSummary>>,
+    },
+    DeleteGame {
+        game_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<()>>,
+    },
+    /// Only the game's players may see its map
+    GetMap {
+        game_id: Uuid,
+        user_id: Uuid,
+        reply: oneshot::Sender<ManagerResult<Arc<Map>>>,
+    },
+}
+
+/// A game whose map has been generated, ready to be registered and started by the manager
+struct GeneratedGame {
+    summary: GameSummary,
+    seed: GameSeed,
+    snapshot: Snapshot,
+    map: Map,
+    /// Answers the [ManagerCommand::CreateGame] which created the game, if any
+    reply: Option<oneshot::Sender<ManagerResult<GameSummary>>>,
+}
+
+/// Handle to the manager actor. Cheap to clone; every connection holds one.
+#[derive(Debug, Clone)]
+pub struct ManagerChannel {
+    sender: mpsc::Sender<ManagerCommand>,
+}
+
+impl ManagerChannel {
+    pub async fn create_game(&self, settings: GameSettings) -> Result<ManagerResult<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::CreateGame { settings, reply }).await
+    }
+
+    pub async fn get_game(&self, game_id: Uuid) -> Result<Option<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::GetGame { game_id, reply }).await
+    }
+
+    pub async fn list_games(&self, filter: GameFilter) -> Result<Vec<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::ListGames { filter, reply }).await
+    }
+
+    pub async fn join_game(&self, game_id: Uuid, user_id: Uuid) -> Result<ManagerResult<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::JoinGame {
+            game_id,
+            user_id,
+            reply,
+        })
+        .await
+    }
+
+    pub async fn leave_game(&self, game_id: Uuid, user_id: Uuid) -> Result<ManagerResult<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::LeaveGame {
+            game_id,
+            user_id,
+            reply,
+        })
+        .await
+    }
+
+    pub async fn start_game(&self, game_id: Uuid) -> Result<ManagerResult<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::StartGame { game_id, reply }).await
+    }
+
+    pub async fn finish_game(&self, game_id: Uuid) -> Result<ManagerResult<GameSummary>, AppError> {
+        self.request(|reply| ManagerCommand::FinishGame { game_id, reply }).await
+    }
+
+    pub async fn delete_game(&self, game_id: Uuid) -> Result<ManagerResult<()>, AppError> {
+        self.request(|reply| ManagerCommand::DeleteGame { game_id, reply }).await
+    }
+
+    pub async fn get_map(&self, game_id: Uuid, user_id: Uuid) -> Result<ManagerResult<Arc<Map>>, AppError> {
+        self.request(|reply| ManagerCommand::GetMap {
+            game_id,
+            user_id,
+            reply,
+        })
+        .await
+    }
+
+    /// Send a command and wait for its reply.
+    /// Fails only if the manager has stopped.
+    async fn request<T>(&self, command: impl FnOnce(oneshot::Sender<T>) -> ManagerCommand) -> Result<T, AppError> {
+        let (reply, receiver): (oneshot::Sender<T>, oneshot::Receiver<T>) = oneshot::channel();
+        if self.sender.send(command(reply)).await.is_err() {
+            return Err(AppError::new("Manager is not running"));
+        }
+        receiver.await.map_err(|_| AppError::new("Manager dropped the command"))
+    }
+}
+
+pub struct Manager {
+    receiver: mpsc::Receiver<ManagerCommand>,
+    games: GameRegistry,
+    /// Shared by every game, and paces the sweep of finished games
+    clock: Arc<dyn GameClock>,
+    /// Clock time between each game's ticks
+    tick_interval: Duration,
+    /// Resubscribed by each game task so that it stops on shutdown
+    cancellation_receiver: broadcast::Receiver<()>,
+    storage: Arc<dyn Storage>,
+    /// Games whose maps were generated off the actor; see [Manager::spawn_game]
+    generated_sender: mpsc::Sender<GeneratedGame>,
+    generated_receiver: mpsc::Receiver<GeneratedGame>,
+}
+
+impl Manager {
+    pub fn new(
+        cancellation_receiver: broadcast::Receiver<()>,
+        clock: Arc<dyn GameClock>,
+        tick_interval: Duration,
+        storage: Arc<dyn Storage>,
+    ) -> (Manager, ManagerChannel) {
+        let (sender, receiver) = mpsc::channel(MANAGER_CHANNEL_CAPACITY);
+        let (generated_sender, generated_receiver) = mpsc::channel(MANAGER_CHANNEL_CAPACITY);
+        let manager: Manager = Manager {
+            receiver,
+            games: GameRegistry::default(),
+            clock,
+            tick_interval,
+            cancellation_receiver,
+            storage,
+            generated_sender,
+            generated_receiver,
+        };
+        (manager, ManagerChannel { sender })
+    }
+
+    /// Start a task for every saved game, resuming each from its latest snapshot with the journal since replayed.
+    /// Each game is listed once its map has been regenerated. Returns the number of games restored.
+    pub fn restore(&mut self) -> Result<usize, AppError> {
+        let games: Vec<(GameSummary, GameSeed)> = self.storage.load_games()?;
+        let count: usize = games.len();
+        for (summary, seed) in games {
+            let mut snapshot: Snapshot = self.storage.load_snapshot(&summary.id)?.unwrap_or_default();
+            let entries: Vec<JournalEntry> = self.storage.load_journal(&summary.id, snapshot.sequence)?;
+            for entry in &entries {
+                // Resume from the last consistent state rather than losing the game
+                if let Err(e) = snapshot.replay(entry) {
+                    log::error!("Failed to replay game journal; [{}] {:#}", summary.id, e);
+                    break;
+                }
+            }
+            log::debug!(
+                "Restoring game; [{}] [tick: {}] [replayed: {}]",
+                summary.id,
+                snapshot.state.tick,
+                entries.len()
+            );
+            self.spawn_game(summary, seed, snapshot, None);
+        }
+        Ok(count)
+    }
+
+    /// Handle commands until every [ManagerChannel] has been dropped.
+    /// Finished games are removed periodically.
+    pub async fn run(mut self) {
+        let mut sweep: Ticker = Ticker::new(self.clock.clone(), FINISHED_GAME_SWEEP_INTERVAL);
+
+        loop {
+            tokio::select! {
+                command = self.receiver.recv() => match command {
+                    Some(command) => self.handle(command).await,
+                    None => break,
+                },
+                // The manager holds a sender, so this never yields None
+                Some(generated) = self.generated_receiver.recv() => self.register_game(generated),
+                _ = sweep.tick() => self.remove_finished(),
+            }
+        }
+        log::debug!("Manager stopped; every channel was dropped");
+    }
+
+    async fn handle(&mut self, command: ManagerCommand) {
+        log::trace!("Manager command; [{:?}]", command);
+        match command {
+            ManagerCommand::CreateGame { settings, reply } => self.create_game(settings, reply),
+            ManagerCommand::GetGame { game_id, reply } => {
+                let _ = reply.send(self.games.get(&game_id).map(|game| game.summary.clone()));
+            }
+            ManagerCommand::ListGames { filter, reply } => {
+                let _ = reply.send(self.games.list(filter));
+            }
+            ManagerCommand::JoinGame {
+                game_id,
+                user_id,
+                reply,
+            } => {
+                let _ = reply.send(self.join_game(game_id, user_id).await);
+            }
+            ManagerCommand::LeaveGame {
+                game_id,
+                user_id,
+                reply,
+            } => {
+                let _ = reply.send(self.leave_game(game_id, user_id).await);
+            }
+            ManagerCommand::StartGame { game_id, reply } => {
+                let _ = reply.send(self.start_game(game_id));
+            }
+            ManagerCommand::FinishGame { game_id, reply } => {
+                let _ = reply.send(self.finish_game(game_id));
+            }
+            ManagerCommand::DeleteGame { game_id, reply } => {
+                let _ = reply.send(self.delete_game(game_id));
+            }
+            ManagerCommand::GetMap {
+                game_id,
+                user_id,
+                reply,
+            } => {
+                let _ = reply.send(self.get_map(game_id, user_id));
+            }
+        }
+    }
+
+    /// Replies once the game's map has been generated
+    fn create_game(&mut self, settings: GameSettings, reply: oneshot::Sender<ManagerResult<GameSummary>>) {
+        // Any size decodes, but only the presets are offered, which bounds the time spent generating a map
+        if settings.max_players == 0
+            || settings.max_players > MAX_PLAYERS_LIMIT
+            || settings.name.trim().is_empty()
+            || !MapSize::PRESETS.contains(&settings.map_size)
+        {
+            let _ = reply.send(Err(ManagerError::InvalidSettings));
+            return;
+        }
+
+        let summary: GameSummary = GameSummary {
+            id: random_uuid(),
+            settings,
+            players: Vec::new(),
+            status: GameStatus::Open,
+        };
+        let seed: GameSeed = GameSeed::random();
+        // Saved before the game task starts, as its snapshots refer to the game
+        self.save(&summary, seed);
+        log::info!("Game created; [{}] [{}]", summary.id, summary.settings.name);
+        self.spawn_game(summary, seed, Snapshot::default(), Some(reply));
+    }
+
+    /// Generate the game's map, then register and start the game; see [Manager::register_game].
+    /// Generating a large map takes long enough to stall every other command, so it runs on a blocking thread.
+    /// The map is regenerated from the seed rather than saved.
+    fn spawn_game(
+        &self,
+        summary: GameSummary,
+        seed: GameSeed,
+        snapshot: Snapshot,
+        reply: Option<oneshot::Sender<ManagerResult<GameSummary>>>,
+    ) {
+        let generated_sender: mpsc::Sender<GeneratedGame> = self.generated_sender.clone();
+        let storage: Arc<dyn Storage> = self.storage.clone();
+        tokio::spawn(async move {
+            let settings: GameSettings = summary.settings.clone();
+            let generate_f = task::spawn_blocking(move || {
+                generate_map(settings.map_generator, settings.map_size, settings.max_players, seed)
+            });
+            let map: Map = match generate_f.await {
+                Ok(map) => map,
+                // Dropping the reply tells the requester that the command failed
+                Err(e) => {
+                    log::error!("Failed to generate map; [{}] {}", summary.id, e);
+                    // A new game would otherwise be restored on every startup without ever having been joined.
+                    // A restored game is kept, along with its journal, in case it can be generated next time.
+                    if reply.is_some()
+                        && let Err(e) = storage.delete_game(&summary.id)
+                    {
+                        log::error!("Failed to delete saved game; [{}] {:#}", summary.id, e);
+                    }
+                    return;
+                }
+            };
+            let generated: GeneratedGame = GeneratedGame {
+                summary,
+                seed,
+                snapshot,
+                map,
+                reply,
+            };
+            // Fails only once the manager has stopped
+            let _ = generated_sender.send(generated).await;
+        });
+    }
+
+    fn register_game(&mut self, generated: GeneratedGame) {
+        let GeneratedGame {
+            summary,
+            seed,
+            snapshot,
+            map,
+            reply,
+        } = generated;
+        let (mut game, channel): (Game, GameChannel) = Game::restore(
+            summary.id,
+            self.clock.clone(),
+            self.tick_interval,
+            Some(self.storage.clone()),
+            snapshot,
+        );
+        // Memberships are saved before the game is told of them, so they may be ahead of its journal
+        game.sync_players(&summary.players);
+        tokio::spawn(game.run(self.cancellation_receiver.resubscribe()));
+        self.games.insert(RegisteredGame {
+            summary: summary.clone(),
+            seed,
+            map: Arc::new(map),
+            channel,
+        });
+        if let Some(reply) = reply {
+            let _ = reply.send(Ok(summary));
+        }
+    }
+
+    /// A player joining again is answered with the game unchanged, so that a client can always get back into its games.
+    async fn join_game(&mut self, game_id: Uuid, user_id: Uuid) -> ManagerResult<GameSummary> {
+        let RegisteredGame {
+            summary: game,
+            seed,
+            channel,
+            ..
+        } = self.games.get_mut(&game_id).ok_or(ManagerError::GameNotFound)?;
+        if game.players.contains(&user_id) {
+            return Ok(game.clone());
+        }
+        if game.status != GameStatus::Open {
+            return Err(ManagerError::GameNotOpen);
+        }
+        if game.is_full() {
+            return Err(ManagerError::GameFull);
+        }
+
+        game.players.push(user_id);
+        if let Err(e) = channel.add_player(user_id).await {
+            log::error!("Failed to add player to game; [{}] {}", game_id, e);
+        }
+        let (game, seed): (GameSummary, GameSeed) = (game.clone(), *seed);
+        self.save(&game, seed);
+        Ok(game)
+    }
+
+    async fn leave_game(&mut self, game_id: Uuid, user_id: Uuid) -> ManagerResult<GameSummary> {
+        let RegisteredGame {
+            summary: game,
+            seed,
+            channel,
+            ..
+        } = self.games.get_mut(&game_id).ok_or(ManagerError::GameNotFound)?;
+        let Some(index) = game.players.iter().position(|id| *id == user_id) else {
+            return Err(ManagerError::NotJoined);
+        };
+
+        game.players.remove(index);
+        if let Err(e) = channel.remove_player(user_id).await {
+            log::error!("Failed to remove player from game; [{}] {}", game_id, e);
+        }
+        let (game, seed): (GameSummary, GameSeed) = (game.clone(), *seed);
+        self.save(&game, seed);
+        Ok(game)
+    }
+
+    fn start_game(&mut self, game_id: Uuid) -> ManagerResult<GameSummary> {
+        let RegisteredGame {
+            summary: game, seed, ..
+        } = self.games.get_mut(&game_id).ok_or(ManagerError::GameNotFound)?;
+        if game.status != GameStatus::Open {
+            return Err(ManagerError::GameNotOpen);
+        }
+        if game.players.is_empty() {
+            return Err(ManagerError::NoPlayers);
+        }
+
+        game.status = GameStatus::InProgress;
+        log::info!("Game started; [{}]", game_id);
+        let (game, seed): (GameSummary, GameSeed) = (game.clone(), *seed);
+        self.save(&game, seed);
+        Ok(game)
+    }
+
+    fn finish_game(&mut self, game_id: Uuid) -> ManagerResult<GameSummary> {
+        let RegisteredGame {
+            summary: game, seed, ..
+        } = self.games.get_mut(&game_id).ok_or(ManagerError::GameNotFound)?;
+        if game.status != GameStatus::InProgress {
+            return Err(ManagerError::GameNotInProgress);
+        }
+
+        game.status = GameStatus::Finished;
+        log::info!("Game finished; [{}]", game_id);
+        let (game, seed): (GameSummary, GameSeed) = (game.clone(), *seed);
+        self.save(&game, seed);
+        Ok(game)
+    }
+
+    fn delete_game(&mut self, game_id: Uuid) -> ManagerResult<()> {
+        // Dropping the game's channel stops its task
+        self.games.remove(&game_id).ok_or(ManagerError::GameNotFound)?;
+        self.delete(&game_id);
+        log::info!("Game deleted; [{}]", game_id);
+        Ok(())
+    }
+
+    fn get_map(&self, game_id: Uuid, user_id: Uuid) -> ManagerResult<Arc<Map>> {
+        let game: &RegisteredGame = self.games.get(&game_id).ok_or(ManagerError::GameNotFound)?;
+        if !game.summary.players.contains(&user_id) {
+            return Err(ManagerError::NotJoined);
+        }
+        Ok(game.map.clone())
+    }
+
+    fn remove_finished(&mut self) {
+        let finished: Vec<GameSummary> = self.games.list(GameFilter::Finished);
+        for game in &finished {
+            // The journal is kept for debugging and replays
+            if let Err(e) = self.storage.archive_game(&game.id) {
+                log::error!("Failed to archive finished game; [{}] {:#}", game.id, e);
+            }
+        }
+        let removed: usize = self.games.remove_finished();
+        if removed > 0 {
+            log::info!("Removed {} finished games", removed);
+        }
+    }
+
+    /// Each save writes the whole game, so a failed save is repaired by the next.
+    fn save(&self, game: &GameSummary, seed: GameSeed) {
+        if let Err(e) = self.storage.save_game(game, seed) {
+            log::error!("Failed to save game; [{}] {:#}", game.id, e);
+        }
+    }
+
+    fn delete(&self, game_id: &Uuid) {
+        if let Err(e) = self.storage.delete_game(game_id) {
+            log::error!("Failed to delete saved game; [{}] {:#}", game_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sqlite::SqliteStorage;
+    use shared::clock::RealTimeClock;
+    use shared::map::MapGeneratorType;
+    use tokio::time;
+
+    const TICK_INTERVAL: Duration = Duration::from_millis(5);
+
+    fn storage() -> Arc<dyn Storage> {
+        Arc::new(SqliteStorage::open_in_memory().unwrap())
+    }
+
+    fn start() -> (broadcast::Sender<()>, ManagerChannel) {
+        start_with(storage())
+    }
+
+    fn start_with(storage: Arc<dyn Storage>) -> (broadcast::Sender<()>, ManagerChannel) {
+        let (cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (mut manager, channel): (Manager, ManagerChannel) = Manager::new(
+            cancellation_receiver,
+            Arc::new(RealTimeClock::new()),
+            TICK_INTERVAL,
+            storage,
+        );
+        manager.restore().unwrap();
+        tokio::spawn(manager.run());
+        (cancellation_sender, channel)
+    }
+
+    fn settings(max_players: u8) -> GameSettings {
+        GameSettings {
+            name: String::from("Test"),
+            max_players,
+            map_generator: MapGeneratorType::DEFAULT,
+            map_size: MapSize::DEFAULT,
+        }
+    }
+
+    #[tokio::test]
+    async fn lifecycle() {
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start();
+
+        let game: GameSummary = channel.create_game(settings(2)).await.unwrap().unwrap();
+        assert_eq!(vec![game.clone()], channel.list_games(GameFilter::All).await.unwrap());
+
+        let (first, second, third): (Uuid, Uuid, Uuid) = (random_uuid(), random_uuid(), random_uuid());
+        let joined: GameSummary = channel.join_game(game.id, first).await.unwrap().unwrap();
+        assert_eq!(joined, channel.join_game(game.id, first).await.unwrap().unwrap());
+        let joined: GameSummary = channel.join_game(game.id, second).await.unwrap().unwrap();
+        assert_eq!(vec![first, second], joined.players);
+        assert_eq!(
+            Err(ManagerError::GameFull),
+            channel.join_game(game.id, third).await.unwrap()
+        );
+
+        let left: GameSummary = channel.leave_game(game.id, first).await.unwrap().unwrap();
+        assert_eq!(vec![second], left.players);
+        assert_eq!(
+            Err(ManagerError::NotJoined),
+            channel.leave_game(game.id, first).await.unwrap()
+        );
+
+        channel.delete_game(game.id).await.unwrap().unwrap();
+        assert_eq!(
+            Err(ManagerError::GameNotFound),
+            channel.join_game(game.id, third).await.unwrap()
+        );
+        assert!(channel.list_games(GameFilter::All).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn status() {
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start();
+
+        let game: GameSummary = channel.create_game(settings(2)).await.unwrap().unwrap();
+        assert_eq!(Err(ManagerError::NoPlayers), channel.start_game(game.id).await.unwrap());
+        assert_eq!(
+            Err(ManagerError::GameNotInProgress),
+            channel.finish_game(game.id).await.unwrap()
+        );
+
+        channel.join_game(game.id, random_uuid()).await.unwrap().unwrap();
+        assert_eq!(1, channel.list_games(GameFilter::Open).await.unwrap().len());
+        let started: GameSummary = channel.start_game(game.id).await.unwrap().unwrap();
+        assert_eq!(GameStatus::InProgress, started.status);
+        assert_eq!(
+            Err(ManagerError::GameNotOpen),
+            channel.join_game(game.id, random_uuid()).await.unwrap()
+        );
+        assert!(channel.list_games(GameFilter::Open).await.unwrap().is_empty());
+        assert_eq!(1, channel.list_games(GameFilter::InProgress).await.unwrap().len());
+
+        channel.finish_game(game.id).await.unwrap().unwrap();
+        let finished: Option<GameSummary> = channel.get_game(game.id).await.unwrap();
+        assert_eq!(Some(GameStatus::Finished), finished.map(|game| game.status));
+    }
+
+    #[tokio::test]
+    async fn maps() {
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start();
+
+        let map_size: MapSize = MapSize { width: 96, height: 64 };
+        let game: GameSummary = channel
+            .create_game(GameSettings {
+                map_size,
+                ..settings(2)
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let user_id: Uuid = random_uuid();
+        assert_eq!(
+            Err(ManagerError::NotJoined),
+            channel.get_map(game.id, user_id).await.unwrap()
+        );
+        channel.join_game(game.id, user_id).await.unwrap().unwrap();
+        let map: Arc<Map> = channel.get_map(game.id, user_id).await.unwrap().unwrap();
+        assert_eq!(map_size, map.size());
+        assert_eq!(
+            Err(ManagerError::GameNotFound),
+            channel.get_map(random_uuid(), user_id).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_settings() {
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start();
+
+        assert_eq!(
+            Err(ManagerError::InvalidSettings),
+            channel.create_game(settings(0)).await.unwrap()
+        );
+        assert_eq!(
+            Err(ManagerError::InvalidSettings),
+            channel.create_game(settings(MAX_PLAYERS_LIMIT + 1)).await.unwrap()
+        );
+        let oversized: GameSettings = GameSettings {
+            map_size: MapSize::new(MapSize::MAX_SIDE, MapSize::MAX_SIDE).unwrap(),
+            ..settings(2)
+        };
+        assert_eq!(
+            Err(ManagerError::InvalidSettings),
+            channel.create_game(oversized).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn stopped_manager() {
+        let (_cancellation_sender, cancellation_receiver) = broadcast::channel::<()>(1);
+        let (manager, channel): (Manager, ManagerChannel) = Manager::new(
+            cancellation_receiver,
+            Arc::new(RealTimeClock::new()),
+            TICK_INTERVAL,
+            storage(),
+        );
+        drop(manager);
+        assert!(channel.list_games(GameFilter::All).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn restores_from_storage() {
+        let storage: Arc<dyn Storage> = storage();
+        let (cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start_with(storage.clone());
+        let user_id: Uuid = random_uuid();
+        let kept: GameSummary = channel.create_game(settings(2)).await.unwrap().unwrap();
+        let kept: GameSummary = channel.join_game(kept.id, user_id).await.unwrap().unwrap();
+        let map: Arc<Map> = channel.get_map(kept.id, user_id).await.unwrap().unwrap();
+        let deleted: GameSummary = channel.create_game(settings(2)).await.unwrap().unwrap();
+        channel.delete_game(deleted.id).await.unwrap().unwrap();
+        // Stop the games, so that they are not still writing once restored
+        cancellation_sender.send(()).unwrap();
+        time::sleep(TICK_INTERVAL * 4).await;
+        assert!(storage.load_snapshot(&kept.id).unwrap().is_some());
+
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start_with(storage);
+        // Listed once its map has been regenerated
+        time::timeout(Duration::from_secs(5), async {
+            while channel.list_games(GameFilter::All).await.unwrap().is_empty() {
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(vec![kept.clone()], channel.list_games(GameFilter::All).await.unwrap());
+        // Regenerated from the saved seed
+        assert_eq!(Ok(map), channel.get_map(kept.id, user_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn generates_maps_off_the_actor() {
+        let (_cancellation_sender, channel): (broadcast::Sender<()>, ManagerChannel) = start();
+        let large: GameSettings = GameSettings {
+            map_size: MapSize::PRESETS[MapSize::PRESETS.len() - 1],
+            ..settings(MAX_PLAYERS_LIMIT)
+        };
+        let create = tokio::spawn({
+            let channel: ManagerChannel = channel.clone();
+            async move { channel.create_game(large).await.unwrap().unwrap() }
+        });
+
+        // The manager answers while the map, which takes far longer than this, is generated
+        time::sleep(Duration::from_millis(1)).await;
+        assert!(channel.list_games(GameFilter::All).await.unwrap().is_empty());
+        let created: GameSummary = create.await.unwrap();
+        assert_eq!(vec![created], channel.list_games(GameFilter::All).await.unwrap());
+    }
+}
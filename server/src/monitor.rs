@@ -1,72 +1,43 @@
+use crate::context::ConnectionContext;
+use crate::game::GameChannel;
+use crate::manager::{Manager, ManagerChannel};
 use crate::route::route_frame;
+use crate::session::{SESSION_TTL, SESSIONS};
 use futures::future;
-use futures::future::Either;
 use network::monitor;
+use shared::environment::RuntimeEnvironment;
 use shared::network;
 use shared::network::connection::Connection;
-use shared::random::random_uuid;
-use std::collections::HashMap;
+use shared::network::transport::{PeerAddr, Transport};
 use std::net::SocketAddr;
 use std::pin;
-use std::sync::LazyLock;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::sync;
-use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
 use uuid::Uuid;
 
-pub const GAMES: LazyLock<HashMap<Uuid, Game>> = LazyLock::new(|| HashMap::new());
-
-pub struct MpscChannel {
-    pub sender: mpsc::Sender<Vec<u8>>,
-    pub receiver: mpsc::Receiver<Vec<u8>>,
-}
-
-impl MpscChannel {
-    pub fn new(buffer_size: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(buffer_size);
-        MpscChannel { sender, receiver }
-    }
-}
-
-pub struct ManagerChannel(pub MpscChannel);
-
-pub struct GameChannel(pub MpscChannel);
-
-pub struct Manager {
-    pub channel: ManagerChannel,
-}
-
-impl Manager {
-    pub fn new() -> Self {
-        Manager {
-            channel: ManagerChannel(MpscChannel::new(128)),
-        }
-    }
-}
-
-pub struct Game {
-    pub id: Uuid,
-    pub channel: GameChannel,
-}
-
-impl Game {
-    pub fn new() -> Self {
-        Game {
-            id: random_uuid(),
-            channel: GameChannel(MpscChannel::new(128)),
-        }
-    }
-}
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+/// Time allowed for tasks to finish, e.g. flushing outgoing frames, once shutdown is signalled
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+const SESSION_EXPIRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 pub struct User {
     pub id: Uuid,
-    pub game_senders: Vec<mpsc::Sender<u8>>,
+    pub game_channels: Vec<GameChannel>,
     pub connection: Connection,
 }
 
-pub async fn monitor_listener(mut cancellation_receiver: sync::broadcast::Receiver<()>, listener: TcpListener) {
+pub async fn monitor_listener(
+    mut cancellation_receiver: sync::broadcast::Receiver<()>,
+    listener: TcpListener,
+    manager: ManagerChannel,
+) {
     let cancellation_receiver_forward = cancellation_receiver.resubscribe();
+    let idle_timeout: Duration = RuntimeEnvironment::default().get_idle_timeout();
+    log::info!("Idle connections will be closed after {:?}", idle_timeout);
     let task_f = async move {
         loop {
             let accept_r = listener.accept().await;
@@ -78,7 +49,9 @@ pub async fn monitor_listener(mut cancellation_receiver: sync::broadcast::Receiv
                     tokio::spawn(monitor_client(
                         cancellation_receiver_forward.resubscribe(),
                         tcp_stream,
-                        socket_addr,
+                        PeerAddr::from(socket_addr),
+                        idle_timeout,
+                        manager.clone(),
                     ));
                 }
                 Err(err) => {
@@ -99,11 +72,8 @@ pub async fn monitor_listener(mut cancellation_receiver: sync::broadcast::Receiv
     // clean up here if necessary
 }
 
-pub async fn monitor_manager(mut cancellation_receiver: sync::broadcast::Receiver<()>) {
-    let task_f = async {
-        let _manager = Manager::new();
-        // todo: loop await on manager channel receiver
-    };
+pub async fn monitor_manager(mut cancellation_receiver: sync::broadcast::Receiver<()>, manager: Manager) {
+    let task_f = manager.run();
     let task_f = pin::pin!(task_f);
 
     let cancellation_f = cancellation_receiver.recv();
@@ -115,12 +85,17 @@ pub async fn monitor_manager(mut cancellation_receiver: sync::broadcast::Receive
     // clean up here if necessary
 }
 
-async fn monitor_client(
-    mut cancellation_receiver: sync::broadcast::Receiver<()>,
-    tcp_stream: TcpStream,
-    socket_addr: SocketAddr,
-) {
-    let task_f = monitor_client_task(tcp_stream, socket_addr);
+/// Periodically discard sessions which can no longer be resumed.
+pub async fn monitor_sessions(mut cancellation_receiver: sync::broadcast::Receiver<()>) {
+    let task_f = async {
+        loop {
+            time::sleep(SESSION_EXPIRY_INTERVAL).await;
+            let expired: usize = SESSIONS.expire(SESSION_TTL).await;
+            if expired > 0 {
+                log::info!("Expired {} sessions", expired);
+            }
+        }
+    };
     let task_f = pin::pin!(task_f);
 
     let cancellation_f = cancellation_receiver.recv();
@@ -128,26 +103,330 @@ async fn monitor_client(
 
     future::select(cancellation_f, task_f).await;
 
-    log::debug!("monitor_client terminated");
-    // clean up here if necessary
+    log::debug!("monitor_sessions terminated");
 }
 
-async fn monitor_client_task(tcp_stream: TcpStream, socket_addr: SocketAddr) {
-    let connection: Connection = Connection::new(tcp_stream, socket_addr);
+/// Serve a single client over any transport.
+pub async fn monitor_client<T: Transport>(
+    cancellation_receiver: sync::broadcast::Receiver<()>,
+    transport: T,
+    peer_addr: PeerAddr,
+    idle_timeout: Duration,
+    manager: ManagerChannel,
+) {
+    // Cancellation is handled within the task so that outgoing frames are still flushed
+    monitor_client_task(cancellation_receiver, transport, peer_addr, idle_timeout, manager).await;
+
+    log::debug!("monitor_client terminated");
+}
 
-    let incoming_f = monitor::monitor_incoming_frames(connection.reader, route_frame);
-    let incoming_f = pin::pin!(incoming_f);
+async fn monitor_client_task<T: Transport>(
+    mut cancellation_receiver: sync::broadcast::Receiver<()>,
+    transport: T,
+    peer_addr: PeerAddr,
+    idle_timeout: Duration,
+    manager: ManagerChannel,
+) {
+    let Connection {
+        peer_addr,
+        reader,
+        mut writer,
+        ..
+    } = Connection::new(transport, peer_addr);
+    let context: Arc<ConnectionContext> = Arc::new(ConnectionContext::new(peer_addr, manager));
 
-    let outgoing_f = monitor::monitor_outgoing_frames(connection.writer);
-    let outgoing_f = pin::pin!(outgoing_f);
+    let writable: bool = {
+        let incoming_f = monitor::monitor_incoming_frames(reader, |write_buffer, frame| {
+            context.touch();
+            route_frame(context.clone(), write_buffer, frame)
+        });
 
-    match future::select(incoming_f, outgoing_f).await {
-        Either::Left(_) => {}
-        Either::Right((outgoing_r, _)) => match outgoing_r {
-            Ok(_) => {}
-            Err(e) => {
-                log::error!("Error monitoring outgoing frames; {:#}", e);
+        tokio::select! {
+            _ = incoming_f => true,
+            outgoing_r = monitor::monitor_outgoing_frames(&mut writer) => match outgoing_r {
+                Ok(_) => true,
+                Err(e) => {
+                    log::error!("Error monitoring outgoing frames; {:#}", e);
+                    false
+                }
+            },
+            _ = monitor_idle(&context, idle_timeout) => {
+                log::info!(
+                    "Closing idle connection; [{}] [timeout: {:?}]",
+                    context.peer_addr,
+                    idle_timeout
+                );
+                true
             }
-        },
+            _ = cancellation_receiver.recv() => true,
+        }
+    }; // Release the writer borrowed by the outgoing future
+
+    // Frames delivered from now on are retained until the client resumes the session
+    if let Some(session) = context.session() {
+        session.detach(&writer.buffer).await;
+    }
+
+    if writable && let Err(e) = monitor::flush_outgoing_frames(&mut writer, FLUSH_TIMEOUT).await {
+        log::error!("Failed to flush outgoing frames; {:#}", e);
+    }
+    // Nothing more is written, so anything still waiting to push must not wait forever
+    writer.buffer.close();
+}
+
+/// Resolves once the client has sent nothing for the idle timeout.
+async fn monitor_idle(context: &ConnectionContext, idle_timeout: Duration) {
+    loop {
+        let deadline: Instant = context.last_seen() + idle_timeout;
+        if Instant::now() >= deadline {
+            return;
+        }
+        time::sleep_until(deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::storage::sqlite::SqliteStorage;
+    use shared::clock::RealTimeClock;
+    use shared::map::{MapGeneratorType, MapSize};
+    use shared::network::connection;
+    use shared::network::handshake;
+    use shared::network::monitor::RouteResult;
+    use shared::network::protocol::{
+        _PlaceholderDynamic, CreateAccount, CreateGame, Deliver, Error, ErrorCode, Frame, GameJoined, GameList,
+        GameSettings, GameSummary, GameUpdated, JoinGame, ListGames, LoggedIn, Operation, OperationType, ProtocolError,
+        Register, Resume, Resumed, SessionGranted,
     };
+    use shared::network::request::Requester;
+    use shared::network::transport::{self, MemoryTransport};
+    use shared::random::random_uuid;
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+
+    struct TestClient {
+        requester: Requester,
+        /// Frames which answer no request
+        frame_receiver: mpsc::UnboundedReceiver<Frame>,
+        tasks: Vec<JoinHandle<()>>,
+        server: JoinHandle<()>,
+    }
+
+    impl TestClient {
+        async fn next_frame(&mut self) -> Frame {
+            time::timeout(Duration::from_secs(1), self.frame_receiver.recv()).await.unwrap().unwrap()
+        }
+
+        /// Drop the client's end of the connection and wait for the server to let go of it.
+        async fn disconnect(self) {
+            for task in &self.tasks {
+                task.abort();
+            }
+            time::timeout(Duration::from_secs(1), self.server).await.unwrap().unwrap();
+        }
+    }
+
+    fn start_manager(cancellation_receiver: &sync::broadcast::Receiver<()>) -> ManagerChannel {
+        let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::open_in_memory().unwrap());
+        let (manager, manager_channel): (Manager, ManagerChannel) = Manager::new(
+            cancellation_receiver.resubscribe(),
+            Arc::new(RealTimeClock::new()),
+            Duration::from_millis(100),
+            storage,
+        );
+        tokio::spawn(manager.run());
+        manager_channel
+    }
+
+    /// Serve a new memory client and complete its handshake.
+    async fn connect(cancellation_receiver: &sync::broadcast::Receiver<()>, manager: &ManagerChannel) -> TestClient {
+        let (client_transport, server_transport): (MemoryTransport, MemoryTransport) = transport::memory_pair();
+        let server_peer_addr: PeerAddr = server_transport.peer_addr();
+        let server = tokio::spawn(monitor_client(
+            cancellation_receiver.resubscribe(),
+            server_transport,
+            server_peer_addr,
+            Duration::from_secs(30),
+            manager.clone(),
+        ));
+
+        let client_peer_addr: PeerAddr = client_transport.peer_addr();
+        let connection: Connection = Connection::new(client_transport, client_peer_addr);
+        let requester: Requester = connection.requester();
+        let Connection { reader, mut writer, .. } = connection;
+        let (frame_sender, frame_receiver) = mpsc::unbounded_channel::<Frame>();
+        let incoming = tokio::spawn(monitor::monitor_incoming_frames(reader, move |_, frame| {
+            let _ = frame_sender.send(frame);
+            async { RouteResult::Continue }
+        }));
+        let outgoing = tokio::spawn(async move {
+            let _ = monitor::monitor_outgoing_frames(&mut writer).await;
+        });
+
+        let mut client: TestClient = TestClient {
+            requester,
+            frame_receiver,
+            tasks: vec![incoming, outgoing],
+            server,
+        };
+        connection::send(&client.requester.write_buffer, &handshake::hello()).await.unwrap();
+        assert!(matches!(
+            client.next_frame().await.head.op_type,
+            OperationType::HelloAck
+        ));
+        client
+    }
+
+    /// Create an account and register a session for it.
+    async fn open_session(requester: &Requester, username: &str) -> (LoggedIn, SessionGranted) {
+        let create_account: CreateAccount = CreateAccount {
+            request_id: 0,
+            username: String::from(username),
+            password: String::from("correct horse"),
+        };
+        let response: Frame = requester.request(create_account).await.unwrap();
+        let logged_in: LoggedIn = LoggedIn::from_frame(&response).unwrap();
+
+        let register: Register = Register {
+            request_id: 0,
+            account_token: logged_in.account_token,
+        };
+        let response: Frame = requester.request(register).await.unwrap();
+        (logged_in, SessionGranted::from_frame(&response).unwrap())
+    }
+
+    fn game_settings() -> GameSettings {
+        GameSettings {
+            name: String::from("Lobby"),
+            max_players: 2,
+            map_generator: MapGeneratorType::DEFAULT,
+            map_size: MapSize::DEFAULT,
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_memory_client() {
+        let (cancellation_sender, cancellation_receiver) = sync::broadcast::channel::<()>(1);
+        let manager_channel: ManagerChannel = start_manager(&cancellation_receiver);
+        let mut client: TestClient = connect(&cancellation_receiver, &manager_channel).await;
+        let requester: Requester = client.requester.clone();
+
+        // An unknown operation is answered without closing the connection
+        requester.write_buffer.push(&[u8::MAX, 0, 0, 0, 5]).await.unwrap();
+        let frame: Frame = client.next_frame().await;
+        assert_eq!(u8::MAX, ProtocolError::from_frame(&frame).unwrap().op_code);
+        // As is an operation which the server never handles
+        let placeholder: _PlaceholderDynamic = _PlaceholderDynamic {
+            string: String::from("unhandled"),
+        };
+        connection::send(&requester.write_buffer, &placeholder).await.unwrap();
+        let frame: Frame = client.next_frame().await;
+        assert_eq!(
+            _PlaceholderDynamic::OP_CODE,
+            ProtocolError::from_frame(&frame).unwrap().op_code
+        );
+
+        // Registering requires an account
+        let register: Register = Register {
+            request_id: 0,
+            account_token: random_uuid(),
+        };
+        let response: Frame = requester.request(register).await.unwrap();
+        assert_eq!(ErrorCode::Unauthorized, Error::from_frame(&response).unwrap().code);
+
+        let (logged_in, session_granted): (LoggedIn, SessionGranted) = open_session(&requester, "monitor_test").await;
+        assert!(SESSIONS.get(&session_granted.session_token).is_some());
+        assert!(session_granted.games.is_empty());
+
+        let create_game: CreateGame = CreateGame {
+            request_id: 0,
+            settings: game_settings(),
+        };
+        let response: Frame = requester.request(create_game).await.unwrap();
+        let game_joined: GameJoined = GameJoined::from_frame(&response).unwrap();
+        assert_eq!(vec![logged_in.user_id], game_joined.game.players);
+
+        // A new session, e.g. after the server restarted, is given the games already joined
+        let register: Register = Register {
+            request_id: 0,
+            account_token: logged_in.account_token,
+        };
+        let response: Frame = requester.request(register).await.unwrap();
+        assert_eq!(
+            vec![game_joined.game.id],
+            SessionGranted::from_frame(&response).unwrap().games
+        );
+
+        let response: Frame = requester.request(ListGames { request_id: 0 }).await.unwrap();
+        assert_eq!(vec![game_joined.game.clone()], GameList::from_frame(&response).unwrap().games);
+
+        // Joining again enters the game without changing it
+        let join_game: JoinGame = JoinGame {
+            request_id: 0,
+            game_id: game_joined.game.id,
+        };
+        let response: Frame = requester.request(join_game).await.unwrap();
+        assert_eq!(game_joined.game, GameJoined::from_frame(&response).unwrap().game);
+
+        // A refused join is answered with an Error rather than closing the connection
+        let join_game: JoinGame = JoinGame {
+            request_id: 0,
+            game_id: random_uuid(),
+        };
+        let response: Frame = requester.request(join_game).await.unwrap();
+        assert!(Error::from_frame(&response).is_ok());
+
+        cancellation_sender.send(()).unwrap();
+        time::timeout(Duration::from_secs(1), client.server).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_replays_game_updates() {
+        let (cancellation_sender, cancellation_receiver) = sync::broadcast::channel::<()>(1);
+        let manager_channel: ManagerChannel = start_manager(&cancellation_receiver);
+
+        let mut owner: TestClient = connect(&cancellation_receiver, &manager_channel).await;
+        let (_, session_granted): (LoggedIn, SessionGranted) = open_session(&owner.requester, "resume_owner").await;
+        let create_game: CreateGame = CreateGame {
+            request_id: 0,
+            settings: game_settings(),
+        };
+        let response: Frame = owner.requester.request(create_game).await.unwrap();
+        let game_joined: GameJoined = GameJoined::from_frame(&response).unwrap();
+        let deliver: Deliver = Deliver::from_frame(&owner.next_frame().await).unwrap();
+        owner.disconnect().await;
+
+        // Delivered while the owner is disconnected
+        let guest: TestClient = connect(&cancellation_receiver, &manager_channel).await;
+        let (guest_logged_in, _): (LoggedIn, SessionGranted) = open_session(&guest.requester, "resume_guest").await;
+        let join_game: JoinGame = JoinGame {
+            request_id: 0,
+            game_id: game_joined.game.id,
+        };
+        let response: Frame = guest.requester.request(join_game).await.unwrap();
+        let joined: GameSummary = GameJoined::from_frame(&response).unwrap().game;
+        assert!(joined.players.contains(&guest_logged_in.user_id));
+
+        let mut owner: TestClient = connect(&cancellation_receiver, &manager_channel).await;
+        let resume: Resume = Resume {
+            request_id: 0,
+            session_token: session_granted.session_token,
+            last_sequence: deliver.sequence,
+        };
+        let response: Frame = owner.requester.request(resume).await.unwrap();
+        let resumed: Resumed = Resumed::from_frame(&response).unwrap();
+        assert_eq!(vec![game_joined.game.id], resumed.games);
+        assert_eq!(1, resumed.replayed);
+        assert!(!resumed.incomplete);
+
+        let replayed: Deliver = Deliver::from_frame(&owner.next_frame().await).unwrap();
+        assert_eq!(deliver.sequence + 1, replayed.sequence);
+        let frame: Frame = Frame::from_bytes(replayed.frame).unwrap();
+        assert_eq!(joined, GameUpdated::from_frame(&frame).unwrap().game);
+
+        cancellation_sender.send(()).unwrap();
+        time::timeout(Duration::from_secs(1), owner.server).await.unwrap().unwrap();
+    }
 }
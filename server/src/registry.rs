@@ -0,0 +1,183 @@
+//! Every game known to the server. Owned by the [crate::manager::Manager], which serializes changes to it;
+//! other tasks query it through the [crate::manager::ManagerChannel].
+
+use crate::game::GameChannel;
+use shared::map::Map;
+use shared::network::protocol::{GameStatus, GameSummary};
+use shared::random::GameSeed;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Selects games when enumerating the registry
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameFilter {
+    All,
+    /// Open games which can still be joined
+    Open,
+    /// Open games which can still be joined, and the unfinished games which the user has joined
+    Available {
+        user_id: Uuid,
+    },
+    /// Open games which have no seats left
+    Full,
+    InProgress,
+    Finished,
+}
+
+impl GameFilter {
+    pub fn matches(&self, game: &GameSummary) -> bool {
+        match self {
+            GameFilter::All => true,
+            GameFilter::Open => game.status == GameStatus::Open && !game.is_full(),
+            GameFilter::Available { user_id } => {
+                GameFilter::Open.matches(game)
+                    || (game.status != GameStatus::Finished && game.players.contains(user_id))
+            }
+            GameFilter::Full => game.status == GameStatus::Open && game.is_full(),
+            GameFilter::InProgress => game.status == GameStatus::InProgress,
+            GameFilter::Finished => game.status == GameStatus::Finished,
+        }
+    }
+}
+
+/// A game's lobby state and map together with the channel to its task
+#[derive(Debug)]
+pub struct RegisteredGame {
+    pub summary: GameSummary,
+    /// The map is generated from the seed
+    pub seed: GameSeed,
+    pub map: Arc<Map>,
+    pub channel: GameChannel,
+}
+
+#[derive(Debug, Default)]
+pub struct GameRegistry {
+    by_id: HashMap<Uuid, RegisteredGame>,
+}
+
+impl GameRegistry {
+    pub fn insert(&mut self, game: RegisteredGame) {
+        self.by_id.insert(game.summary.id, game);
+    }
+
+    pub fn get(&self, game_id: &Uuid) -> Option<&RegisteredGame> {
+        self.by_id.get(game_id)
+    }
+
+    pub fn get_mut(&mut self, game_id: &Uuid) -> Option<&mut RegisteredGame> {
+        self.by_id.get_mut(game_id)
+    }
+
+    /// Dropping the returned game's channel stops its task.
+    pub fn remove(&mut self, game_id: &Uuid) -> Option<RegisteredGame> {
+        self.by_id.remove(game_id)
+    }
+
+    /// Summaries of the games matching the filter, ordered by name
+    pub fn list(&self, filter: GameFilter) -> Vec<GameSummary> {
+        let mut games: Vec<GameSummary> =
+            self.by_id.values().filter(|game| filter.matches(&game.summary)).map(|game| game.summary.clone()).collect();
+        games.sort_by(|a, b| a.settings.name.cmp(&b.settings.name).then(a.id.cmp(&b.id)));
+        games
+    }
+
+    /// Remove every finished game, stopping their tasks.
+    /// Returns the number of games removed.
+    pub fn remove_finished(&mut self) -> usize {
+        let count: usize = self.by_id.len();
+        self.by_id.retain(|_, game| game.summary.status != GameStatus::Finished);
+        count - self.by_id.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use shared::clock::RealTimeClock;
+    use shared::map::{MapGeneratorType, MapSize};
+    use shared::network::protocol::GameSettings;
+    use shared::random::random_uuid;
+    use std::time::Duration;
+
+    fn register(registry: &mut GameRegistry, name: &str, players: usize, status: GameStatus) -> Uuid {
+        let summary: GameSummary = GameSummary {
+            id: random_uuid(),
+            settings: GameSettings {
+                name: String::from(name),
+                max_players: 2,
+                map_generator: MapGeneratorType::DEFAULT,
+                map_size: MapSize::DEFAULT,
+            },
+            players: (0..players).map(|_| random_uuid()).collect(),
+            status,
+        };
+        let (_game, channel): (Game, GameChannel) = Game::new(
+            summary.id,
+            Arc::new(RealTimeClock::new()),
+            Duration::from_millis(100),
+            None,
+        );
+        let game_id: Uuid = summary.id;
+        registry.insert(RegisteredGame {
+            summary,
+            seed: GameSeed(0),
+            map: Arc::new(Map::empty(MapSize::DEFAULT)),
+            channel,
+        });
+        game_id
+    }
+
+    fn names(games: Vec<GameSummary>) -> Vec<String> {
+        games.into_iter().map(|game| game.settings.name).collect()
+    }
+
+    #[test]
+    fn filters() {
+        let mut registry: GameRegistry = GameRegistry::default();
+        register(&mut registry, "open", 1, GameStatus::Open);
+        register(&mut registry, "full", 2, GameStatus::Open);
+        let in_progress: Uuid = register(&mut registry, "in progress", 2, GameStatus::InProgress);
+        let finished: Uuid = register(&mut registry, "finished", 2, GameStatus::Finished);
+
+        assert_eq!(
+            vec!["finished", "full", "in progress", "open"],
+            names(registry.list(GameFilter::All))
+        );
+        assert_eq!(vec!["open"], names(registry.list(GameFilter::Open)));
+        assert_eq!(vec!["full"], names(registry.list(GameFilter::Full)));
+        assert_eq!(vec!["in progress"], names(registry.list(GameFilter::InProgress)));
+        assert_eq!(vec!["finished"], names(registry.list(GameFilter::Finished)));
+        assert_eq!("in progress", registry.get(&in_progress).unwrap().summary.settings.name);
+
+        let user_id: Uuid = registry.get(&in_progress).unwrap().summary.players[0];
+        assert_eq!(
+            vec!["in progress", "open"],
+            names(registry.list(GameFilter::Available { user_id }))
+        );
+        let user_id: Uuid = registry.get(&finished).unwrap().summary.players[0];
+        assert_eq!(vec!["open"], names(registry.list(GameFilter::Available { user_id })));
+    }
+
+    #[test]
+    fn remove_finished() {
+        let mut registry: GameRegistry = GameRegistry::default();
+        let open: Uuid = register(&mut registry, "open", 0, GameStatus::Open);
+        let finished: Uuid = register(&mut registry, "finished", 0, GameStatus::Finished);
+
+        assert_eq!(1, registry.remove_finished());
+        assert!(registry.get(&finished).is_none());
+        assert!(registry.get(&open).is_some());
+        assert_eq!(0, registry.remove_finished());
+        assert_eq!(1, registry.len());
+    }
+}
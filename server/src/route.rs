@@ -1,49 +1,402 @@
-use shared::network::connection::WriteBufferT;
-use shared::network::protocol::{Acknowledgement, Frame, Heartbeat, OperationType, Register, _PlaceholderDynamic};
-
-pub async fn route_frame(write_buffer: WriteBufferT, frame: Frame) {
-    match frame.head.op_type {
-        OperationType::Heartbeat => {
-            log::trace!("Heartbeat received; [{}]", frame);
-            heartbeat(frame);
+use crate::account::{ACCOUNTS, Account};
+use crate::context::ConnectionContext;
+use crate::handler::{HandlerError, HandlerResult, Handlers};
+use crate::manager::ManagerError;
+use crate::registry::GameFilter;
+use crate::session::{SESSIONS, Session};
+use shared::error::AppError;
+use shared::map::Map;
+use shared::network::connection::{self, WriteBufferT};
+use shared::network::handshake::{self, PROTOCOL_VERSION};
+use shared::network::monitor::RouteResult;
+use shared::network::protocol::{
+    Acknowledgement, CreateAccount, CreateGame, Error, ErrorCode, Frame, GameJoined, GameList, GameMap, GameSummary,
+    GameUpdated, GetMap, Heartbeat, Hello, HelloAck, HelloReject, JoinGame, LeaveGame, ListGames, LoggedIn, Login,
+    Operation, OperationType, ProtocolError, Register, RequestId, Resume, ResumeReject, SessionGranted,
+};
+use std::sync::{Arc, LazyLock};
+use uuid::Uuid;
+
+pub async fn route_frame(context: Arc<ConnectionContext>, write_buffer: WriteBufferT, frame: Frame) -> RouteResult {
+    if !context.handshake_complete() {
+        return match frame.head.op_type {
+            OperationType::Hello => {
+                log::trace!("Hello received; [{}]", frame);
+                hello(&context, &write_buffer, frame).await
+            }
+            _ => {
+                log::warn!("Frame received before handshake; [{}] [{}]", context.peer_addr, frame);
+                reject(
+                    &write_buffer,
+                    HelloReject {
+                        protocol_version: PROTOCOL_VERSION,
+                        reason: String::from("Expected Hello as the first frame"),
+                    },
+                )
+                .await
+            }
+        };
+    }
+
+    let Some(handler) = HANDLERS.get(&frame.head.op_type.op_code()) else {
+        log::warn!("No handler for frame; [{}] [{}]", context.peer_addr, frame);
+        return protocol_error(&write_buffer, &frame, "Unsupported operation").await;
+    };
+    log::trace!("Routing frame; [{}]", frame);
+
+    let request_id: RequestId = frame.request_id().unwrap_or(0);
+    let error: Error = match handler(frame, context, write_buffer.clone()).await {
+        Ok(route_result) => return route_result,
+        Err(e) => {
+            log::error!("Failed to route frame; [{:?}] {:#}", e.code, e.error);
+            Error::from_app_error(request_id, e.code, &e.error)
         }
-        OperationType::Register => {
-            log::trace!("Register received; [{}]", frame);
-            register(frame);
+    };
+
+    // The connection remains usable after a failed handler
+    if let Err(e) = connection::send(&write_buffer, &error).await {
+        log::error!("Failed to send Error; {:#}", e);
+        return RouteResult::Close;
+    }
+    RouteResult::Continue
+}
+
+/// Handlers for every operation accepted once the handshake has completed
+static HANDLERS: LazyLock<Handlers> = LazyLock::new(|| {
+    let mut handlers: Handlers = Handlers::default();
+    handlers
+        .register(heartbeat)
+        .register(create_account)
+        .register(login)
+        .register(register)
+        .register(resume)
+        .register(acknowledgement)
+        .register(list_games)
+        .register(create_game)
+        .register(join_game)
+        .register(leave_game)
+        .register(get_map);
+    handlers
+});
+
+/// Tell the client that the frame was not accepted, leaving the connection open.
+async fn protocol_error(write_buffer: &WriteBufferT, frame: &Frame, reason: &str) -> RouteResult {
+    let protocol_error: ProtocolError = ProtocolError {
+        op_code: frame.head.op_type.op_code(),
+        reason: String::from(reason),
+    };
+    if let Err(e) = connection::send(write_buffer, &protocol_error).await {
+        log::error!("Failed to send ProtocolError; {:#}", e);
+        return RouteResult::Close;
+    }
+    RouteResult::Continue
+}
+
+async fn hello(context: &ConnectionContext, write_buffer: &WriteBufferT, frame: Frame) -> RouteResult {
+    let hello_r: Result<Hello, AppError> = Hello::from_frame(&frame);
+    let Ok(hello) = hello_r.inspect_err(|e| log::error!("Failed to parse Hello; {:#}", e)) else {
+        return reject(
+            write_buffer,
+            HelloReject {
+                protocol_version: PROTOCOL_VERSION,
+                reason: String::from("Malformed Hello"),
+            },
+        )
+        .await;
+    };
+    log::debug!("parsed frame; [{:?}]", hello);
+
+    match handshake::negotiate(&hello) {
+        Ok(hello_ack) => {
+            *context.capabilities.write().unwrap() = Some(hello_ack.capabilities);
+            send_hello_ack(write_buffer, hello_ack).await
         }
-        OperationType::Acknowledgement => {
-            log::trace!("Acknowledgement received; [{}]", frame);
-            acknowledgement(frame);
+        Err(hello_reject) => {
+            log::info!("Rejecting client; [{}] [{}]", context.peer_addr, hello_reject.reason);
+            reject(write_buffer, hello_reject).await
         }
-        OperationType::_PlaceholderDynamic => {
-            log::trace!("_PlaceholderDynamic received; [{}]", frame);
-            _placeholder_dynamic(frame);
+    }
+}
+
+async fn send_hello_ack(write_buffer: &WriteBufferT, hello_ack: HelloAck) -> RouteResult {
+    match connection::send(write_buffer, &hello_ack).await {
+        Ok(_) => RouteResult::Continue,
+        Err(e) => {
+            log::error!("Failed to send HelloAck; {:#}", e);
+            RouteResult::Close
         }
     }
 }
 
-fn heartbeat(frame: Frame) {
-    let heartbeat: Heartbeat = Heartbeat::from(&frame);
-    log::debug!("parsed frame; [{:?}]", heartbeat);
+async fn reject(write_buffer: &WriteBufferT, hello_reject: HelloReject) -> RouteResult {
+    if let Err(e) = connection::send(write_buffer, &hello_reject).await {
+        log::error!("Failed to send HelloReject; {:#}", e);
+    }
+    RouteResult::Close
+}
+
+async fn heartbeat(
+    _heartbeat: Heartbeat,
+    _context: Arc<ConnectionContext>,
+    _write_buffer: WriteBufferT,
+) -> HandlerResult {
+    Ok(RouteResult::Continue)
 }
 
-fn register(frame: Frame) {
-    let register: Register = Register::from(&frame);
-    log::debug!("parsed frame; [{:?}]", register);
+async fn create_account(
+    create_account: CreateAccount,
+    context: Arc<ConnectionContext>,
+    write_buffer: WriteBufferT,
+) -> HandlerResult {
+    let (account, account_token): (Account, Uuid) =
+        ACCOUNTS.create(&create_account.username, &create_account.password).await?;
+    log::info!("Account created; [{}] [user: {}]", context.peer_addr, account.user_id);
+    send_logged_in(&write_buffer, create_account.request_id, account, account_token).await
+}
+
+async fn login(login: Login, context: Arc<ConnectionContext>, write_buffer: WriteBufferT) -> HandlerResult {
+    let (account, account_token): (Account, Uuid) = ACCOUNTS.login(&login.username, &login.password).await?;
+    log::info!("Logged in; [{}] [user: {}]", context.peer_addr, account.user_id);
+    send_logged_in(&write_buffer, login.request_id, account, account_token).await
+}
 
-    // todo: send game collection to client
+async fn send_logged_in(
+    write_buffer: &WriteBufferT,
+    request_id: RequestId,
+    account: Account,
+    account_token: Uuid,
+) -> HandlerResult {
+    connection::send(
+        write_buffer,
+        &LoggedIn {
+            request_id,
+            user_id: account.user_id,
+            account_token,
+            username: account.username,
+        },
+    )
+    .await?;
+    Ok(RouteResult::Continue)
 }
 
-fn acknowledgement(frame: Frame) {
-    let acknowledgement: Acknowledgement = Acknowledgement::from(&frame);
-    log::debug!("parsed frame; [{:?}]", acknowledgement);
+async fn register(register: Register, context: Arc<ConnectionContext>, write_buffer: WriteBufferT) -> HandlerResult {
+    let Some(account) = ACCOUNTS.authenticate(&register.account_token) else {
+        log::info!("Unknown account token; [{}]", context.peer_addr);
+        return Err(HandlerError::new(
+            ErrorCode::Unauthorized,
+            AppError::new("Unknown or expired login; log in again"),
+        ));
+    };
+
+    if let Some(previous) = context.session() {
+        previous.detach(&write_buffer).await;
+    }
+    // Memberships are persisted with the games, so a new session finds the user's games even after a restart
+    let games: Vec<Uuid> = context
+        .manager
+        .list_games(GameFilter::Available {
+            user_id: account.user_id,
+        })
+        .await?
+        .into_iter()
+        .filter(|game| game.players.contains(&account.user_id))
+        .map(|game| game.id)
+        .collect();
+    let session: Arc<Session> = SESSIONS.create(account.user_id);
+    for game_id in &games {
+        session.join_game(*game_id).await;
+    }
+    session.attach(write_buffer.clone()).await;
+    *context.session.write().unwrap() = Some(session.clone());
+    log::info!(
+        "Session opened; [{}] [user: {}] [games: {}]",
+        context.peer_addr,
+        account.user_id,
+        games.len()
+    );
 
-    todo!();
+    connection::send(
+        &write_buffer,
+        &SessionGranted {
+            request_id: register.request_id,
+            session_token: session.token,
+            games,
+        },
+    )
+    .await?;
+    Ok(RouteResult::Continue)
+}
+
+async fn resume(resume: Resume, context: Arc<ConnectionContext>, write_buffer: WriteBufferT) -> HandlerResult {
+    let Some(session) = SESSIONS.get(&resume.session_token) else {
+        log::info!("Unknown session; [{}]", context.peer_addr);
+        connection::send(
+            &write_buffer,
+            &ResumeReject {
+                request_id: resume.request_id,
+                reason: String::from("Unknown or expired session"),
+            },
+        )
+        .await?;
+        return Ok(RouteResult::Continue);
+    };
+
+    if let Some(previous) = context.session() {
+        previous.detach(&write_buffer).await;
+    }
+    session.resume(resume.request_id, resume.last_sequence, write_buffer.clone()).await?;
+    *context.session.write().unwrap() = Some(session.clone());
+    log::info!(
+        "Session resumed; [{}] [user: {}] [last_sequence: {}]",
+        context.peer_addr,
+        session.user_id,
+        resume.last_sequence
+    );
+    Ok(RouteResult::Continue)
+}
+
+/// Acknowledgements of the server's own requests are delivered to the requester before routing.
+/// Any which arrive here answer no pending request.
+async fn acknowledgement(
+    acknowledgement: Acknowledgement,
+    _context: Arc<ConnectionContext>,
+    _write_buffer: WriteBufferT,
+) -> HandlerResult {
+    log::warn!("Unsolicited acknowledgement; [{:?}]", acknowledgement);
+    Ok(RouteResult::Continue)
+}
+
+/// The session of a client which has registered or resumed, as required by the lobby operations.
+fn require_session(context: &ConnectionContext) -> Result<Arc<Session>, HandlerError> {
+    context
+        .session()
+        .ok_or_else(|| HandlerError::new(ErrorCode::Rejected, AppError::new("Log in before joining games")))
+}
+
+async fn list_games(
+    list_games: ListGames,
+    context: Arc<ConnectionContext>,
+    write_buffer: WriteBufferT,
+) -> HandlerResult {
+    // A logged in user's own games are listed too, so that they can be entered again
+    let filter: GameFilter = match context.session() {
+        Some(session) => GameFilter::Available {
+            user_id: session.user_id,
+        },
+        None => GameFilter::Open,
+    };
+    let games: Vec<GameSummary> = context.manager.list_games(filter).await?;
+    connection::send(
+        &write_buffer,
+        &GameList {
+            request_id: list_games.request_id,
+            games,
+        },
+    )
+    .await?;
+    Ok(RouteResult::Continue)
+}
+
+/// Create a game and join it as its first player.
+async fn create_game(
+    create_game: CreateGame,
+    context: Arc<ConnectionContext>,
+    write_buffer: WriteBufferT,
+) -> HandlerResult {
+    let session: Arc<Session> = require_session(&context)?;
+    let game: GameSummary = context.manager.create_game(create_game.settings).await??;
+    let joined: Result<GameSummary, ManagerError> = context.manager.join_game(game.id, session.user_id).await?;
+    let game: GameSummary = match joined {
+        Ok(game) => game,
+        Err(e) => {
+            context.manager.delete_game(game.id).await??;
+            return Err(e.into());
+        }
+    };
+
+    connection::send(
+        &write_buffer,
+        &GameJoined {
+            request_id: create_game.request_id,
+            game: game.clone(),
+        },
+    )
+    .await?;
+    deliver_game_updated(&game, None).await;
+    Ok(RouteResult::Continue)
+}
+
+async fn join_game(join_game: JoinGame, context: Arc<ConnectionContext>, write_buffer: WriteBufferT) -> HandlerResult {
+    let session: Arc<Session> = require_session(&context)?;
+    let game: GameSummary = context.manager.join_game(join_game.game_id, session.user_id).await??;
+
+    connection::send(
+        &write_buffer,
+        &GameJoined {
+            request_id: join_game.request_id,
+            game: game.clone(),
+        },
+    )
+    .await?;
+    deliver_game_updated(&game, None).await;
+    Ok(RouteResult::Continue)
+}
+
+async fn leave_game(
+    leave_game: LeaveGame,
+    context: Arc<ConnectionContext>,
+    write_buffer: WriteBufferT,
+) -> HandlerResult {
+    let session: Arc<Session> = require_session(&context)?;
+    let game: GameSummary = context.manager.leave_game(leave_game.game_id, session.user_id).await??;
+
+    connection::send(
+        &write_buffer,
+        &Acknowledgement {
+            request_id: leave_game.request_id,
+            op_code_acknowledged: LeaveGame::OP_CODE,
+        },
+    )
+    .await?;
+    deliver_game_updated(&game, Some(session.user_id)).await;
+    Ok(RouteResult::Continue)
+}
+
+/// Deliver the game's lobby state to every session of its players, and of the player who has just left it.
+/// The sessions' games are updated first, so that a client resuming later is told the games it is in.
+async fn deliver_game_updated(game: &GameSummary, left_o: Option<Uuid>) {
+    let game_updated: GameUpdated = GameUpdated { game: game.clone() };
+    for user_id in game.players.iter().chain(left_o.iter()) {
+        for session in SESSIONS.of_user(user_id) {
+            if game.players.contains(user_id) {
+                session.join_game(game.id).await;
+            } else {
+                session.leave_game(&game.id).await;
+            }
+            if let Err(e) = session.deliver(&game_updated).await {
+                log::warn!(
+                    "Failed to deliver game update; [{}] [user: {}] {:#}",
+                    game.id,
+                    user_id,
+                    e
+                );
+            }
+        }
+    }
 }
 
-fn _placeholder_dynamic(frame: Frame) {
-    let _placeholder_dynamic: _PlaceholderDynamic = _PlaceholderDynamic::from(&frame);
-    log::debug!("parsed frame; [{:?}]", _placeholder_dynamic);
+async fn get_map(get_map: GetMap, context: Arc<ConnectionContext>, write_buffer: WriteBufferT) -> HandlerResult {
+    let session: Arc<Session> = require_session(&context)?;
+    let map: Arc<Map> = context.manager.get_map(get_map.game_id, session.user_id).await??;
 
-    todo!();
+    connection::send(
+        &write_buffer,
+        &GameMap {
+            request_id: get_map.request_id,
+            game_id: get_map.game_id,
+            map: Map::clone(&map),
+        },
+    )
+    .await?;
+    Ok(RouteResult::Continue)
 }
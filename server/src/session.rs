@@ -0,0 +1,281 @@
+//! Sessions outlive connections so that a client can reconnect without losing its place.
+//! Sessions are held in memory only and do not survive a server restart; the client then registers anew,
+//! and the new session is given the user's games from their persisted memberships.
+
+use shared::error::AppError;
+use shared::network::connection::{self, WriteBufferT};
+use shared::network::protocol::{Deliver, Operation, RequestId, Resumed, Sequence};
+use shared::random::random_uuid;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Number of delivered frames retained per session for replay
+pub const OUTBOX_CAPACITY: usize = 1024;
+/// Sessions without a connection for this long can no longer be resumed
+pub const SESSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+pub static SESSIONS: LazyLock<Sessions> = LazyLock::new(Sessions::default);
+
+#[derive(Debug, Default)]
+pub struct Sessions {
+    by_token: RwLock<HashMap<Uuid, Arc<Session>>>,
+}
+
+impl Sessions {
+    pub fn create(&self, user_id: Uuid) -> Arc<Session> {
+        let session: Arc<Session> = Arc::new(Session::new(random_uuid(), user_id));
+        self.by_token.write().unwrap().insert(session.token, session.clone());
+        session
+    }
+
+    pub fn get(&self, session_token: &Uuid) -> Option<Arc<Session>> {
+        self.by_token.read().unwrap().get(session_token).cloned()
+    }
+
+    /// Every session of the user, one for each client they have registered.
+    pub fn of_user(&self, user_id: &Uuid) -> Vec<Arc<Session>> {
+        self.by_token.read().unwrap().values().filter(|session| session.user_id == *user_id).cloned().collect()
+    }
+
+    /// Remove sessions which have been detached for longer than the time to live.
+    /// Returns the number of sessions removed.
+    pub async fn expire(&self, ttl: Duration) -> usize {
+        let sessions: Vec<Arc<Session>> = self.by_token.read().unwrap().values().cloned().collect();
+
+        let mut expired: Vec<Uuid> = Vec::new();
+        for session in sessions {
+            if session.detached_for().await.is_some_and(|detached_for| detached_for > ttl) {
+                expired.push(session.token);
+            }
+        }
+
+        let mut by_token = self.by_token.write().unwrap();
+        for session_token in &expired {
+            by_token.remove(session_token);
+        }
+        expired.len()
+    }
+}
+
+#[derive(Debug)]
+pub struct Session {
+    pub token: Uuid,
+    pub user_id: Uuid,
+    state: Mutex<SessionState>,
+    /// Held while frames are sent, so that they reach the connection in sequence order.
+    /// The state is never locked across a send, which may wait for the peer.
+    sending: Mutex<()>,
+}
+
+#[derive(Debug)]
+struct SessionState {
+    next_sequence: Sequence,
+    /// The most recently delivered frames, oldest first
+    outbox: VecDeque<Deliver>,
+    /// The write buffer of the session's current connection
+    write_buffer: Option<WriteBufferT>,
+    detached_at: Option<Instant>,
+    games: Vec<Uuid>,
+}
+
+impl Session {
+    fn new(token: Uuid, user_id: Uuid) -> Self {
+        Session {
+            token,
+            user_id,
+            state: Mutex::new(SessionState {
+                next_sequence: 1,
+                outbox: VecDeque::new(),
+                write_buffer: None,
+                detached_at: Some(Instant::now()),
+                games: Vec::new(),
+            }),
+            sending: Mutex::new(()),
+        }
+    }
+
+    /// Direct deliveries to the connection owning the write buffer.
+    pub async fn attach(&self, write_buffer: WriteBufferT) {
+        let mut state_g: MutexGuard<SessionState> = self.state.lock().await;
+        state_g.write_buffer = Some(write_buffer);
+        state_g.detached_at = None;
+    }
+
+    /// Stop deliveries to the connection owning the write buffer.
+    /// Has no effect if the session has since been attached to another connection.
+    pub async fn detach(&self, write_buffer: &WriteBufferT) {
+        let mut state_g: MutexGuard<SessionState> = self.state.lock().await;
+        if state_g.write_buffer.as_ref().is_some_and(|attached| Arc::ptr_eq(attached, write_buffer)) {
+            state_g.write_buffer = None;
+            state_g.detached_at = Some(Instant::now());
+        }
+    }
+
+    async fn detached_for(&self) -> Option<Duration> {
+        self.state.lock().await.detached_at.map(|detached_at| detached_at.elapsed())
+    }
+
+    /// Push a frame to the session, retaining it for replay.
+    /// If the session is detached, the frame is only retained.
+    pub async fn deliver<T: Operation>(&self, operation: &T) -> Result<(), AppError> {
+        let _sending_g: MutexGuard<()> = self.sending.lock().await;
+        let mut state_g: MutexGuard<SessionState> = self.state.lock().await;
+        let deliver: Deliver = Deliver {
+            sequence: state_g.next_sequence,
+            frame: operation.as_bytes(),
+        };
+        state_g.next_sequence += 1;
+
+        if state_g.outbox.len() == OUTBOX_CAPACITY {
+            state_g.outbox.pop_front();
+        }
+        state_g.outbox.push_back(deliver.clone());
+        let write_buffer_o: Option<WriteBufferT> = state_g.write_buffer.clone();
+        drop(state_g);
+
+        match write_buffer_o {
+            Some(write_buffer) => connection::send(&write_buffer, &deliver).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Attach the session to a new connection, answer the [shared::network::protocol::Resume] request,
+    /// then replay every frame delivered after the client's last received sequence.
+    pub async fn resume(
+        &self,
+        request_id: RequestId,
+        last_sequence: Sequence,
+        write_buffer: WriteBufferT,
+    ) -> Result<(), AppError> {
+        let _sending_g: MutexGuard<()> = self.sending.lock().await;
+        let mut state_g: MutexGuard<SessionState> = self.state.lock().await;
+
+        let first_retained: Sequence = state_g.outbox.front().map_or(state_g.next_sequence, |deliver| deliver.sequence);
+        let replay: Vec<Deliver> =
+            state_g.outbox.iter().filter(|deliver| deliver.sequence > last_sequence).cloned().collect();
+
+        let resumed: Resumed = Resumed {
+            request_id,
+            games: state_g.games.clone(),
+            replayed: replay.len() as u32,
+            incomplete: last_sequence + 1 < first_retained,
+        };
+        // Frames delivered from now on wait for the replay to be sent
+        state_g.write_buffer = Some(write_buffer.clone());
+        state_g.detached_at = None;
+        drop(state_g);
+
+        connection::send(&write_buffer, &resumed).await?;
+        for deliver in &replay {
+            connection::send(&write_buffer, deliver).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn join_game(&self, game_id: Uuid) {
+        let mut state_g: MutexGuard<SessionState> = self.state.lock().await;
+        if !state_g.games.contains(&game_id) {
+            state_g.games.push(game_id);
+        }
+    }
+
+    pub async fn leave_game(&self, game_id: &Uuid) {
+        self.state.lock().await.games.retain(|id| id != game_id);
+    }
+
+    pub async fn games(&self) -> Vec<Uuid> {
+        self.state.lock().await.games.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::network::protocol::{_PlaceholderDynamic, Frame};
+    use shared::network::ring_buffer::RingBuffer;
+    use shared::network::write_buffer::WriteBuffer;
+    use tokio::time;
+
+    fn new_write_buffer() -> WriteBufferT {
+        Arc::new(WriteBuffer::new(connection::new_buffer()))
+    }
+
+    async fn written_frames(write_buffer: &WriteBufferT) -> Vec<Frame> {
+        write_buffer.pop_frames().await.unwrap()
+    }
+
+    fn placeholder(i: usize) -> _PlaceholderDynamic {
+        _PlaceholderDynamic { string: i.to_string() }
+    }
+
+    #[tokio::test]
+    async fn resume_replays_missed_frames() {
+        let session: Session = Session::new(random_uuid(), random_uuid());
+        let first: WriteBufferT = new_write_buffer();
+        session.attach(first.clone()).await;
+        session.deliver(&placeholder(1)).await.unwrap();
+        assert_eq!(1, written_frames(&first).await.len());
+
+        session.detach(&first).await;
+        session.deliver(&placeholder(2)).await.unwrap();
+        session.deliver(&placeholder(3)).await.unwrap();
+        assert!(written_frames(&first).await.is_empty());
+
+        let second: WriteBufferT = new_write_buffer();
+        session.resume(9, 1, second.clone()).await.unwrap();
+        let frames: Vec<Frame> = written_frames(&second).await;
+        assert_eq!(3, frames.len());
+
+        let resumed: Resumed = Resumed::from_frame(&frames[0]).unwrap();
+        assert_eq!(9, resumed.request_id);
+        assert_eq!(2, resumed.replayed);
+        assert!(!resumed.incomplete);
+        let deliver: Deliver = Deliver::from_frame(&frames[1]).unwrap();
+        assert_eq!(2, deliver.sequence);
+
+        // A stale connection no longer detaches the session
+        session.detach(&first).await;
+        assert!(session.detached_for().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn resume_incomplete() {
+        let session: Session = Session::new(random_uuid(), random_uuid());
+        for i in 0..(OUTBOX_CAPACITY + 1) {
+            session.deliver(&placeholder(i)).await.unwrap();
+        }
+
+        let write_buffer: WriteBufferT = new_write_buffer();
+        session.resume(1, 0, write_buffer.clone()).await.unwrap();
+        let frames: Vec<Frame> = written_frames(&write_buffer).await;
+        let resumed: Resumed = Resumed::from_frame(&frames[0]).unwrap();
+        assert_eq!(OUTBOX_CAPACITY as u32, resumed.replayed);
+        assert!(resumed.incomplete);
+    }
+
+    #[tokio::test]
+    async fn closed_connection_does_not_block_session() {
+        let session: Arc<Session> = Arc::new(Session::new(random_uuid(), random_uuid()));
+        let write_buffer: WriteBufferT = Arc::new(WriteBuffer::new(RingBuffer::new(64)));
+        write_buffer.push(&[0; 60]).await.unwrap();
+        session.attach(write_buffer.clone()).await;
+
+        let deliver = tokio::spawn({
+            let session: Arc<Session> = session.clone();
+            async move { session.deliver(&placeholder(1)).await.is_err() }
+        });
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!deliver.is_finished());
+        // The pending send holds no lock on the session's state
+        time::timeout(Duration::from_secs(1), session.detach(&write_buffer)).await.unwrap();
+
+        write_buffer.close();
+        assert!(time::timeout(Duration::from_secs(1), deliver).await.unwrap().unwrap());
+        session.deliver(&placeholder(2)).await.unwrap();
+        assert_eq!(2, session.state.lock().await.outbox.len());
+    }
+}
@@ -0,0 +1,42 @@
+//! Durable server state, restored when the server starts.
+//! The in-memory structures remain authoritative while running; changes are written through to [Storage].
+
+pub mod sqlite;
+
+use crate::account::Account;
+use crate::journal::{JournalEntry, Snapshot};
+use shared::error::AppError;
+use shared::network::protocol::GameSummary;
+use shared::random::GameSeed;
+use std::fmt::Debug;
+use uuid::Uuid;
+
+pub type StorageResult<T> = Result<T, AppError>;
+
+/// Calls block until the write is durable, so they should be brief.
+pub trait Storage: Debug + Send + Sync {
+    fn insert_account(&self, account: &Account) -> StorageResult<()>;
+    fn load_accounts(&self) -> StorageResult<Vec<Account>>;
+    fn insert_account_token(&self, account_token: &Uuid, user_id: &Uuid) -> StorageResult<()>;
+    /// Pairs of account token and user ID
+    fn load_account_tokens(&self) -> StorageResult<Vec<(Uuid, Uuid)>>;
+
+    /// Insert or replace the game's metadata, seed and memberships.
+    fn save_game(&self, game: &GameSummary, seed: GameSeed) -> StorageResult<()>;
+    /// Delete the game along with its memberships, snapshot and journal.
+    fn delete_game(&self, game_id: &Uuid) -> StorageResult<()>;
+    /// Stop restoring the game, keeping its snapshot and journal so that it can still be replayed.
+    fn archive_game(&self, game_id: &Uuid) -> StorageResult<()>;
+    /// Every saved game which has not been archived, with its seed, and with its players in order of joining
+    fn load_games(&self) -> StorageResult<Vec<(GameSummary, GameSeed)>>;
+
+    /// Replace the game's snapshot. The game must have been saved.
+    fn save_snapshot(&self, game_id: &Uuid, snapshot: &Snapshot) -> StorageResult<()>;
+    /// The game's latest snapshot, if one has been saved
+    fn load_snapshot(&self, game_id: &Uuid) -> StorageResult<Option<Snapshot>>;
+
+    /// Append entries to the game's journal, all or none. The game must have been saved.
+    fn append_journal(&self, game_id: &Uuid, entries: &[JournalEntry]) -> StorageResult<()>;
+    /// The game's journal entries after the sequence number, in order
+    fn load_journal(&self, game_id: &Uuid, after_sequence: u64) -> StorageResult<Vec<JournalEntry>>;
+}
@@ -0,0 +1,511 @@
+//! [Storage] in an embedded SQLite database.
+//! The schema is created and upgraded by [MIGRATIONS]; the version applied is kept in `PRAGMA user_version`.
+
+use crate::account::Account;
+use crate::game::GameState;
+use crate::journal::{GameEvent, JournalEntry, Snapshot};
+use crate::storage::{Storage, StorageResult};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use shared::error::AppError;
+use shared::map::{MapGeneratorType, MapSize};
+use shared::network::codec::{ByteReader, Decode, Encode};
+use shared::network::protocol::{GameSettings, GameStatus, GameSummary};
+use shared::random::GameSeed;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Applied in order; the schema version is the number applied.
+/// Released migrations must never be edited, only appended to.
+const MIGRATIONS: &[&str] = &[
+    // 1: accounts, games, memberships and snapshots
+    "CREATE TABLE accounts (
+        user_id BLOB PRIMARY KEY NOT NULL,
+        username TEXT NOT NULL,
+        username_key TEXT NOT NULL UNIQUE,
+        password_hash TEXT NOT NULL
+    );
+    CREATE TABLE account_tokens (
+        account_token BLOB PRIMARY KEY NOT NULL,
+        user_id BLOB NOT NULL REFERENCES accounts (user_id) ON DELETE CASCADE
+    );
+    CREATE TABLE games (
+        game_id BLOB PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        max_players INTEGER NOT NULL,
+        status INTEGER NOT NULL
+    );
+    CREATE TABLE memberships (
+        game_id BLOB NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+        user_id BLOB NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (game_id, user_id)
+    );
+    CREATE TABLE snapshots (
+        game_id BLOB PRIMARY KEY NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+        tick INTEGER NOT NULL,
+        state BLOB NOT NULL
+    );",
+    // 2: the journal, and the journal sequence each snapshot was taken at
+    "ALTER TABLE snapshots ADD COLUMN sequence INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE journal (
+        game_id BLOB NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+        sequence INTEGER NOT NULL,
+        tick INTEGER NOT NULL,
+        event BLOB NOT NULL,
+        PRIMARY KEY (game_id, sequence)
+    );",
+    // 3: the seed each game's map is generated from; earlier games are given seed 0
+    "ALTER TABLE games ADD COLUMN seed INTEGER NOT NULL DEFAULT 0;",
+    // 4: the generator each game's map is generated with; earlier games are given the default
+    "ALTER TABLE games ADD COLUMN map_generator INTEGER NOT NULL DEFAULT 0;",
+    // 5: each game's map size; earlier games were all played on the default
+    "ALTER TABLE games ADD COLUMN map_width INTEGER NOT NULL DEFAULT 64;
+     ALTER TABLE games ADD COLUMN map_height INTEGER NOT NULL DEFAULT 64;",
+    // 6: finished games are archived, keeping their journals, rather than deleted
+    "ALTER TABLE games ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;",
+];
+
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open the database, creating it if necessary, and apply any pending migrations.
+    pub fn open(path: &Path) -> Result<SqliteStorage, AppError> {
+        log::info!("Opening database; [{}]", path.display());
+        Self::from_connection(Connection::open(path).map_err(sql_error)?)
+    }
+
+    /// A private database which is discarded when dropped
+    pub fn open_in_memory() -> Result<SqliteStorage, AppError> {
+        Self::from_connection(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<SqliteStorage, AppError> {
+        connection.pragma_update(None, "foreign_keys", true).map_err(sql_error)?;
+        migrate(&mut connection)?;
+        Ok(SqliteStorage {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn schema_version(&self) -> Result<usize, AppError> {
+        schema_version(&self.connection.lock().unwrap())
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap()
+    }
+}
+
+fn schema_version(connection: &Connection) -> Result<usize, AppError> {
+    let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(sql_error)?;
+    Ok(version as usize)
+}
+
+/// Apply every migration newer than the database, each in its own transaction.
+fn migrate(connection: &mut Connection) -> Result<(), AppError> {
+    let current: usize = schema_version(connection)?;
+    if current > MIGRATIONS.len() {
+        return Err(AppError::new(&format!(
+            "Database schema is newer than this build; [database: {}] [latest: {}]",
+            current,
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version: usize = index + 1;
+        let transaction: Transaction = connection.transaction().map_err(sql_error)?;
+        transaction.execute_batch(migration).map_err(sql_error)?;
+        transaction.pragma_update(None, "user_version", version as i64).map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)?;
+        log::info!("Applied database migration; [version: {}]", version);
+    }
+    Ok(())
+}
+
+fn sql_error(error: rusqlite::Error) -> AppError {
+    AppError::from_error(&error.to_string(), Box::new(error))
+}
+
+impl Storage for SqliteStorage {
+    fn insert_account(&self, account: &Account) -> StorageResult<()> {
+        self.connection()
+            .execute(
+                "INSERT INTO accounts (user_id, username, username_key, password_hash) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    account.user_id,
+                    account.username,
+                    account.username.to_lowercase(),
+                    account.password_hash
+                ],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn load_accounts(&self) -> StorageResult<Vec<Account>> {
+        let connection: MutexGuard<Connection> = self.connection();
+        let mut statement =
+            connection.prepare("SELECT user_id, username, password_hash FROM accounts").map_err(sql_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(Account {
+                    user_id: row.get(0)?,
+                    username: row.get(1)?,
+                    password_hash: row.get(2)?,
+                })
+            })
+            .map_err(sql_error)?;
+        rows.collect::<Result<Vec<Account>, rusqlite::Error>>().map_err(sql_error)
+    }
+
+    fn insert_account_token(&self, account_token: &Uuid, user_id: &Uuid) -> StorageResult<()> {
+        self.connection()
+            .execute(
+                "INSERT INTO account_tokens (account_token, user_id) VALUES (?1, ?2)",
+                params![account_token, user_id],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn load_account_tokens(&self) -> StorageResult<Vec<(Uuid, Uuid)>> {
+        let connection: MutexGuard<Connection> = self.connection();
+        let mut statement =
+            connection.prepare("SELECT account_token, user_id FROM account_tokens").map_err(sql_error)?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).map_err(sql_error)?;
+        rows.collect::<Result<Vec<(Uuid, Uuid)>, rusqlite::Error>>().map_err(sql_error)
+    }
+
+    fn save_game(&self, game: &GameSummary, seed: GameSeed) -> StorageResult<()> {
+        let mut connection: MutexGuard<Connection> = self.connection();
+        let transaction: Transaction = connection.transaction().map_err(sql_error)?;
+        transaction
+            .execute(
+                "INSERT INTO games (game_id, name, max_players, status, seed, map_generator, map_width, map_height)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (game_id) DO UPDATE
+                 SET name = excluded.name, max_players = excluded.max_players, status = excluded.status,
+                     seed = excluded.seed, map_generator = excluded.map_generator,
+                     map_width = excluded.map_width, map_height = excluded.map_height",
+                params![
+                    game.id,
+                    game.settings.name,
+                    game.settings.max_players,
+                    game.status as u8,
+                    // SQLite integers are signed; the bits are kept as they are
+                    seed.0 as i64,
+                    game.settings.map_generator as u8,
+                    game.settings.map_size.width,
+                    game.settings.map_size.height
+                ],
+            )
+            .map_err(sql_error)?;
+        transaction.execute("DELETE FROM memberships WHERE game_id = ?1", params![game.id]).map_err(sql_error)?;
+        for (position, user_id) in game.players.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO memberships (game_id, user_id, position) VALUES (?1, ?2, ?3)",
+                    params![game.id, user_id, position as i64],
+                )
+                .map_err(sql_error)?;
+        }
+        transaction.commit().map_err(sql_error)
+    }
+
+    fn delete_game(&self, game_id: &Uuid) -> StorageResult<()> {
+        // Memberships, the snapshot and the journal cascade
+        self.connection().execute("DELETE FROM games WHERE game_id = ?1", params![game_id]).map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn archive_game(&self, game_id: &Uuid) -> StorageResult<()> {
+        let updated: usize = self
+            .connection()
+            .execute("UPDATE games SET archived = 1 WHERE game_id = ?1", params![game_id])
+            .map_err(sql_error)?;
+        if updated == 0 {
+            return Err(AppError::new(&format!("No saved game to archive; [{}]", game_id)));
+        }
+        Ok(())
+    }
+
+    fn load_games(&self) -> StorageResult<Vec<(GameSummary, GameSeed)>> {
+        let connection: MutexGuard<Connection> = self.connection();
+
+        let mut statement = connection
+            .prepare("SELECT game_id, user_id FROM memberships ORDER BY game_id, position")
+            .map_err(sql_error)?;
+        let memberships =
+            statement.query_map([], |row| Ok((row.get::<_, Uuid>(0)?, row.get::<_, Uuid>(1)?))).map_err(sql_error)?;
+        let mut players: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for membership in memberships {
+            let (game_id, user_id): (Uuid, Uuid) = membership.map_err(sql_error)?;
+            players.entry(game_id).or_default().push(user_id);
+        }
+
+        let mut statement = connection
+            .prepare("SELECT game_id, name, max_players, status, seed, map_generator, map_width, map_height FROM games WHERE archived = 0")
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Uuid>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u8>(2)?,
+                    row.get::<_, u8>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, u8>(5)?,
+                    row.get::<_, i16>(6)?,
+                    row.get::<_, i16>(7)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        let mut games: Vec<(GameSummary, GameSeed)> = Vec::new();
+        for row in rows {
+            let (id, name, max_players, status, seed, map_generator, map_width, map_height): (
+                Uuid,
+                String,
+                u8,
+                u8,
+                i64,
+                u8,
+                i16,
+                i16,
+            ) = row.map_err(sql_error)?;
+            let game: GameSummary = GameSummary {
+                id,
+                settings: GameSettings {
+                    name,
+                    max_players,
+                    map_generator: MapGeneratorType::from_u8(map_generator)?,
+                    map_size: MapSize::new(map_width, map_height)?,
+                },
+                players: players.remove(&id).unwrap_or_default(),
+                status: GameStatus::from_u8(status)?,
+            };
+            games.push((game, GameSeed(seed as u64)));
+        }
+        Ok(games)
+    }
+
+    fn save_snapshot(&self, game_id: &Uuid, snapshot: &Snapshot) -> StorageResult<()> {
+        let mut bytes: Vec<u8> = Vec::new();
+        snapshot.state.encode(&mut bytes);
+        self.connection()
+            .execute(
+                "INSERT OR REPLACE INTO snapshots (game_id, tick, state, sequence) VALUES (?1, ?2, ?3, ?4)",
+                params![game_id, snapshot.state.tick as i64, bytes, snapshot.sequence as i64],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn load_snapshot(&self, game_id: &Uuid) -> StorageResult<Option<Snapshot>> {
+        let row_o: Option<(i64, Vec<u8>)> = self
+            .connection()
+            .query_row(
+                "SELECT sequence, state FROM snapshots WHERE game_id = ?1",
+                params![game_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        let Some((sequence, bytes)) = row_o else {
+            return Ok(None);
+        };
+
+        Ok(Some(Snapshot {
+            sequence: sequence as u64,
+            state: decode::<GameState>(&bytes)?,
+        }))
+    }
+
+    fn append_journal(&self, game_id: &Uuid, entries: &[JournalEntry]) -> StorageResult<()> {
+        let mut connection: MutexGuard<Connection> = self.connection();
+        let transaction: Transaction = connection.transaction().map_err(sql_error)?;
+        for entry in entries {
+            let mut bytes: Vec<u8> = Vec::new();
+            entry.event.encode(&mut bytes);
+            transaction
+                .execute(
+                    "INSERT INTO journal (game_id, sequence, tick, event) VALUES (?1, ?2, ?3, ?4)",
+                    params![game_id, entry.sequence as i64, entry.tick as i64, bytes],
+                )
+                .map_err(sql_error)?;
+        }
+        transaction.commit().map_err(sql_error)
+    }
+
+    fn load_journal(&self, game_id: &Uuid, after_sequence: u64) -> StorageResult<Vec<JournalEntry>> {
+        let connection: MutexGuard<Connection> = self.connection();
+        let mut statement = connection
+            .prepare("SELECT sequence, tick, event FROM journal WHERE game_id = ?1 AND sequence > ?2 ORDER BY sequence")
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params![game_id, after_sequence as i64], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, Vec<u8>>(2)?))
+            })
+            .map_err(sql_error)?;
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for row in rows {
+            let (sequence, tick, bytes): (i64, i64, Vec<u8>) = row.map_err(sql_error)?;
+            entries.push(JournalEntry {
+                sequence: sequence as u64,
+                tick: tick as u64,
+                event: decode::<GameEvent>(&bytes)?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// Decode a value which must fill the whole blob
+fn decode<T: Decode>(bytes: &[u8]) -> Result<T, AppError> {
+    let mut reader: ByteReader = ByteReader::new(bytes);
+    let value: T = T::decode(&mut reader)?;
+    reader.finish()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::random::random_uuid;
+
+    fn game(players: Vec<Uuid>) -> GameSummary {
+        GameSummary {
+            id: random_uuid(),
+            settings: GameSettings {
+                name: String::from("Stored"),
+                max_players: 4,
+                map_generator: MapGeneratorType::ParticleRepulsion,
+                map_size: MapSize { width: 96, height: 64 },
+            },
+            players,
+            status: GameStatus::Open,
+        }
+    }
+
+    #[test]
+    fn migrations() {
+        let storage: SqliteStorage = SqliteStorage::open_in_memory().unwrap();
+        assert_eq!(MIGRATIONS.len(), storage.schema_version().unwrap());
+
+        // Reapplying is a no-op
+        let mut connection: MutexGuard<Connection> = storage.connection();
+        migrate(&mut connection).unwrap();
+        assert_eq!(MIGRATIONS.len(), schema_version(&connection).unwrap());
+
+        connection.pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1).unwrap();
+        assert!(migrate(&mut connection).is_err());
+    }
+
+    #[test]
+    fn accounts() {
+        let storage: SqliteStorage = SqliteStorage::open_in_memory().unwrap();
+        let account: Account = Account {
+            user_id: random_uuid(),
+            username: String::from("Player"),
+            password_hash: String::from("$argon2id$hash"),
+        };
+        storage.insert_account(&account).unwrap();
+        let account_token: Uuid = random_uuid();
+        storage.insert_account_token(&account_token, &account.user_id).unwrap();
+
+        assert_eq!(vec![account.clone()], storage.load_accounts().unwrap());
+        assert_eq!(
+            vec![(account_token, account.user_id)],
+            storage.load_account_tokens().unwrap()
+        );
+
+        let duplicate: Account = Account {
+            user_id: random_uuid(),
+            username: String::from("PLAYER"),
+            ..account
+        };
+        assert!(storage.insert_account(&duplicate).is_err());
+        assert!(storage.insert_account_token(&random_uuid(), &random_uuid()).is_err());
+    }
+
+    #[test]
+    fn games_and_snapshots() {
+        let storage: SqliteStorage = SqliteStorage::open_in_memory().unwrap();
+        let (first, second): (Uuid, Uuid) = (random_uuid(), random_uuid());
+        let mut game: GameSummary = game(vec![second, first]);
+        // Seeds use every bit, though SQLite integers are signed
+        let seed: GameSeed = GameSeed(u64::MAX - 1);
+        storage.save_game(&game, seed).unwrap();
+        assert_eq!(vec![(game.clone(), seed)], storage.load_games().unwrap());
+
+        game.players.remove(0);
+        game.status = GameStatus::InProgress;
+        storage.save_game(&game, seed).unwrap();
+        assert_eq!(vec![(game.clone(), seed)], storage.load_games().unwrap());
+
+        assert_eq!(None, storage.load_snapshot(&game.id).unwrap());
+        let snapshot: Snapshot = Snapshot {
+            sequence: 50,
+            state: GameState {
+                tick: 42,
+                players: game.players.clone(),
+            },
+        };
+        storage.save_snapshot(&game.id, &snapshot).unwrap();
+        assert_eq!(Some(snapshot), storage.load_snapshot(&game.id).unwrap());
+        assert!(storage.save_snapshot(&random_uuid(), &Snapshot::default()).is_err());
+
+        storage.delete_game(&game.id).unwrap();
+        assert!(storage.load_games().unwrap().is_empty());
+        assert_eq!(None, storage.load_snapshot(&game.id).unwrap());
+    }
+
+    #[test]
+    fn journal() {
+        let storage: SqliteStorage = SqliteStorage::open_in_memory().unwrap();
+        let game: GameSummary = game(Vec::new());
+        storage.save_game(&game, GameSeed(1)).unwrap();
+
+        let mut snapshot: Snapshot = Snapshot::default();
+        let entries: Vec<JournalEntry> = vec![
+            snapshot.record(GameEvent::PlayerAdded { user_id: random_uuid() }),
+            snapshot.record(GameEvent::PlayerAdded { user_id: random_uuid() }),
+            snapshot.record(GameEvent::PlayerRemoved { user_id: random_uuid() }),
+        ];
+        storage.append_journal(&game.id, &entries[..1]).unwrap();
+        storage.append_journal(&game.id, &entries[1..]).unwrap();
+        assert_eq!(entries, storage.load_journal(&game.id, 0).unwrap());
+        assert_eq!(entries[2..], storage.load_journal(&game.id, 2).unwrap());
+
+        // Appending is all or none
+        assert!(
+            storage
+                .append_journal(
+                    &game.id,
+                    &[
+                        snapshot.record(GameEvent::PlayerAdded { user_id: random_uuid() }),
+                        entries[0].clone()
+                    ]
+                )
+                .is_err()
+        );
+        assert_eq!(3, storage.load_journal(&game.id, 0).unwrap().len());
+        assert!(storage.append_journal(&random_uuid(), &entries).is_err());
+
+        // Archived games are no longer restored, but keep their journals
+        storage.archive_game(&game.id).unwrap();
+        assert!(storage.load_games().unwrap().is_empty());
+        assert_eq!(entries, storage.load_journal(&game.id, 0).unwrap());
+        assert!(storage.archive_game(&random_uuid()).is_err());
+
+        storage.delete_game(&game.id).unwrap();
+        assert!(storage.load_journal(&game.id, 0).unwrap().is_empty());
+    }
+}
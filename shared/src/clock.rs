@@ -0,0 +1,282 @@
+//! Time as seen by the simulation. Games read and wait on a [GameClock] rather than the wall clock, so that tests
+//! can pause, step and accelerate time with a [SimulatedClock].
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::{self, Instant};
+
+pub type ClockFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+pub trait GameClock: Debug + Send + Sync {
+    /// Time elapsed on this clock since it was created
+    fn now(&self) -> Duration;
+    /// Completes once [GameClock::now] has reached the deadline
+    fn sleep_until(&self, deadline: Duration) -> ClockFuture<'_>;
+}
+
+/// Follows the wall clock
+#[derive(Debug)]
+pub struct RealTimeClock {
+    start: Instant,
+}
+
+impl RealTimeClock {
+    pub fn new() -> RealTimeClock {
+        RealTimeClock { start: Instant::now() }
+    }
+}
+
+impl Default for RealTimeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameClock for RealTimeClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> ClockFuture<'_> {
+        Box::pin(time::sleep_until(self.start + deadline))
+    }
+}
+
+/// Runs at a multiple of the wall clock's speed, and may be paused and stepped
+#[derive(Debug)]
+pub struct SimulatedClock {
+    state: Mutex<SimulatedState>,
+    /// Wakes sleepers whenever the state changes
+    changed: watch::Sender<()>,
+}
+
+#[derive(Debug)]
+struct SimulatedState {
+    /// Simulated time at the anchor
+    anchor: Duration,
+    /// Wall clock time at the anchor
+    anchor_instant: Instant,
+    speed: f64,
+    paused: bool,
+}
+
+impl SimulatedState {
+    fn now(&self) -> Duration {
+        if self.paused {
+            return self.anchor;
+        }
+        self.anchor + self.anchor_instant.elapsed().mul_f64(self.speed)
+    }
+
+    /// Move the anchor to the present, before the speed or pause changes
+    fn reanchor(&mut self) {
+        self.anchor = self.now();
+        self.anchor_instant = Instant::now();
+    }
+}
+
+impl SimulatedClock {
+    /// A running clock. Panics unless the speed is positive and finite.
+    pub fn new(speed: f64) -> SimulatedClock {
+        assert_speed(speed);
+        Self::from_state(speed, false)
+    }
+
+    /// A clock which stands still at zero until stepped or resumed
+    pub fn paused() -> SimulatedClock {
+        Self::from_state(1.0, true)
+    }
+
+    fn from_state(speed: f64, paused: bool) -> SimulatedClock {
+        SimulatedClock {
+            state: Mutex::new(SimulatedState {
+                anchor: Duration::ZERO,
+                anchor_instant: Instant::now(),
+                speed,
+                paused,
+            }),
+            changed: watch::Sender::new(()),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.update(|state| {
+            state.reanchor();
+            state.paused = true;
+        });
+    }
+
+    pub fn resume(&self) {
+        self.update(|state| {
+            state.reanchor();
+            state.paused = false;
+        });
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Panics unless the speed is positive and finite.
+    pub fn set_speed(&self, speed: f64) {
+        assert_speed(speed);
+        self.update(|state| {
+            state.reanchor();
+            state.speed = speed;
+        });
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.state.lock().unwrap().speed
+    }
+
+    /// Advance the clock at once, whether or not it is paused.
+    /// Sleepers whose deadlines have passed wake, so a [Ticker] catches up every tick stepped over.
+    pub fn step(&self, duration: Duration) {
+        self.update(|state| state.anchor += duration);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut SimulatedState)) {
+        change(&mut self.state.lock().unwrap());
+        self.changed.send_replace(());
+    }
+}
+
+impl GameClock for SimulatedClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().now()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> ClockFuture<'_> {
+        Box::pin(async move {
+            let mut changed: watch::Receiver<()> = self.changed.subscribe();
+            loop {
+                // None while paused, as only a change can bring the deadline closer
+                let wait_o: Option<Duration> = {
+                    let state = self.state.lock().unwrap();
+                    let now: Duration = state.now();
+                    if now >= deadline {
+                        return;
+                    }
+                    (!state.paused).then(|| (deadline - now).div_f64(state.speed))
+                };
+                match wait_o {
+                    Some(wait) => {
+                        tokio::select! {
+                            _ = time::sleep(wait) => {}
+                            _ = changed.changed() => {}
+                        }
+                    }
+                    None => {
+                        // The sender lives as long as the clock, which outlives this future
+                        let _ = changed.changed().await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn assert_speed(speed: f64) {
+    assert!(
+        speed.is_finite() && speed > 0.0,
+        "clock speed must be positive; [{}]",
+        speed
+    );
+}
+
+/// Fires once per period of clock time, the first time immediately.
+/// Missed ticks are caught up rather than skipped, so that the number of ticks follows the clock even when it is
+/// stepped or accelerated.
+#[derive(Debug)]
+pub struct Ticker {
+    clock: Arc<dyn GameClock>,
+    period: Duration,
+    next: Duration,
+}
+
+impl Ticker {
+    pub fn new(clock: Arc<dyn GameClock>, period: Duration) -> Ticker {
+        let next: Duration = clock.now();
+        Ticker { clock, period, next }
+    }
+
+    /// Wait for the next tick, returning its scheduled time. Cancel safe.
+    pub async fn tick(&mut self) -> Duration {
+        self.clock.sleep_until(self.next).await;
+        let deadline: Duration = self.next;
+        self.next += self.period;
+        deadline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[tokio::test]
+    async fn paused_clock_steps() {
+        let clock: Arc<SimulatedClock> = Arc::new(SimulatedClock::paused());
+        time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(Duration::ZERO, clock.now());
+
+        let sleeper = tokio::spawn({
+            let clock: Arc<SimulatedClock> = clock.clone();
+            async move { clock.sleep_until(Duration::from_secs(60)).await }
+        });
+        clock.step(Duration::from_secs(30));
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!sleeper.is_finished());
+
+        clock.step(Duration::from_secs(30));
+        time::timeout(TIMEOUT, sleeper).await.unwrap().unwrap();
+        assert_eq!(Duration::from_secs(60), clock.now());
+    }
+
+    #[tokio::test]
+    async fn speed_multiplies_elapsed_time() {
+        let clock: SimulatedClock = SimulatedClock::new(10_000.0);
+        // An hour passes in well under a second of wall clock time
+        time::timeout(TIMEOUT, clock.sleep_until(Duration::from_secs(3600))).await.unwrap();
+
+        clock.pause();
+        let paused_at: Duration = clock.now();
+        time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(paused_at, clock.now());
+
+        clock.set_speed(2.0);
+        clock.resume();
+        assert_eq!(2.0, clock.speed());
+        assert!(!clock.is_paused());
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(clock.now() >= paused_at + Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn ticker_catches_up() {
+        let clock: Arc<SimulatedClock> = Arc::new(SimulatedClock::paused());
+        let mut ticker: Ticker = Ticker::new(clock.clone(), Duration::from_secs(10));
+        assert_eq!(Duration::ZERO, ticker.tick().await);
+
+        clock.step(Duration::from_secs(35));
+        for expected in [10, 20, 30] {
+            let deadline: Duration = time::timeout(TIMEOUT, ticker.tick()).await.unwrap();
+            assert_eq!(Duration::from_secs(expected), deadline);
+        }
+        assert!(time::timeout(Duration::from_millis(10), ticker.tick()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn real_time_clock_sleeps() {
+        let clock: RealTimeClock = RealTimeClock::new();
+        let deadline: Duration = clock.now() + Duration::from_millis(5);
+        time::timeout(TIMEOUT, clock.sleep_until(deadline)).await.unwrap();
+        assert!(clock.now() >= deadline);
+    }
+}
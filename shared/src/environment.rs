@@ -3,8 +3,10 @@
 use std::env;
 use std::fmt::Display;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::string::ToString;
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use crate::error::AppError;
 
@@ -63,6 +65,45 @@ impl RuntimeEnvironment {
         }
     }
 
+    /// The SQLite database holding accounts and games.
+    /// May be overridden with `DATABASE_PATH`.
+    pub fn get_database_path(&self) -> PathBuf {
+        if let Ok(path) = env::var("DATABASE_PATH") {
+            return PathBuf::from(path);
+        }
+        match self {
+            RuntimeEnvironment::Local => PathBuf::from("server.db"),
+            RuntimeEnvironment::Stage => PathBuf::from("server.db"), // todo
+            RuntimeEnvironment::Production => PathBuf::from("server.db"), // todo
+        }
+    }
+
+    /// Connections which send nothing for this long are closed.
+    /// May be overridden with `IDLE_TIMEOUT_SECONDS`.
+    pub fn get_idle_timeout(&self) -> Duration {
+        if let Some(seconds) = positive_env_var("IDLE_TIMEOUT_SECONDS") {
+            return Duration::from_secs(seconds);
+        }
+        match self {
+            RuntimeEnvironment::Local => Duration::from_secs(30),
+            RuntimeEnvironment::Stage => Duration::from_secs(60),
+            RuntimeEnvironment::Production => Duration::from_secs(60),
+        }
+    }
+
+    /// How often each game advances its simulation.
+    /// May be overridden with `TICK_INTERVAL_MILLIS`.
+    pub fn get_tick_interval(&self) -> Duration {
+        if let Some(millis) = positive_env_var("TICK_INTERVAL_MILLIS") {
+            return Duration::from_millis(millis);
+        }
+        match self {
+            RuntimeEnvironment::Local => Duration::from_millis(100),
+            RuntimeEnvironment::Stage => Duration::from_millis(100),
+            RuntimeEnvironment::Production => Duration::from_millis(100),
+        }
+    }
+
     pub fn is_debug(&self) -> bool {
         match self {
             RuntimeEnvironment::Local => true,
@@ -72,7 +113,45 @@ impl RuntimeEnvironment {
     }
 }
 
+/// The variable's value if it is set to a positive integer.
+/// Any other value is logged and ignored, so that the default applies.
+fn positive_env_var(name: &str) -> Option<u64> {
+    let value: String = env::var(name).ok()?;
+    parse_positive(name, &value)
+}
+
+fn parse_positive(name: &str, value: &str) -> Option<u64> {
+    match value.trim().parse::<u64>() {
+        Ok(0) => {
+            log::error!(
+                "Ignoring environment variable which must be positive; [{}: {}]",
+                name,
+                value
+            );
+            None
+        }
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            log::error!("Ignoring unparsable environment variable; [{}: {}] {}", name, value, e);
+            None
+        }
+    }
+}
+
 pub fn load_env() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_values() {
+        assert_eq!(Some(250), parse_positive("TICK_INTERVAL_MILLIS", "250"));
+        assert_eq!(None, parse_positive("TICK_INTERVAL_MILLIS", "0"));
+        assert_eq!(None, parse_positive("TICK_INTERVAL_MILLIS", "-5"));
+        assert_eq!(None, parse_positive("IDLE_TIMEOUT_SECONDS", "thirty"));
+    }
+}
@@ -13,6 +13,7 @@ pub struct AppError {
 
 impl AppError {
     const DEFAULT_MESSAGE: &'static str = "unspecified";
+    const MESSAGE_PREFIX: &'static str = "Error: ";
 
     pub fn new(message: &str) -> AppError {
         Self::_new(message, None)
@@ -26,14 +27,18 @@ impl AppError {
         Self::_new(Self::DEFAULT_MESSAGE, Some(error))
     }
 
+    /// The message without its prefix, suitable for display to the user.
+    pub fn summary(&self) -> &str {
+        self.message.strip_prefix(Self::MESSAGE_PREFIX).unwrap_or(&self.message)
+    }
+
     fn _new(message: &str, error: Option<Box<dyn Error>>) -> AppError {
         let backtrace: Backtrace = Backtrace::force_capture();
-        let app_error = AppError {
-            message: format!("Error: {}", message),
+        AppError {
+            message: format!("{}{}", Self::MESSAGE_PREFIX, message),
             sub_error: error,
             backtrace,
-        };
-        app_error
+        }
     }
 }
 
@@ -78,11 +83,10 @@ impl AppErrorStatic {
 
     fn new(message: &str) -> AppErrorStatic {
         let backtrace: Backtrace = Backtrace::force_capture();
-        let app_error = AppErrorStatic {
+        AppErrorStatic {
             message: format!("Error: {}", message),
             backtrace,
-        };
-        app_error
+        }
     }
 }
 
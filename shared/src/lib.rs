@@ -1,4 +1,6 @@
+pub mod clock;
 pub mod environment;
 pub mod error;
+pub mod map;
 pub mod network;
 pub mod random;
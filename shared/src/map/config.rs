@@ -0,0 +1,142 @@
+use crate::error::AppError;
+use crate::map::coordinate::HexCoord;
+use crate::network::codec::{ByteReader, Decode, Encode};
+use std::fmt::{self, Display};
+
+/// Hexes per player in the smallest preset that [MapSize::for_players] picks
+const HEXES_PER_PLAYER: usize = 1024;
+
+/// Width and height of a game's map in hexes, chosen when the game is created.
+/// The map wraps at every edge, and as odd rows are shifted, the height must be even for rows to line up where the
+/// top and bottom edges meet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MapSize {
+    pub width: i16,
+    pub height: i16,
+}
+
+impl Default for MapSize {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl MapSize {
+    pub const DEFAULT: MapSize = MapSize { width: 64, height: 64 };
+    pub const MIN_SIDE: i16 = 16;
+    pub const MAX_SIDE: i16 = 256;
+    /// The sizes offered when creating a game, smallest first
+    pub const PRESETS: [MapSize; 6] = [
+        MapSize { width: 40, height: 32 },
+        MapSize { width: 48, height: 48 },
+        MapSize { width: 64, height: 64 },
+        MapSize { width: 96, height: 64 },
+        MapSize { width: 96, height: 96 },
+        MapSize {
+            width: 128,
+            height: 128,
+        },
+    ];
+
+    pub fn new(width: i16, height: i16) -> Result<MapSize, AppError> {
+        let sides = MapSize::MIN_SIDE..=MapSize::MAX_SIDE;
+        if !sides.contains(&width) || !sides.contains(&height) || height % 2 != 0 {
+            return Err(AppError::new(&format!(
+                "Invalid map size; [width: {}] [height: {}]",
+                width, height
+            )));
+        }
+        Ok(MapSize { width, height })
+    }
+
+    /// The smallest preset with room for the players
+    pub fn for_players(player_count: u8) -> MapSize {
+        let wanted: usize = usize::from(player_count) * HEXES_PER_PLAYER;
+        MapSize::PRESETS
+            .into_iter()
+            .find(|size| size.hex_count() >= wanted)
+            .unwrap_or(MapSize::PRESETS[MapSize::PRESETS.len() - 1])
+    }
+
+    /// Cycles through the presets. A size which is not a preset moves to the default.
+    pub fn next(&self) -> MapSize {
+        match MapSize::PRESETS.iter().position(|size| size == self) {
+            Some(index) => MapSize::PRESETS[(index + 1) % MapSize::PRESETS.len()],
+            None => MapSize::DEFAULT,
+        }
+    }
+
+    pub const fn hex_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn contains(&self, hex_coord: HexCoord) -> bool {
+        (0..self.width).contains(&hex_coord.i) && (0..self.height).contains(&hex_coord.j)
+    }
+
+    /// The same hex, moved onto the map from wherever it lies beyond the edges
+    pub fn wrap(&self, hex_coord: HexCoord) -> HexCoord {
+        HexCoord {
+            i: hex_coord.i.rem_euclid(self.width),
+            j: hex_coord.j.rem_euclid(self.height),
+        }
+    }
+
+    /// Every hex on the map, in map index order
+    pub fn hex_coords(self) -> impl Iterator<Item = HexCoord> {
+        (0..self.hex_count()).map(move |index| HexCoord::from_map_index(index, self))
+    }
+}
+
+impl Display for MapSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} x {}", self.width, self.height)
+    }
+}
+
+impl Encode for MapSize {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.width.encode(buffer);
+        self.height.encode(buffer);
+    }
+}
+
+impl Decode for MapSize {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        MapSize::new(i16::decode(reader)?, i16::decode(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_and_cycles() {
+        assert!(MapSize::new(96, 64).is_ok());
+        assert!(MapSize::new(97, 64).is_ok());
+        assert!(MapSize::new(64, 63).is_err());
+        assert!(MapSize::new(MapSize::MIN_SIDE - 1, 64).is_err());
+        assert!(MapSize::new(64, MapSize::MAX_SIDE + 2).is_err());
+        for size in MapSize::PRESETS {
+            assert_eq!(size, MapSize::new(size.width, size.height).unwrap());
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        MapSize { width: 64, height: 63 }.encode(&mut buffer);
+        assert!(MapSize::decode(&mut ByteReader::new(&buffer)).is_err());
+
+        assert_eq!(MapSize::PRESETS[0], MapSize::PRESETS[MapSize::PRESETS.len() - 1].next());
+        assert_eq!(MapSize::DEFAULT, MapSize { width: 50, height: 50 }.next());
+    }
+
+    #[test]
+    fn scales_with_players() {
+        assert_eq!(MapSize::DEFAULT, MapSize::for_players(4));
+        assert!(MapSize::for_players(1).hex_count() < MapSize::for_players(8).hex_count());
+        assert_eq!(
+            MapSize::PRESETS[MapSize::PRESETS.len() - 1],
+            MapSize::for_players(u8::MAX)
+        );
+    }
+}
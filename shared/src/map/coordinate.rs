@@ -0,0 +1,385 @@
+use crate::error::AppError;
+use crate::map::config::MapSize;
+use crate::map::cube::CubeCoord;
+use crate::network::codec::{ByteReader, Decode, Encode};
+use std::ops::{Add, Sub};
+
+/// Offset coordinates on the toroidal map, where odd rows are shifted half a hex to the right.
+/// Where the map wraps depends on its [MapSize], which methods that wrap take.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HexCoord {
+    pub i: i16,
+    pub j: i16,
+}
+
+impl Default for HexCoord {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Encode for HexCoord {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.i.encode(buffer);
+        self.j.encode(buffer);
+    }
+}
+
+/// The map's size is not known here, so whether the hex lies on the map is checked by the caller.
+impl Decode for HexCoord {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(HexCoord {
+            i: i16::decode(reader)?,
+            j: i16::decode(reader)?,
+        })
+    }
+}
+
+/// Does not wrap; see [MapSize::wrap]
+impl Add for HexCoord {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        HexCoord {
+            i: self.i + rhs.i,
+            j: self.j + rhs.j,
+        }
+    }
+}
+
+impl Sub for HexCoord {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        HexCoord {
+            i: self.i - rhs.i,
+            j: self.j - rhs.j,
+        }
+    }
+}
+
+impl HexCoord {
+    pub const DEFAULT: HexCoord = HexCoord { i: 0, j: 0 };
+
+    /// Indices into [CubeCoord::DIRECTIONS], in the order [HexCoord::neighbors] returns them for even and odd rows.
+    /// Map generation picks among neighbors in this order, and stored games regenerate their maps from their seeds,
+    /// so it must not change.
+    const NEIGHBOR_ORDER_EVEN: [usize; 6] = [2, 4, 3, 0, 1, 5];
+    const NEIGHBOR_ORDER_ODD: [usize; 6] = [5, 1, 3, 2, 4, 0];
+
+    pub const fn map_index(&self, size: MapSize) -> usize {
+        self.i as usize + self.j as usize * size.width as usize
+    }
+
+    /// The inverse of [HexCoord::map_index]
+    pub const fn from_map_index(index: usize, size: MapSize) -> HexCoord {
+        HexCoord {
+            i: (index % size.width as usize) as i16,
+            j: (index / size.width as usize) as i16,
+        }
+    }
+
+    pub fn even_row(&self) -> bool {
+        self.j % 2 == 0
+    }
+
+    pub fn neighbors(&self, size: MapSize) -> [HexCoord; 6] {
+        let order: &[usize; 6] = match self.even_row() {
+            true => &Self::NEIGHBOR_ORDER_EVEN,
+            false => &Self::NEIGHBOR_ORDER_ODD,
+        };
+        let cube_coord: CubeCoord = CubeCoord::from(*self);
+        order.map(|direction| size.wrap(HexCoord::from(cube_coord + CubeCoord::DIRECTIONS[direction])))
+    }
+
+    pub fn is_neighbor(&self, other: HexCoord, size: MapSize) -> bool {
+        self.distance(other, size) == 1
+    }
+
+    /// Steps to the other hex, the shortest way round the map
+    pub fn distance(&self, other: HexCoord, size: MapSize) -> i16 {
+        self.shortest_diff(other, size).length()
+    }
+
+    /// The hexes `radius` steps away, beside each other in turn. Where the map is small enough for the ring to wrap
+    /// onto itself, hexes may repeat, and some may be nearer than `radius` the other way round.
+    pub fn ring(&self, radius: i16, size: MapSize) -> impl Iterator<Item = HexCoord> {
+        CubeCoord::from(*self).ring(radius).map(move |cube_coord| size.wrap(HexCoord::from(cube_coord)))
+    }
+
+    /// This hex, then each ring out to the radius. Hexes may repeat as with [HexCoord::ring].
+    pub fn spiral(&self, radius: i16, size: MapSize) -> impl Iterator<Item = HexCoord> {
+        CubeCoord::from(*self).spiral(radius).map(move |cube_coord| size.wrap(HexCoord::from(cube_coord)))
+    }
+
+    /// Every hex within the number of steps, including this one, paired with its step distance.
+    /// Ordered by distance, nearest first. Each hex appears once, even where the map wraps onto itself.
+    pub fn within_steps(&self, max_steps: i16, size: MapSize) -> Vec<(HexCoord, i16)> {
+        // No hex is further than half of each side, so larger rings only revisit hexes
+        let max_steps: i16 = max_steps.min(size.width / 2 + size.height / 2);
+        let mut visited: Vec<bool> = vec![false; size.hex_count()];
+        let mut found: Vec<(HexCoord, i16)> = Vec::new();
+        for steps in 0..=max_steps {
+            for hex_coord in self.ring(steps, size) {
+                // The first time a hex is reached is the shortest way to it
+                if !visited[hex_coord.map_index(size)] {
+                    visited[hex_coord.map_index(size)] = true;
+                    found.push((hex_coord, steps));
+                }
+            }
+        }
+        found
+    }
+
+    /// The hexes a straight line to the other hex passes through, the shortest way round the map, including both
+    /// ends. Each hex is beside the one before.
+    pub fn line_to(&self, other: HexCoord, size: MapSize) -> Vec<HexCoord> {
+        let start: CubeCoord = CubeCoord::from(*self);
+        start
+            .line(start + self.shortest_diff(other, size))
+            .into_iter()
+            .map(|cube_coord| size.wrap(HexCoord::from(cube_coord)))
+            .collect()
+    }
+
+    /// The difference to whichever copy of the other hex is nearest, among those repeated beyond each of the map's
+    /// edges
+    fn shortest_diff(&self, other: HexCoord, size: MapSize) -> CubeCoord {
+        let diff: CubeCoord = CubeCoord::from(size.wrap(other)) - CubeCoord::from(size.wrap(*self));
+        // The copies a map's width to the right and a map's height below; the height is even, so rows keep their shift
+        let across: CubeCoord = CubeCoord::new(size.width, 0);
+        let down: CubeCoord = CubeCoord::new(-size.height / 2, size.height);
+
+        let mut shortest: CubeCoord = diff;
+        for rows in -1..=1 {
+            let shifted: CubeCoord = diff + down * rows;
+            // Along a row, distance is least where q balances r, so the nearest copies lie either side of that
+            let balanced: f64 = -(f64::from(shifted.q) + f64::from(shifted.r) / 2.0) / f64::from(size.width);
+            let columns: i16 = balanced.round() as i16;
+            for copy in (columns - 1..=columns + 1).map(|columns| shifted + across * columns) {
+                if copy.length() < shortest.length() {
+                    shortest = copy;
+                }
+            }
+        }
+        shortest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wider than it is tall, so that mixing up the sides shows
+    const WIDE: MapSize = MapSize { width: 24, height: 16 };
+
+    #[test]
+    fn map_index_round_trip() {
+        for size in [MapSize::DEFAULT, WIDE] {
+            for hex_coord in [
+                HexCoord::DEFAULT,
+                HexCoord {
+                    i: size.width - 1,
+                    j: 0,
+                },
+                HexCoord {
+                    i: 3,
+                    j: size.height - 1,
+                },
+            ] {
+                assert_eq!(hex_coord, HexCoord::from_map_index(hex_coord.map_index(size), size));
+            }
+            assert_eq!(
+                size.hex_count() - 1,
+                HexCoord {
+                    i: size.width - 1,
+                    j: size.height - 1
+                }
+                .map_index(size)
+            );
+        }
+    }
+
+    #[test]
+    fn neighbors_wrap() {
+        for size in [MapSize::DEFAULT, WIDE] {
+            for corner in [
+                HexCoord::DEFAULT,
+                HexCoord {
+                    i: size.width - 1,
+                    j: size.height - 1,
+                },
+            ] {
+                for neighbor in corner.neighbors(size) {
+                    assert!(size.contains(neighbor));
+                    assert!(corner.is_neighbor(neighbor, size));
+                    assert!(neighbor.is_neighbor(corner, size));
+                }
+            }
+            assert!(!HexCoord::DEFAULT.is_neighbor(HexCoord { i: 2, j: 0 }, size));
+        }
+        // Across the left and right edges
+        assert!(HexCoord { i: 0, j: 3 }.is_neighbor(
+            HexCoord {
+                i: WIDE.width - 1,
+                j: 3
+            },
+            WIDE
+        ));
+        assert!(!HexCoord { i: 0, j: 3 }.is_neighbor(
+            HexCoord {
+                i: WIDE.height - 1,
+                j: 3
+            },
+            WIDE
+        ));
+    }
+
+    #[test]
+    fn within_steps() {
+        for size in [MapSize::DEFAULT, WIDE] {
+            let corner: HexCoord = HexCoord::DEFAULT;
+            let within: Vec<(HexCoord, i16)> = corner.within_steps(2, size);
+            // 1, 6 and 12 hexes at each distance
+            assert_eq!(19, within.len());
+            assert_eq!((corner, 0), within[0]);
+            for (hex_coord, steps) in &within {
+                assert_eq!(*steps == 1, corner.is_neighbor(*hex_coord, size));
+            }
+            assert_eq!(12, within.iter().filter(|(_, steps)| *steps == 2).count());
+            assert_eq!(
+                size.hex_count(),
+                corner.within_steps(size.width.max(size.height), size).len()
+            );
+        }
+    }
+
+    /// Step distances by searching outwards through neighbors, to check [HexCoord::distance] against
+    fn searched_distances(from: HexCoord, size: MapSize) -> Vec<i16> {
+        let mut distances: Vec<Option<i16>> = vec![None; size.hex_count()];
+        distances[from.map_index(size)] = Some(0);
+        let mut frontier: Vec<HexCoord> = vec![from];
+        let mut steps: i16 = 0;
+        while !frontier.is_empty() {
+            steps += 1;
+            let mut next: Vec<HexCoord> = Vec::new();
+            for neighbor in frontier.iter().flat_map(|hex_coord| hex_coord.neighbors(size)) {
+                if distances[neighbor.map_index(size)].is_none() {
+                    distances[neighbor.map_index(size)] = Some(steps);
+                    next.push(neighbor);
+                }
+            }
+            frontier = next;
+        }
+        distances.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// A hex in the middle of the map, then hexes on each edge and in each corner
+    fn edge_hexes(size: MapSize) -> Vec<HexCoord> {
+        let (right, bottom): (i16, i16) = (size.width - 1, size.height - 1);
+        [
+            (5, 6),
+            (0, 5),
+            (right, 6),
+            (4, 0),
+            (7, bottom),
+            (0, 0),
+            (right, 0),
+            (0, bottom),
+            (right, bottom),
+        ]
+        .into_iter()
+        .map(|(i, j)| HexCoord { i, j })
+        .collect()
+    }
+
+    #[test]
+    fn neighbors_keep_their_order() {
+        let even: HexCoord = HexCoord { i: 5, j: 4 };
+        let odd: HexCoord = HexCoord { i: 5, j: 5 };
+        let offsets = |hex_coord: HexCoord| hex_coord.neighbors(MapSize::DEFAULT).map(|neighbor| neighbor - hex_coord);
+        let expected_even: [(i16, i16); 6] = [(-1, -1), (-1, 1), (-1, 0), (1, 0), (0, -1), (0, 1)];
+        let expected_odd: [(i16, i16); 6] = [(1, 1), (1, -1), (-1, 0), (0, -1), (0, 1), (1, 0)];
+        assert_eq!(expected_even.map(|(i, j)| HexCoord { i, j }), offsets(even));
+        assert_eq!(expected_odd.map(|(i, j)| HexCoord { i, j }), offsets(odd));
+    }
+
+    #[test]
+    fn exact_distance() {
+        let tall: MapSize = MapSize { width: 16, height: 32 };
+        for size in [MapSize::DEFAULT, WIDE, tall, MapSize::PRESETS[0]] {
+            for from in edge_hexes(size) {
+                let searched: Vec<i16> = searched_distances(from, size);
+                for to in size.hex_coords() {
+                    assert_eq!(
+                        searched[to.map_index(size)],
+                        from.distance(to, size),
+                        "{:?} to {:?} on {}",
+                        from,
+                        to,
+                        size
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            6,
+            HexCoord { i: 2, j: 3 }.distance(HexCoord { i: 6, j: 8 }, MapSize::DEFAULT)
+        );
+    }
+
+    #[test]
+    fn wraps_at_every_edge() {
+        for size in [MapSize::DEFAULT, WIDE] {
+            let (right, bottom): (i16, i16) = (size.width - 1, size.height - 1);
+            // Across the left and right edges, the top and bottom edges, and the corners
+            for (a, b) in [((0, 3), (right, 3)), ((4, 0), (4, bottom)), ((0, 0), (right, bottom))] {
+                let (a, b): (HexCoord, HexCoord) = (HexCoord { i: a.0, j: a.1 }, HexCoord { i: b.0, j: b.1 });
+                assert!(a.is_neighbor(b, size) && b.is_neighbor(a, size));
+                assert!(a.neighbors(size).contains(&b) && b.neighbors(size).contains(&a));
+            }
+            assert!(!HexCoord { i: 0, j: 3 }.is_neighbor(HexCoord { i: right - 1, j: 3 }, size));
+
+            for center in edge_hexes(size) {
+                let ring: Vec<HexCoord> = center.ring(2, size).collect();
+                assert_eq!(12, ring.len());
+                assert!(ring.iter().all(|hex_coord| size.contains(*hex_coord)));
+                assert!(ring.iter().all(|hex_coord| center.distance(*hex_coord, size) == 2));
+                for (n, hex_coord) in ring.iter().enumerate() {
+                    assert!(hex_coord.is_neighbor(ring[(n + 1) % ring.len()], size));
+                }
+                assert_eq!(19, center.spiral(2, size).count());
+
+                for other in edge_hexes(size) {
+                    let line: Vec<HexCoord> = center.line_to(other, size);
+                    assert_eq!(center.distance(other, size) as usize + 1, line.len());
+                    assert_eq!((center, other), (line[0], line[line.len() - 1]));
+                    assert!(line.windows(2).all(|pair| pair[0].is_neighbor(pair[1], size)));
+                }
+            }
+        }
+
+        // The line goes round the short way, over the edge
+        let line: Vec<HexCoord> = HexCoord { i: 1, j: 6 }.line_to(
+            HexCoord {
+                i: WIDE.width - 2,
+                j: 6,
+            },
+            WIDE,
+        );
+        let expected: [i16; 4] = [1, 0, WIDE.width - 1, WIDE.width - 2];
+        assert_eq!(expected.map(|i| HexCoord { i, j: 6 }).to_vec(), line);
+    }
+
+    #[test]
+    fn small_maps_wrap_onto_themselves() {
+        let size: MapSize = MapSize::PRESETS[0];
+        let center: HexCoord = HexCoord { i: 3, j: 3 };
+        // Rings wider than the map meet themselves, and repeat hexes
+        let ring: Vec<HexCoord> = center.ring(size.width, size).collect();
+        assert_eq!(6 * size.width as usize, ring.len());
+        assert!(ring.iter().all(|hex_coord| center.distance(*hex_coord, size) < size.width));
+        // Every hex is within reach, once
+        let within: Vec<(HexCoord, i16)> = center.within_steps(i16::MAX, size);
+        assert_eq!(size.hex_count(), within.len());
+        assert!(within.iter().all(|(hex_coord, steps)| center.distance(*hex_coord, size) == *steps));
+    }
+}
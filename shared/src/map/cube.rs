@@ -0,0 +1,236 @@
+//! Cube coordinates for hex grid arithmetic (https://www.redblobgames.com/grids/hexagons/).
+//! A hex's three cube coordinates sum to zero, which makes distances, rings and lines plain integer arithmetic. They
+//! know nothing of the map's edges; [HexCoord] converts to and from them and wraps the results onto the map.
+
+use crate::map::coordinate::HexCoord;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CubeCoord {
+    pub q: i16,
+    pub r: i16,
+    pub s: i16,
+}
+
+impl Default for CubeCoord {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The offset layout shifts odd rows half a hex to the right
+impl From<HexCoord> for CubeCoord {
+    fn from(hex_coord: HexCoord) -> Self {
+        CubeCoord::new(hex_coord.i - (hex_coord.j - (hex_coord.j & 1)) / 2, hex_coord.j)
+    }
+}
+
+/// The inverse of the conversion from [HexCoord]. The result is not wrapped onto the map.
+impl From<CubeCoord> for HexCoord {
+    fn from(cube_coord: CubeCoord) -> Self {
+        HexCoord {
+            i: cube_coord.q + (cube_coord.r - (cube_coord.r & 1)) / 2,
+            j: cube_coord.r,
+        }
+    }
+}
+
+impl Add for CubeCoord {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        CubeCoord {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+            s: self.s + rhs.s,
+        }
+    }
+}
+
+impl Sub for CubeCoord {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        CubeCoord {
+            q: self.q - rhs.q,
+            r: self.r - rhs.r,
+            s: self.s - rhs.s,
+        }
+    }
+}
+
+impl Mul<i16> for CubeCoord {
+    type Output = Self;
+    fn mul(self, rhs: i16) -> Self::Output {
+        CubeCoord {
+            q: self.q * rhs,
+            r: self.r * rhs,
+            s: self.s * rhs,
+        }
+    }
+}
+
+impl CubeCoord {
+    pub const DEFAULT: CubeCoord = CubeCoord { q: 0, r: 0, s: 0 };
+
+    /// East, then counterclockwise
+    pub const DIRECTIONS: [CubeCoord; 6] = [
+        CubeCoord { q: 1, r: 0, s: -1 },
+        CubeCoord { q: 1, r: -1, s: 0 },
+        CubeCoord { q: 0, r: -1, s: 1 },
+        CubeCoord { q: -1, r: 0, s: 1 },
+        CubeCoord { q: -1, r: 1, s: 0 },
+        CubeCoord { q: 0, r: 1, s: -1 },
+    ];
+
+    pub const fn new(q: i16, r: i16) -> CubeCoord {
+        CubeCoord { q, r, s: -q - r }
+    }
+
+    /// Steps from the origin
+    pub fn length(&self) -> i16 {
+        self.q.abs().max(self.r.abs()).max(self.s.abs())
+    }
+
+    pub fn distance(&self, other: CubeCoord) -> i16 {
+        (*self - other).length()
+    }
+
+    /// The hexes `radius` steps away, starting to the southwest and going counterclockwise.
+    /// A radius of zero yields only this hex.
+    pub fn ring(self, radius: i16) -> impl Iterator<Item = CubeCoord> {
+        let sides: usize = if radius == 0 { 1 } else { Self::DIRECTIONS.len() };
+        (0..sides).flat_map(move |side| {
+            // Each side starts at a corner and runs in the direction of the side
+            let corner: CubeCoord = self + Self::DIRECTIONS[(side + 4) % 6] * radius;
+            (0..radius.max(1)).map(move |step| corner + Self::DIRECTIONS[side] * step)
+        })
+    }
+
+    /// This hex, then each ring out to the radius
+    pub fn spiral(self, radius: i16) -> impl Iterator<Item = CubeCoord> {
+        (0..=radius).flat_map(move |ring_radius| self.ring(ring_radius))
+    }
+
+    /// Every hex within the radius, by increasing q and then r.
+    /// Cheaper than [CubeCoord::spiral] when the order does not matter.
+    pub fn range(self, radius: i16) -> impl Iterator<Item = CubeCoord> {
+        (-radius..=radius).flat_map(move |q| {
+            let rs = (-radius).max(-q - radius)..=radius.min(-q + radius);
+            rs.map(move |r| self + CubeCoord::new(q, r))
+        })
+    }
+
+    /// The hexes a straight line from this hex to the other passes through, including both ends.
+    /// Each hex is a step from the one before.
+    pub fn line(self, other: CubeCoord) -> Vec<CubeCoord> {
+        let steps: i16 = self.distance(other);
+        if steps == 0 {
+            return vec![self];
+        }
+        // Nudged so that a line along the edge between two hexes consistently picks the same side
+        let (q, r): (f64, f64) = (f64::from(self.q) + 1e-6, f64::from(self.r) + 1e-6);
+        let (dq, dr): (f64, f64) = (f64::from(other.q - self.q), f64::from(other.r - self.r));
+        (0..=steps)
+            .map(|step| {
+                let t: f64 = f64::from(step) / f64::from(steps);
+                CubeCoord::round(q + dq * t, r + dr * t)
+            })
+            .collect()
+    }
+
+    /// The hex containing a fractional position
+    pub fn round(q: f64, r: f64) -> CubeCoord {
+        let s: f64 = -q - r;
+        let (mut rounded_q, mut rounded_r, rounded_s): (f64, f64, f64) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds): (f64, f64, f64) = ((rounded_q - q).abs(), (rounded_r - r).abs(), (rounded_s - s).abs());
+        // The coordinate rounded furthest is recomputed from the others, so that the three still sum to zero
+        if dq > dr && dq > ds {
+            rounded_q = -rounded_r - rounded_s;
+        } else if dr > ds {
+            rounded_r = -rounded_q - rounded_s;
+        }
+        CubeCoord::new(rounded_q as i16, rounded_r as i16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_round_trip() {
+        for j in -5..5 {
+            for i in -5..5 {
+                let hex_coord: HexCoord = HexCoord { i, j };
+                let cube_coord: CubeCoord = CubeCoord::from(hex_coord);
+                assert_eq!(0, cube_coord.q + cube_coord.r + cube_coord.s);
+                assert_eq!(hex_coord, HexCoord::from(cube_coord));
+            }
+        }
+        // Odd rows are shifted right, so the hex below and to the right of an odd row hex is one column over
+        assert_eq!(
+            1,
+            CubeCoord::from(HexCoord { i: 3, j: 1 }).distance(CubeCoord::from(HexCoord { i: 4, j: 2 }))
+        );
+    }
+
+    #[test]
+    fn distances() {
+        let origin: CubeCoord = CubeCoord::DEFAULT;
+        for direction in CubeCoord::DIRECTIONS {
+            assert_eq!(1, direction.length());
+            assert_eq!(5, origin.distance(direction * 5));
+        }
+        assert_eq!(7, CubeCoord::new(3, 4).length());
+        assert_eq!(4, CubeCoord::new(3, -4).length());
+    }
+
+    #[test]
+    fn rings_and_spirals() {
+        let center: CubeCoord = CubeCoord::new(2, -7);
+        assert_eq!(vec![center], center.ring(0).collect::<Vec<CubeCoord>>());
+        for radius in 1..5 {
+            let ring: Vec<CubeCoord> = center.ring(radius).collect();
+            assert_eq!(6 * radius as usize, ring.len());
+            assert!(ring.iter().all(|hex| hex.distance(center) == radius));
+            // Each hex is beside the next, all the way round
+            for (n, hex) in ring.iter().enumerate() {
+                assert_eq!(1, hex.distance(ring[(n + 1) % ring.len()]));
+            }
+        }
+
+        let spiral: Vec<CubeCoord> = center.spiral(3).collect();
+        assert_eq!(37, spiral.len());
+        assert!(spiral.windows(2).all(|pair| pair[0].distance(center) <= pair[1].distance(center)));
+        let mut range: Vec<CubeCoord> = center.range(3).collect();
+        let mut sorted_spiral: Vec<CubeCoord> = spiral.clone();
+        range.sort_by_key(|hex| (hex.q, hex.r));
+        sorted_spiral.sort_by_key(|hex| (hex.q, hex.r));
+        assert_eq!(sorted_spiral, range);
+    }
+
+    #[test]
+    fn lines() {
+        let start: CubeCoord = CubeCoord::new(-3, 1);
+        assert_eq!(vec![start], start.line(start));
+        for end in [CubeCoord::new(4, -1), CubeCoord::new(-3, 6), CubeCoord::new(0, -5)] {
+            let line: Vec<CubeCoord> = start.line(end);
+            assert_eq!(start.distance(end) as usize + 1, line.len());
+            assert_eq!(start, line[0]);
+            assert_eq!(end, line[line.len() - 1]);
+            assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+        }
+        // Straight along a direction
+        assert_eq!(
+            (0..4).map(|step| start + CubeCoord::DIRECTIONS[1] * step).collect::<Vec<CubeCoord>>(),
+            start.line(start + CubeCoord::DIRECTIONS[1] * 3)
+        );
+    }
+
+    #[test]
+    fn rounds_to_containing_hex() {
+        assert_eq!(CubeCoord::new(1, 0), CubeCoord::round(0.9, 0.05));
+        assert_eq!(CubeCoord::new(-2, 1), CubeCoord::round(-1.6, 0.7));
+        // Rounding each coordinate alone gives (1, 1, -1), which is not a hex
+        assert_eq!(CubeCoord::new(1, 0), CubeCoord::round(0.7, 0.6));
+    }
+}
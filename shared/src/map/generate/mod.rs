@@ -0,0 +1,194 @@
+mod particle;
+pub use particle::*;
+
+mod players;
+pub use players::*;
+
+mod weighted;
+pub use weighted::*;
+
+use crate::error::AppError;
+use crate::map::config::MapSize;
+use crate::map::coordinate::HexCoord;
+use crate::map::state::{Map, ResourceType};
+use crate::network::codec::{ByteReader, Decode, Encode};
+use crate::random::{GameSeed, SeededRng};
+use std::fmt::{self, Display};
+
+/// The resource types placed, in the order clusters alternate between them
+const RESOURCE_TYPES: [ResourceType; 2] = [ResourceType::Metal, ResourceType::Oil];
+
+pub trait MapGenerator {
+    /// The same size and seed always yield the same map.
+    fn generate(&self, map_size: MapSize, seed: GameSeed) -> Map;
+}
+
+/// Chosen in a game's settings
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MapGeneratorType {
+    WeightedSeeding = 0,
+    ParticleRepulsion = 1,
+}
+
+impl Default for MapGeneratorType {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl MapGeneratorType {
+    pub const DEFAULT: MapGeneratorType = MapGeneratorType::WeightedSeeding;
+
+    pub fn from_u8(value: u8) -> Result<Self, AppError> {
+        match value {
+            0 => Ok(MapGeneratorType::WeightedSeeding),
+            1 => Ok(MapGeneratorType::ParticleRepulsion),
+            _ => Err(AppError::new(&format!("Invalid map generator; [{}]", value))),
+        }
+    }
+
+    /// Cycles through every type
+    pub fn next(&self) -> MapGeneratorType {
+        match self {
+            MapGeneratorType::WeightedSeeding => MapGeneratorType::ParticleRepulsion,
+            MapGeneratorType::ParticleRepulsion => MapGeneratorType::WeightedSeeding,
+        }
+    }
+
+    /// The generator with its default parameters
+    pub fn generator(&self) -> Box<dyn MapGenerator> {
+        match self {
+            MapGeneratorType::WeightedSeeding => Box::new(WeightedSeeding::default()),
+            MapGeneratorType::ParticleRepulsion => Box::new(ParticleRepulsion::default()),
+        }
+    }
+}
+
+impl Display for MapGeneratorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string: &'static str = match self {
+            MapGeneratorType::WeightedSeeding => "Weighted seeding",
+            MapGeneratorType::ParticleRepulsion => "Particle repulsion",
+        };
+        write!(f, "{}", string)
+    }
+}
+
+impl Encode for MapGeneratorType {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        (*self as u8).encode(buffer);
+    }
+}
+
+impl Decode for MapGeneratorType {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        MapGeneratorType::from_u8(u8::decode(reader)?)
+    }
+}
+
+/// Generate a game's map with the chosen generator, and the start positions for its players.
+/// The same settings and seed always yield the same map.
+pub fn generate_map(generator: MapGeneratorType, map_size: MapSize, player_count: u8, seed: GameSeed) -> Map {
+    let mut map: Map = generator.generator().generate(map_size, seed);
+    let starts: Vec<HexCoord> = place_players(&map, player_count, seed);
+    map.set_starts(starts);
+    map
+}
+
+/// A placed cluster, with its center first
+#[derive(Debug, Clone, PartialEq)]
+struct Cluster {
+    resource_type: ResourceType,
+    hexes: Vec<HexCoord>,
+}
+
+/// Generators are tuned with cluster counts for a map of the default size, which this scales to the map's area, so
+/// that larger maps are as densely filled
+fn scaled_count(count: u16, map_size: MapSize) -> u16 {
+    let scale: f64 = map_size.hex_count() as f64 / MapSize::DEFAULT.hex_count() as f64;
+    (f64::from(count) * scale).round() as u16
+}
+
+fn fill_clusters(map_size: MapSize, clusters: Vec<Cluster>) -> Map {
+    let mut map: Map = Map::empty(map_size);
+    for cluster in clusters {
+        for hex_coord in cluster.hexes {
+            map.set_resource_type(hex_coord, cluster.resource_type);
+        }
+    }
+    map
+}
+
+/// The center and up to `size - 1` unoccupied hexes connected to it, each placed beside a random hex of the cluster.
+/// Hexes beside several of the cluster's hexes are likelier to be placed, which keeps clusters compact.
+fn grow_cluster(
+    rng: &mut SeededRng,
+    map_size: MapSize,
+    center: HexCoord,
+    size: u16,
+    occupied: &mut [bool],
+) -> Vec<HexCoord> {
+    occupied[center.map_index(map_size)] = true;
+    let mut hexes: Vec<HexCoord> = vec![center];
+
+    while hexes.len() < usize::from(size) {
+        let frontier: Vec<HexCoord> = hexes
+            .iter()
+            .flat_map(|hex_coord| hex_coord.neighbors(map_size))
+            .filter(|neighbor| !occupied[neighbor.map_index(map_size)])
+            .collect();
+        if frontier.is_empty() {
+            break;
+        }
+
+        let hex_coord: HexCoord = frontier[rng.below(frontier.len() as u64) as usize];
+        occupied[hex_coord.map_index(map_size)] = true;
+        hexes.push(hex_coord);
+    }
+    hexes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generator_type_round_trip() {
+        for generator in [MapGeneratorType::WeightedSeeding, MapGeneratorType::ParticleRepulsion] {
+            let mut buffer: Vec<u8> = Vec::new();
+            generator.encode(&mut buffer);
+            assert_eq!(
+                generator,
+                MapGeneratorType::decode(&mut ByteReader::new(&buffer)).unwrap()
+            );
+            assert_ne!(generator, generator.next());
+            let map: Map = generate_map(generator, MapSize::DEFAULT, 2, GameSeed(1));
+            assert_eq!(
+                generator.generator().generate(MapSize::DEFAULT, GameSeed(1)).hexes(),
+                map.hexes()
+            );
+            assert_eq!(2, map.starts().len());
+        }
+        assert!(MapGeneratorType::from_u8(2).is_err());
+    }
+
+    #[test]
+    fn generates_any_size() {
+        let wide: MapSize = MapSize { width: 96, height: 48 };
+        for generator in [MapGeneratorType::WeightedSeeding, MapGeneratorType::ParticleRepulsion] {
+            let small: Map = generate_map(generator, MapSize::PRESETS[0], 2, GameSeed(4));
+            let map: Map = generate_map(generator, wide, 6, GameSeed(4));
+            assert_eq!(wide, map.size());
+            assert_eq!(wide.hex_count(), map.hexes().len());
+            assert!(map.hexes().iter().all(|hex| map.get(hex.hex_coord) == Some(hex)));
+            assert_eq!(6, map.starts().len());
+            assert!(map.starts().iter().all(|start| wide.contains(*start)));
+
+            let resources =
+                |map: &Map| map.hexes().iter().filter(|hex| hex.resource_type != ResourceType::None).count();
+            assert!(resources(&small) > 0);
+            assert!(resources(&map) > resources(&small) * 2);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Resource clusters placed by repelling particles (plan.txt, Strategy 2).
+//! Each cluster starts as a random point on a continuous plane laid over the map, which wraps at its edges as the map
+//! does, so that points near an edge are pushed by points just across it. Every point repels the points near it, and a
+//! time step simulation moves them apart until they settle. The points then snap to hexes and are split evenly
+//! between metal and oil.
+
+use crate::map::config::MapSize;
+use crate::map::coordinate::HexCoord;
+use crate::map::generate::{Cluster, MapGenerator, RESOURCE_TYPES, fill_clusters, grow_cluster, scaled_count};
+use crate::map::state::Map;
+use crate::random::{GameSeed, RngStream, SeededRng};
+
+/// Distance between the centers of adjacent rows, in hex widths
+const ROW_PITCH: f64 = 0.866_025_403_784_438_6;
+/// Points further apart than this many times their average spacing do not repel each other
+const INTERACTION_RANGE: f64 = 1.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleRepulsion {
+    /// Clusters of each resource type on a map of the default size. As points may snap to the same hex, the number
+    /// placed varies.
+    pub clusters_per_resource: u16,
+    /// Hexes in each cluster, including its center
+    pub cluster_size: u16,
+    /// The simulation stops after this many steps at the latest
+    pub max_steps: u32,
+    /// Hex widths moved in a step under the strongest force, that of two points at the same position
+    pub time_step: f64,
+    /// The simulation stops once a step moves the points less than this many hex widths in total
+    pub min_total_movement: f64,
+    /// The simulation stops once the forces on the points total less than this
+    pub min_total_force: f64,
+}
+
+impl Default for ParticleRepulsion {
+    fn default() -> Self {
+        ParticleRepulsion {
+            clusters_per_resource: 12,
+            cluster_size: 4,
+            max_steps: 200,
+            time_step: 1.0,
+            min_total_movement: 1.0,
+            min_total_force: 0.05,
+        }
+    }
+}
+
+/// A position on the plane, in hex widths from the center of the hex at the origin
+type Point = [f64; 2];
+
+/// The continuous plane the points move on, which wraps at its edges
+#[derive(Debug, Copy, Clone)]
+struct Plane {
+    width: f64,
+    height: f64,
+    /// Average distance between neighboring points
+    spacing: f64,
+}
+
+impl Plane {
+    /// The same position moved onto the plane
+    fn wrap(&self, point: Point) -> Point {
+        [point[0].rem_euclid(self.width), point[1].rem_euclid(self.height)]
+    }
+
+    /// The shortest vector from b to a, which may cross an edge
+    fn diff(&self, a: Point, b: Point) -> Point {
+        let dx: f64 = a[0] - b[0];
+        let dy: f64 = a[1] - b[1];
+        [
+            dx - self.width * (dx / self.width).round(),
+            dy - self.height * (dy / self.height).round(),
+        ]
+    }
+}
+
+impl MapGenerator for ParticleRepulsion {
+    fn generate(&self, map_size: MapSize, seed: GameSeed) -> Map {
+        fill_clusters(map_size, self.place_clusters(map_size, &mut seed.rng(RngStream::Map)))
+    }
+}
+
+impl ParticleRepulsion {
+    fn place_clusters(&self, map_size: MapSize, rng: &mut SeededRng) -> Vec<Cluster> {
+        let (plane, mut points): (Plane, Vec<Point>) = self.scatter(map_size, rng);
+        self.simulate(&plane, &mut points);
+
+        let mut occupied: Vec<bool> = vec![false; map_size.hex_count()];
+        let mut centers: Vec<(HexCoord, Point)> = Vec::new();
+        for point in points {
+            let hex_coord: HexCoord = snap(map_size, point);
+            if !occupied[hex_coord.map_index(map_size)] {
+                occupied[hex_coord.map_index(map_size)] = true;
+                centers.push((hex_coord, point));
+            }
+        }
+        // An even split leaves one point over
+        centers.truncate(centers.len() - centers.len() % RESOURCE_TYPES.len());
+
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for (resource_index, (center, _)) in assign_resources(&plane, &centers).into_iter().zip(&centers) {
+            clusters.push(Cluster {
+                resource_type: RESOURCE_TYPES[resource_index],
+                hexes: grow_cluster(rng, map_size, *center, self.cluster_size, &mut occupied),
+            });
+        }
+        clusters
+    }
+
+    /// As many random points over the plane as are wanted on the map
+    fn scatter(&self, map_size: MapSize, rng: &mut SeededRng) -> (Plane, Vec<Point>) {
+        let (width, height): (f64, f64) = (f64::from(map_size.width), f64::from(map_size.height) * ROW_PITCH);
+        let wanted: f64 = f64::from(scaled_count(self.clusters_per_resource, map_size)) * RESOURCE_TYPES.len() as f64;
+        let spacing: f64 = (width * height / wanted.max(1.0)).sqrt();
+        let plane: Plane = Plane { width, height, spacing };
+
+        let points: Vec<Point> =
+            (0..wanted.round() as usize).map(|_| [rng.next_unit() * width, rng.next_unit() * height]).collect();
+        (plane, points)
+    }
+
+    /// Move the points apart until a step limit, movement threshold or force threshold is reached.
+    /// Returns the number of steps taken.
+    fn simulate(&self, plane: &Plane, points: &mut [Point]) -> u32 {
+        let range: f64 = plane.spacing * INTERACTION_RANGE;
+        for step in 0..self.max_steps {
+            let forces: Vec<Point> = (0..points.len()).map(|i| repulsion(plane, points, i, range)).collect();
+            let total_force: f64 = forces.iter().map(|force| length(*force)).sum();
+            if total_force < self.min_total_force {
+                return step;
+            }
+
+            let mut total_movement: f64 = 0.0;
+            for (point, force) in points.iter_mut().zip(&forces) {
+                let moved: Point = plane.wrap([
+                    point[0] + force[0] * self.time_step,
+                    point[1] + force[1] * self.time_step,
+                ]);
+                total_movement += toroidal_distance(plane, moved, *point);
+                *point = moved;
+            }
+            if total_movement < self.min_total_movement {
+                return step + 1;
+            }
+        }
+        self.max_steps
+    }
+}
+
+/// The force on a point from every other within range, falling from 1 for points at the same position to 0 at the
+/// edge of the range
+fn repulsion(plane: &Plane, points: &[Point], index: usize, range: f64) -> Point {
+    let point: Point = points[index];
+    let mut force: Point = [0.0, 0.0];
+    for (other_index, other) in points.iter().enumerate() {
+        if other_index == index {
+            continue;
+        }
+        let diff: Point = plane.diff(point, *other);
+        let distance: f64 = length(diff);
+        if distance >= range {
+            continue;
+        }
+        let magnitude: f64 = (1.0 - distance / range).powi(2);
+        if distance > 0.0 {
+            force[0] += magnitude * diff[0] / distance;
+            force[1] += magnitude * diff[1] / distance;
+        } else {
+            // Coincident points have no direction between them; push apart along the order they were scattered in
+            force[0] += if index < other_index { -magnitude } else { magnitude };
+        }
+    }
+    force
+}
+
+fn length(vector: Point) -> f64 {
+    (vector[0] * vector[0] + vector[1] * vector[1]).sqrt()
+}
+
+/// The hex whose center is nearest a point on the map, ignoring the slant of the hexes' sides
+fn snap(map_size: MapSize, point: Point) -> HexCoord {
+    let j: i16 = (point[1] / ROW_PITCH).round() as i16;
+    let row_offset: f64 = if j % 2 == 0 { 0.0 } else { 0.5 };
+    let i: i16 = (point[0] - row_offset).round() as i16;
+    map_size.wrap(HexCoord { i, j })
+}
+
+/// The index into [RESOURCE_TYPES] for each center, an equal number of each.
+/// Each center in turn takes the type, among those with places left, whose nearest center is furthest away, so that
+/// neighboring clusters tend to differ.
+fn assign_resources(plane: &Plane, centers: &[(HexCoord, Point)]) -> Vec<usize> {
+    let per_resource: usize = centers.len() / RESOURCE_TYPES.len();
+    let mut assigned: Vec<usize> = Vec::with_capacity(centers.len());
+    let mut counts: [usize; 2] = [0; 2];
+
+    for (n, (_, point)) in centers.iter().enumerate() {
+        let nearest = |resource_index: usize| -> f64 {
+            assigned
+                .iter()
+                .zip(&centers[..n])
+                .filter(|(assigned_index, _)| **assigned_index == resource_index)
+                .map(|(_, (_, other))| toroidal_distance(plane, *point, *other))
+                .fold(f64::INFINITY, f64::min)
+        };
+        let resource_index: usize = (0..RESOURCE_TYPES.len())
+            .filter(|resource_index| counts[*resource_index] < per_resource)
+            .max_by(|a, b| nearest(*a).total_cmp(&nearest(*b)))
+            .expect("a resource type has places left");
+        counts[resource_index] += 1;
+        assigned.push(resource_index);
+    }
+    assigned
+}
+
+/// Distance between two points on the map, which wraps at its edges
+fn toroidal_distance(plane: &Plane, a: Point, b: Point) -> f64 {
+    length(plane.diff(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::state::ResourceType;
+
+    fn count(map: &Map, resource_type: ResourceType) -> usize {
+        map.hexes().iter().filter(|hex| hex.resource_type == resource_type).count()
+    }
+
+    /// The smallest distance between any two points, across the plane's edges included
+    fn closest(plane: &Plane, points: &[Point]) -> f64 {
+        let mut closest: f64 = f64::INFINITY;
+        for (n, a) in points.iter().enumerate() {
+            for b in &points[n + 1..] {
+                closest = closest.min(toroidal_distance(plane, *a, *b));
+            }
+        }
+        closest
+    }
+
+    #[test]
+    fn reproducible_and_balanced() {
+        let generator: ParticleRepulsion = ParticleRepulsion::default();
+        let size: MapSize = MapSize::DEFAULT;
+        assert_eq!(
+            generator.generate(size, GameSeed(1)),
+            generator.generate(size, GameSeed(1))
+        );
+        assert_ne!(
+            generator.generate(size, GameSeed(1)),
+            generator.generate(size, GameSeed(2))
+        );
+
+        for seed in 0..8 {
+            let clusters: Vec<Cluster> = generator.place_clusters(size, &mut GameSeed(seed).rng(RngStream::Map));
+            let metal: usize = clusters.iter().filter(|cluster| cluster.resource_type == ResourceType::Metal).count();
+            assert_eq!(clusters.len(), metal * 2);
+            assert!(metal > 0);
+            assert!(clusters.iter().all(|cluster| cluster.hexes.len() == usize::from(generator.cluster_size)));
+
+            let map: Map = fill_clusters(size, clusters);
+            assert_eq!(count(&map, ResourceType::Metal), count(&map, ResourceType::Oil));
+        }
+    }
+
+    #[test]
+    fn spreads_points_apart() {
+        let generator: ParticleRepulsion = ParticleRepulsion::default();
+        let (plane, mut points): (Plane, Vec<Point>) =
+            generator.scatter(MapSize::DEFAULT, &mut GameSeed(5).rng(RngStream::Map));
+        let before: f64 = closest(&plane, &points);
+        let steps: u32 = generator.simulate(&plane, &mut points);
+        assert!(0 < steps && steps <= generator.max_steps);
+        assert!(closest(&plane, &points) > before);
+        assert!(closest(&plane, &points) > plane.spacing / 2.0);
+        assert!(points.iter().all(|point| plane.wrap(*point) == *point));
+
+        // A tiny step moves the points too little to continue
+        let halting: ParticleRepulsion = ParticleRepulsion {
+            time_step: 1e-9,
+            ..ParticleRepulsion::default()
+        };
+        assert_eq!(1, halting.simulate(&plane, &mut points.clone()));
+    }
+
+    #[test]
+    fn repels_across_edges() {
+        let plane: Plane = Plane {
+            width: 96.0,
+            height: 64.0 * ROW_PITCH,
+            spacing: 8.0,
+        };
+        // Points just inside opposite edges are neighbors, and push each other back from the edge
+        assert_eq!([0.5, 0.0], plane.diff([0.25, 1.0], [95.75, 1.0]));
+        assert_eq!([-0.5, 0.0], plane.diff([95.75, 1.0], [0.25, 1.0]));
+        let points: Vec<Point> = vec![[0.25, 1.0], [95.75, 1.0]];
+        assert!(repulsion(&plane, &points, 0, 12.0)[0] > 0.0);
+        assert!(repulsion(&plane, &points, 1, 12.0)[0] < 0.0);
+        assert_eq!([0.5, 1.0], plane.wrap([96.5, 1.0 + plane.height]));
+
+        // Settled points are as far apart across the map's edges as within it
+        let generator: ParticleRepulsion = ParticleRepulsion::default();
+        for seed in 0..4 {
+            let (plane, mut points): (Plane, Vec<Point>) =
+                generator.scatter(MapSize::DEFAULT, &mut GameSeed(seed).rng(RngStream::Map));
+            generator.simulate(&plane, &mut points);
+            let mut across: usize = 0;
+            for (n, a) in points.iter().enumerate() {
+                for b in &points[n + 1..] {
+                    let distance: f64 = toroidal_distance(&plane, *a, *b);
+                    if distance < length([a[0] - b[0], a[1] - b[1]]) && distance < plane.spacing {
+                        across += 1;
+                        assert!(distance > plane.spacing / 2.0);
+                    }
+                }
+            }
+            assert!(across > 0);
+        }
+    }
+
+    #[test]
+    fn snaps_to_nearest_hex() {
+        let size: MapSize = MapSize { width: 96, height: 64 };
+        assert_eq!(HexCoord { i: 0, j: 0 }, snap(size, [0.2, 0.1]));
+        assert_eq!(HexCoord { i: 3, j: 1 }, snap(size, [3.4, ROW_PITCH]));
+        // Just short of the far edges rounds onto the first column and row
+        assert_eq!(
+            HexCoord { i: 0, j: 2 },
+            snap(size, [f64::from(size.width) - 0.1, 2.0 * ROW_PITCH])
+        );
+        assert_eq!(
+            HexCoord { i: 10, j: 0 },
+            snap(size, [10.0, (f64::from(size.height) - 0.1) * ROW_PITCH])
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Start positions for the players (plan.txt, Players).
+//! The map is divided into regions, each holding the hexes nearer one resource cluster than any other, which is the
+//! Voronoi diagram of the clusters on the toroidal hex grid. Players start on the edges between regions, preferring
+//! edges between a metal and an oil cluster, so that every player is about as near to one of each. A repulsion pass
+//! then spreads the players apart along the edges.
+
+use crate::map::config::MapSize;
+use crate::map::coordinate::HexCoord;
+use crate::map::state::{Map, ResourceType};
+use crate::random::{GameSeed, RngStream, SeededRng};
+use std::collections::VecDeque;
+
+/// The repulsion pass stops once no player moves, or after this many rounds
+const MAX_REPULSION_ROUNDS: usize = 16;
+
+/// One start position per player, in the order players join.
+/// The same map, player count and seed always yield the same positions.
+pub fn place_players(map: &Map, player_count: u8, seed: GameSeed) -> Vec<HexCoord> {
+    let mut rng: SeededRng = seed.rng(RngStream::Players);
+    let mut candidates: Vec<HexCoord> = candidates(map, usize::from(player_count));
+    rng.shuffle(&mut candidates);
+    let mut starts: Vec<HexCoord> = candidates.iter().copied().take(usize::from(player_count)).collect();
+
+    // Each player in turn moves to the candidate where the repulsion from the others is weakest
+    let map_size: MapSize = map.size();
+    let mut distances: Vec<Vec<i16>> = starts.iter().map(|start| distances_from(map_size, *start)).collect();
+    for _ in 0..MAX_REPULSION_ROUNDS {
+        let mut moved: bool = false;
+        for player in 0..starts.len() {
+            let energy = |hex_coord: HexCoord| -> f64 {
+                let mut energy: f64 = 0.0;
+                for (other, other_distances) in distances.iter().enumerate() {
+                    if other == player {
+                        continue;
+                    }
+                    let steps: f64 = f64::from(other_distances[hex_coord.map_index(map_size)]);
+                    energy += 1.0 / (steps * steps);
+                }
+                energy
+            };
+
+            let best: HexCoord = candidates
+                .iter()
+                .copied()
+                .min_by(|a, b| energy(*a).total_cmp(&energy(*b)))
+                .expect("there is a candidate for every player");
+            if energy(best) < energy(starts[player]) {
+                starts[player] = best;
+                distances[player] = distances_from(map_size, best);
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+    starts
+}
+
+/// Where players may start, with at least `player_count` hexes if the map has room for them.
+/// Edges between metal and oil regions are preferred, then any edges, then any hex without resources.
+fn candidates(map: &Map, player_count: usize) -> Vec<HexCoord> {
+    let map_size: MapSize = map.size();
+    let (clusters, resource_types): (Vec<Option<usize>>, Vec<ResourceType>) = clusters(map);
+    let regions: Vec<usize> = regions(map_size, &clusters);
+
+    let mut mixed_edges: Vec<HexCoord> = Vec::new();
+    let mut edges: Vec<HexCoord> = Vec::new();
+    let mut empty: Vec<HexCoord> = Vec::new();
+    for (index, region) in regions.iter().enumerate() {
+        if clusters[index].is_some() {
+            continue;
+        }
+        let hex_coord: HexCoord = HexCoord::from_map_index(index, map_size);
+        empty.push(hex_coord);
+
+        let neighbor_regions = hex_coord.neighbors(map_size).map(|neighbor| regions[neighbor.map_index(map_size)]);
+        if neighbor_regions.iter().any(|neighbor_region| neighbor_region != region) {
+            edges.push(hex_coord);
+        }
+        // Without any clusters there are no resource types to compare
+        let resource_type_o: Option<&ResourceType> = resource_types.get(*region);
+        if neighbor_regions.iter().any(|neighbor_region| resource_types.get(*neighbor_region) != resource_type_o) {
+            mixed_edges.push(hex_coord);
+        }
+    }
+
+    [mixed_edges, edges, empty]
+        .into_iter()
+        .find(|candidates| candidates.len() >= player_count)
+        .unwrap_or_else(|| map_size.hex_coords().collect())
+}
+
+/// The cluster each hex belongs to, if any, and each cluster's resource type.
+/// A cluster is a connected group of hexes with the same resource.
+fn clusters(map: &Map) -> (Vec<Option<usize>>, Vec<ResourceType>) {
+    let map_size: MapSize = map.size();
+    let mut clusters: Vec<Option<usize>> = vec![None; map_size.hex_count()];
+    let mut resource_types: Vec<ResourceType> = Vec::new();
+
+    for hex in map.hexes() {
+        if hex.resource_type == ResourceType::None || clusters[hex.hex_coord.map_index(map_size)].is_some() {
+            continue;
+        }
+        let cluster: usize = resource_types.len();
+        resource_types.push(hex.resource_type);
+        clusters[hex.hex_coord.map_index(map_size)] = Some(cluster);
+
+        let mut queue: VecDeque<HexCoord> = VecDeque::from([hex.hex_coord]);
+        while let Some(hex_coord) = queue.pop_front() {
+            for neighbor in hex_coord.neighbors(map_size) {
+                let same_resource: bool =
+                    map.get(neighbor).is_some_and(|other| other.resource_type == hex.resource_type);
+                if same_resource && clusters[neighbor.map_index(map_size)].is_none() {
+                    clusters[neighbor.map_index(map_size)] = Some(cluster);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    (clusters, resource_types)
+}
+
+/// The cluster nearest each hex, found by searching outwards from every cluster at once.
+/// Without any clusters, the whole map is one region.
+fn regions(map_size: MapSize, clusters: &[Option<usize>]) -> Vec<usize> {
+    let mut regions: Vec<Option<usize>> = clusters.to_vec();
+    let mut queue: VecDeque<HexCoord> =
+        map_size.hex_coords().filter(|hex_coord| clusters[hex_coord.map_index(map_size)].is_some()).collect();
+
+    while let Some(hex_coord) = queue.pop_front() {
+        for neighbor in hex_coord.neighbors(map_size) {
+            if regions[neighbor.map_index(map_size)].is_none() {
+                regions[neighbor.map_index(map_size)] = regions[hex_coord.map_index(map_size)];
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    regions.into_iter().map(|region| region.unwrap_or_default()).collect()
+}
+
+/// Step distance from the hex to every hex, indexed by map index
+fn distances_from(map_size: MapSize, hex_coord: HexCoord) -> Vec<i16> {
+    map_size.hex_coords().map(|other| hex_coord.distance(other, map_size)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::generate::{MapGenerator, ParticleRepulsion, WeightedSeeding};
+
+    /// Steps from the start to the nearest hex with the resource
+    fn nearest(map: &Map, start: HexCoord, resource_type: ResourceType) -> i16 {
+        let distances: Vec<i16> = distances_from(map.size(), start);
+        map.hexes()
+            .iter()
+            .filter(|hex| hex.resource_type == resource_type)
+            .map(|hex| distances[hex.hex_coord.map_index(map.size())])
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn spreads_players_near_both_resources() {
+        let generators: [Box<dyn MapGenerator>; 2] = [
+            Box::new(WeightedSeeding::default()),
+            Box::new(ParticleRepulsion::default()),
+        ];
+        let size: MapSize = MapSize::DEFAULT;
+        for generator in generators {
+            for seed in 0..4 {
+                let map: Map = generator.generate(size, GameSeed(seed));
+                let starts: Vec<HexCoord> = place_players(&map, 4, GameSeed(seed));
+                assert_eq!(starts, place_players(&map, 4, GameSeed(seed)));
+                assert_eq!(4, starts.len());
+
+                for (n, start) in starts.iter().enumerate() {
+                    assert_eq!(ResourceType::None, map.get(*start).unwrap().resource_type);
+                    let distances: Vec<i16> = distances_from(size, *start);
+                    for other in &starts[n + 1..] {
+                        assert!(distances[other.map_index(size)] >= size.width / 4);
+                    }
+                    assert!(nearest(&map, *start, ResourceType::Metal) <= size.width / 4);
+                    assert!(nearest(&map, *start, ResourceType::Oil) <= size.width / 4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn places_players_without_resources() {
+        let empty: Map = Map::empty(MapSize { width: 24, height: 16 });
+        let starts: Vec<HexCoord> = place_players(&empty, 3, GameSeed(1));
+        assert_eq!(3, starts.len());
+        assert!(starts[0] != starts[1] && starts[1] != starts[2] && starts[0] != starts[2]);
+        assert!(place_players(&empty, 0, GameSeed(1)).is_empty());
+    }
+}
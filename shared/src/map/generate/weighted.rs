@@ -0,0 +1,194 @@
+//! Resource clusters placed by weighted seeding (plan.txt, Strategy 1).
+//! Every hex holds a weight for each resource type, and each cluster is centered on a hex chosen with probability
+//! proportional to its weight. Placing a cluster lowers the weights around it, so that clusters of the same resource
+//! keep apart, and clusters of different resources keep apart to a lesser degree.
+
+use crate::map::config::MapSize;
+use crate::map::coordinate::HexCoord;
+use crate::map::generate::{Cluster, MapGenerator, RESOURCE_TYPES, fill_clusters, grow_cluster, scaled_count};
+use crate::map::state::Map;
+use crate::random::{GameSeed, RngStream, SeededRng};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedSeeding {
+    /// Clusters of each resource type on a map of the default size, as long as there is room for them
+    pub clusters_per_resource: u16,
+    /// Hexes in each cluster, including its center
+    pub cluster_size: u16,
+    /// Every hex's weight for each resource type before any cluster is placed
+    pub initial_weight: f64,
+    /// Weights up to this many steps from a cluster's center are cut to zero
+    pub min_distance: i16,
+    /// Weights this many steps or more from a cluster's center are left alone; closer, they taper towards zero
+    pub max_distance: i16,
+    /// How strongly a cluster lowers the weights of the other resource types, from 0 (not at all) to 1 (as much as
+    /// its own)
+    pub cross_resource_effect: f64,
+}
+
+impl Default for WeightedSeeding {
+    fn default() -> Self {
+        WeightedSeeding {
+            clusters_per_resource: 12,
+            cluster_size: 4,
+            initial_weight: 10.0,
+            min_distance: 4,
+            max_distance: 10,
+            cross_resource_effect: 0.5,
+        }
+    }
+}
+
+impl MapGenerator for WeightedSeeding {
+    fn generate(&self, map_size: MapSize, seed: GameSeed) -> Map {
+        fill_clusters(map_size, self.place_clusters(map_size, &mut seed.rng(RngStream::Map)))
+    }
+}
+
+impl WeightedSeeding {
+    fn place_clusters(&self, map_size: MapSize, rng: &mut SeededRng) -> Vec<Cluster> {
+        // Indexed by map index, then by position in RESOURCE_TYPES
+        let mut weights: Vec<[f64; 2]> = vec![[self.initial_weight; 2]; map_size.hex_count()];
+        let mut occupied: Vec<bool> = vec![false; map_size.hex_count()];
+        let mut clusters: Vec<Cluster> = Vec::new();
+
+        // Alternate between the resource types, so that neither is placed while the map is emptier
+        let clusters_per_resource: u16 = scaled_count(self.clusters_per_resource, map_size);
+        for n in 0..usize::from(clusters_per_resource) * RESOURCE_TYPES.len() {
+            let resource_index: usize = n % RESOURCE_TYPES.len();
+            let Some(center) = choose_weighted(rng, map_size, &weights, resource_index) else {
+                // No room is left for this resource type
+                continue;
+            };
+
+            let hexes: Vec<HexCoord> = grow_cluster(rng, map_size, center, self.cluster_size, &mut occupied);
+            self.reweight(map_size, &mut weights, center, resource_index);
+            for hex_coord in &hexes {
+                weights[hex_coord.map_index(map_size)] = [0.0; 2];
+            }
+            clusters.push(Cluster {
+                resource_type: RESOURCE_TYPES[resource_index],
+                hexes,
+            });
+        }
+        clusters
+    }
+
+    /// Lower the weights around a newly placed cluster's center
+    fn reweight(&self, map_size: MapSize, weights: &mut [[f64; 2]], center: HexCoord, resource_index: usize) {
+        for (hex_coord, steps) in center.within_steps(self.max_distance, map_size) {
+            let kept: f64 = self.kept_weight(steps);
+            let hex_weights: &mut [f64; 2] = &mut weights[hex_coord.map_index(map_size)];
+            for (index, weight) in hex_weights.iter_mut().enumerate() {
+                if index == resource_index {
+                    *weight *= kept;
+                } else {
+                    *weight *= 1.0 - self.cross_resource_effect * (1.0 - kept);
+                }
+            }
+        }
+    }
+
+    /// The proportion of a weight kept at the distance from a cluster of the same resource type
+    fn kept_weight(&self, steps: i16) -> f64 {
+        if steps <= self.min_distance {
+            0.0
+        } else if steps >= self.max_distance {
+            1.0
+        } else {
+            f64::from(steps - self.min_distance) / f64::from(self.max_distance - self.min_distance)
+        }
+    }
+}
+
+/// A hex chosen with probability proportional to its weight for the resource type.
+/// None if every weight is zero.
+fn choose_weighted(
+    rng: &mut SeededRng,
+    map_size: MapSize,
+    weights: &[[f64; 2]],
+    resource_index: usize,
+) -> Option<HexCoord> {
+    let total: f64 = weights.iter().map(|hex_weights| hex_weights[resource_index]).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut target: f64 = rng.next_unit() * total;
+    for (index, hex_weights) in weights.iter().enumerate() {
+        let weight: f64 = hex_weights[resource_index];
+        if target < weight {
+            return Some(HexCoord::from_map_index(index, map_size));
+        }
+        target -= weight;
+    }
+    // Rounding may carry the target past the last hex with any weight
+    weights
+        .iter()
+        .rposition(|hex_weights| hex_weights[resource_index] > 0.0)
+        .map(|index| HexCoord::from_map_index(index, map_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::state::ResourceType;
+
+    fn count(map: &Map, resource_type: ResourceType) -> usize {
+        map.hexes().iter().filter(|hex| hex.resource_type == resource_type).count()
+    }
+
+    #[test]
+    fn reproducible_from_seed() {
+        let generator: WeightedSeeding = WeightedSeeding::default();
+        let size: MapSize = MapSize::DEFAULT;
+        assert_eq!(
+            generator.generate(size, GameSeed(1)),
+            generator.generate(size, GameSeed(1))
+        );
+        assert_ne!(
+            generator.generate(size, GameSeed(1)),
+            generator.generate(size, GameSeed(2))
+        );
+
+        let map: Map = generator.generate(size, GameSeed(1));
+        let hexes: usize = usize::from(generator.clusters_per_resource * generator.cluster_size);
+        assert_eq!(hexes, count(&map, ResourceType::Metal));
+        assert_eq!(hexes, count(&map, ResourceType::Oil));
+    }
+
+    #[test]
+    fn clusters_keep_apart() {
+        let generator: WeightedSeeding = WeightedSeeding::default();
+        let size: MapSize = MapSize::DEFAULT;
+        let clusters: Vec<Cluster> = generator.place_clusters(size, &mut GameSeed(7).rng(RngStream::Map));
+        assert_eq!(usize::from(generator.clusters_per_resource) * 2, clusters.len());
+
+        for (n, cluster) in clusters.iter().enumerate() {
+            let nearby: Vec<(HexCoord, i16)> = cluster.hexes[0].within_steps(generator.min_distance, size);
+            for other in clusters[n + 1..].iter().filter(|other| other.resource_type == cluster.resource_type) {
+                assert!(!nearby.iter().any(|(hex_coord, _)| *hex_coord == other.hexes[0]));
+            }
+            // Every hex joins the cluster beside another of its hexes
+            for (m, hex_coord) in cluster.hexes.iter().enumerate().skip(1) {
+                assert!(cluster.hexes[..m].iter().any(|placed| placed.is_neighbor(*hex_coord, size)));
+            }
+        }
+    }
+
+    #[test]
+    fn stops_when_full() {
+        let generator: WeightedSeeding = WeightedSeeding {
+            clusters_per_resource: 1000,
+            cluster_size: 1,
+            min_distance: 8,
+            max_distance: 8,
+            ..WeightedSeeding::default()
+        };
+        let map: Map = generator.generate(MapSize::DEFAULT, GameSeed(3));
+        let metal: usize = count(&map, ResourceType::Metal);
+        assert!(0 < metal && metal < 1000);
+        assert_eq!(0.0, generator.kept_weight(8));
+        assert_eq!(1.0, generator.kept_weight(9));
+    }
+}
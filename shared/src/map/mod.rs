@@ -0,0 +1,17 @@
+//! The hex map every game is played on. The server generates it from the game's seed and sends it to clients,
+//! which only render it.
+
+mod config;
+pub use config::*;
+
+mod coordinate;
+pub use coordinate::*;
+
+mod cube;
+pub use cube::*;
+
+mod generate;
+pub use generate::*;
+
+mod state;
+pub use state::*;
@@ -0,0 +1,199 @@
+use crate::error::AppError;
+use crate::map::config::MapSize;
+use crate::map::coordinate::HexCoord;
+use crate::network::codec::{ByteReader, Decode, Encode};
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResourceType {
+    None = 0,
+    Metal,
+    Oil,
+}
+
+impl Default for ResourceType {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl ResourceType {
+    pub const DEFAULT: ResourceType = ResourceType::None;
+
+    pub fn from_u8(value: u8) -> Result<Self, AppError> {
+        match value {
+            0 => Ok(ResourceType::None),
+            1 => Ok(ResourceType::Metal),
+            2 => Ok(ResourceType::Oil),
+            _ => Err(AppError::new(&format!("Invalid resource type; [{}]", value))),
+        }
+    }
+}
+
+impl Encode for ResourceType {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        (*self as u8).encode(buffer);
+    }
+}
+
+impl Decode for ResourceType {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        ResourceType::from_u8(u8::decode(reader)?)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hex {
+    pub hex_coord: HexCoord,
+    pub resource_type: ResourceType,
+}
+
+impl Default for Hex {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Hex {
+    pub const DEFAULT: Hex = Hex {
+        hex_coord: HexCoord::DEFAULT,
+        resource_type: ResourceType::DEFAULT,
+    };
+}
+
+/// Every hex of a game's map, indexed by [HexCoord::map_index], and where each player starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    size: MapSize,
+    hexes: Vec<Hex>,
+    /// In the order players join
+    starts: Vec<HexCoord>,
+}
+
+impl Map {
+    /// A map with no resources
+    pub fn empty(size: MapSize) -> Map {
+        Map::from_resource_types(size, (0..size.hex_count()).map(|_| ResourceType::None))
+    }
+
+    fn from_resource_types(size: MapSize, resource_types: impl Iterator<Item = ResourceType>) -> Map {
+        let hexes: Vec<Hex> = resource_types
+            .zip(size.hex_coords())
+            .map(|(resource_type, hex_coord)| Hex {
+                hex_coord,
+                resource_type,
+            })
+            .collect();
+        debug_assert_eq!(size.hex_count(), hexes.len());
+        Map {
+            size,
+            hexes,
+            starts: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> MapSize {
+        self.size
+    }
+
+    pub fn hexes(&self) -> &[Hex] {
+        &self.hexes
+    }
+
+    /// None if the hex lies beyond the map's edges
+    pub fn get(&self, hex_coord: HexCoord) -> Option<&Hex> {
+        if !self.size.contains(hex_coord) {
+            return None;
+        }
+        self.hexes.get(hex_coord.map_index(self.size))
+    }
+
+    pub fn set_resource_type(&mut self, hex_coord: HexCoord, resource_type: ResourceType) {
+        self.hexes[hex_coord.map_index(self.size)].resource_type = resource_type;
+    }
+
+    pub fn starts(&self) -> &[HexCoord] {
+        &self.starts
+    }
+
+    pub fn set_starts(&mut self, starts: Vec<HexCoord>) {
+        self.starts = starts;
+    }
+}
+
+/// Only the hexes' resource types are encoded, in index order; the coordinates follow from the order and the size.
+impl Encode for Map {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.size.encode(buffer);
+        let resource_types: Vec<ResourceType> = self.hexes.iter().map(|hex| hex.resource_type).collect();
+        resource_types.encode(buffer);
+        self.starts.encode(buffer);
+    }
+}
+
+impl Decode for Map {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        let size: MapSize = MapSize::decode(reader)?;
+        let resource_types: Vec<ResourceType> = Vec::<ResourceType>::decode(reader)?;
+        if resource_types.len() != size.hex_count() {
+            return Err(AppError::new(&format!(
+                "Unexpected hex count; [expected: {}] [found: {}]",
+                size.hex_count(),
+                resource_types.len()
+            )));
+        }
+        let mut map: Map = Map::from_resource_types(size, resource_types.into_iter());
+        let starts: Vec<HexCoord> = Vec::<HexCoord>::decode(reader)?;
+        if let Some(start) = starts.iter().find(|start| !size.contains(**start)) {
+            return Err(AppError::new(&format!(
+                "Start outside the map; [{:?}] [{}]",
+                start, size
+            )));
+        }
+        map.set_starts(starts);
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_round_trip() {
+        let size: MapSize = MapSize { width: 96, height: 64 };
+        let mut map: Map = Map::empty(size);
+        map.set_resource_type(HexCoord { i: 5, j: 7 }, ResourceType::Metal);
+        map.set_resource_type(HexCoord { i: 95, j: 63 }, ResourceType::Oil);
+        map.set_starts(vec![HexCoord { i: 1, j: 2 }, HexCoord { i: 80, j: 30 }]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        map.encode(&mut buffer);
+        let mut reader: ByteReader = ByteReader::new(&buffer);
+        let decoded: Map = Map::decode(&mut reader).unwrap();
+        reader.finish().unwrap();
+        assert_eq!(map, decoded);
+        assert_eq!(size, decoded.size());
+        assert_eq!(
+            HexCoord { i: 5, j: 7 },
+            decoded.get(HexCoord { i: 5, j: 7 }).unwrap().hex_coord
+        );
+        assert_eq!(
+            ResourceType::Oil,
+            decoded.get(HexCoord { i: 95, j: 63 }).unwrap().resource_type
+        );
+        assert!(decoded.get(HexCoord { i: 64, j: 64 }).is_none());
+
+        let mut truncated: Vec<u8> = Vec::new();
+        size.encode(&mut truncated);
+        vec![ResourceType::None; 3].encode(&mut truncated);
+        assert!(Map::decode(&mut ByteReader::new(&truncated)).is_err());
+
+        // A start that only fits a wider map
+        let mut narrow: Map = Map::empty(MapSize::DEFAULT);
+        narrow.set_starts(vec![HexCoord { i: 80, j: 30 }]);
+        let mut buffer: Vec<u8> = Vec::new();
+        narrow.encode(&mut buffer);
+        assert!(Map::decode(&mut ByteReader::new(&buffer)).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! Delays between reconnection attempts.
+
+use crate::random;
+use std::time::Duration;
+
+/// Exponential backoff with jitter.
+/// Jitter spreads out the reconnection attempts of many clients after a server restart.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Backoff {
+    pub const fn new(min: Duration, max: Duration) -> Self {
+        Backoff { min, max, attempts: 0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// The delay before the next attempt: between half and all of `min * 2^attempts`, capped at `max`.
+    pub fn next_delay(&mut self) -> Duration {
+        let ceiling: Duration = self.ceiling();
+        self.attempts = self.attempts.saturating_add(1);
+        ceiling.mul_f64(0.5 + 0.5 * random::random_unit())
+    }
+
+    fn ceiling(&self) -> Duration {
+        let factor: u32 = 1_u32.checked_shl(self.attempts).unwrap_or(u32::MAX);
+        self.min.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_to_max() {
+        let min: Duration = Duration::from_millis(100);
+        let max: Duration = Duration::from_secs(5);
+        let mut backoff: Backoff = Backoff::new(min, max);
+
+        let first: Duration = backoff.next_delay();
+        assert!(first >= min / 2 && first <= min);
+
+        for _ in 0..100 {
+            let delay: Duration = backoff.next_delay();
+            assert!(delay <= max);
+        }
+        assert!(backoff.next_delay() >= max / 2);
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= min);
+    }
+}
@@ -0,0 +1,237 @@
+//! Explicit serialization of protocol fields.
+//! Every multi-byte integer is written in Big-Endian order.
+//! Variable-length values (strings, sequences) are prefixed with their element count as a `u32`.
+
+use crate::error::AppError;
+use uuid::Uuid;
+
+pub trait Encode {
+    fn encode(&self, buffer: &mut Vec<u8>);
+}
+
+pub trait Decode: Sized {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError>;
+}
+
+/// Cursor over a byte slice which returns an error rather than panicking on short input.
+#[derive(Debug)]
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub fn take(&mut self, count: usize) -> Result<&'a [u8], AppError> {
+        if count > self.remaining() {
+            return Err(AppError::new(&format!(
+                "Unexpected end of input; [position: {}] [requested: {}] [remaining: {}]",
+                self.position,
+                count,
+                self.remaining()
+            )));
+        }
+
+        let slice: &'a [u8] = &self.bytes[self.position..(self.position + count)];
+        self.position += count;
+        Ok(slice)
+    }
+
+    pub fn take_array<const N: usize>(&mut self) -> Result<[u8; N], AppError> {
+        let slice: &[u8] = self.take(N)?;
+        Ok(slice.try_into().unwrap()) // Length was checked by ByteReader::take
+    }
+
+    /// Fail if any input remains unread.
+    pub fn finish(&self) -> Result<(), AppError> {
+        if self.remaining() > 0 {
+            return Err(AppError::new(&format!(
+                "Unexpected trailing input; [remaining: {}]",
+                self.remaining()
+            )));
+        }
+        Ok(())
+    }
+}
+
+macro_rules! integer_impl {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, buffer: &mut ::std::vec::Vec<u8>) {
+                    buffer.extend_from_slice(&self.to_be_bytes());
+                }
+            }
+
+            impl Decode for $t {
+                fn decode(reader: &mut ByteReader) -> ::std::result::Result<Self, AppError> {
+                    ::std::result::Result::Ok(<$t>::from_be_bytes(reader.take_array()?))
+                }
+            }
+        )*
+    };
+}
+
+integer_impl!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Encode for bool {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        u8::from(*self).encode(buffer);
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(AppError::new(&format!("Invalid boolean; [{}]", value))),
+        }
+    }
+}
+
+impl Encode for Uuid {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for Uuid {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Uuid::from_bytes(reader.take_array()?))
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        encode_length(buffer, self.len());
+        buffer.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        let length: usize = decode_length(reader)?;
+        let bytes: &[u8] = reader.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|err| AppError::from_error("Invalid UTF-8 string", Box::new(err)))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        encode_length(buffer, self.len());
+        for item in self {
+            item.encode(buffer);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        let length: usize = decode_length(reader)?;
+        // Do not trust the declared length for preallocation
+        let mut items: Vec<T> = Vec::with_capacity(length.min(reader.remaining()));
+        for _ in 0..length {
+            items.push(T::decode(reader)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        match self {
+            None => false.encode(buffer),
+            Some(value) => {
+                true.encode(buffer);
+                value.encode(buffer);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        match bool::decode(reader)? {
+            false => Ok(None),
+            true => Ok(Some(T::decode(reader)?)),
+        }
+    }
+}
+
+fn encode_length(buffer: &mut Vec<u8>, length: usize) {
+    let length: u32 = u32::try_from(length).expect("Encoded sequence length exceeds u32::MAX");
+    length.encode(buffer);
+}
+
+fn decode_length(reader: &mut ByteReader) -> Result<usize, AppError> {
+    Ok(u32::decode(reader)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Encode + Decode>(value: &T) -> T {
+        let mut buffer: Vec<u8> = Vec::new();
+        value.encode(&mut buffer);
+        let mut reader: ByteReader = ByteReader::new(&buffer);
+        let decoded: T = T::decode(&mut reader).unwrap();
+        reader.finish().unwrap();
+        decoded
+    }
+
+    #[test]
+    fn integers_are_big_endian() {
+        let mut buffer: Vec<u8> = Vec::new();
+        0x0102_u16.encode(&mut buffer);
+        0x0304_0506_u32.encode(&mut buffer);
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], buffer);
+
+        assert_eq!(-2_i16, round_trip(&-2_i16));
+        assert_eq!(u64::MAX, round_trip(&u64::MAX));
+    }
+
+    #[test]
+    fn strings_and_sequences() {
+        assert_eq!("hello world", round_trip(&String::from("hello world")));
+        assert_eq!(vec![1_u16, 2, 3], round_trip(&vec![1_u16, 2, 3]));
+        assert_eq!(Some(7_u8), round_trip(&Some(7_u8)));
+        assert_eq!(None, round_trip(&Option::<u8>::None));
+    }
+
+    #[test]
+    fn short_input() {
+        let mut reader: ByteReader = ByteReader::new(&[0, 1, 2]);
+        assert!(u32::decode(&mut reader).is_err());
+
+        let mut buffer: Vec<u8> = Vec::new();
+        String::from("truncated").encode(&mut buffer);
+        buffer.pop();
+        assert!(String::decode(&mut ByteReader::new(&buffer)).is_err());
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert!(bool::decode(&mut ByteReader::new(&[2])).is_err());
+
+        let mut buffer: Vec<u8> = Vec::new();
+        2_u32.encode(&mut buffer);
+        buffer.extend_from_slice(&[0xff, 0xfe]);
+        assert!(String::decode(&mut ByteReader::new(&buffer)).is_err());
+
+        let reader: ByteReader = ByteReader::new(&[0]);
+        assert!(reader.finish().is_err());
+    }
+}
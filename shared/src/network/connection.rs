@@ -1,20 +1,40 @@
 use crate::error::AppError;
-use crate::network::protocol::Frame;
+use crate::network::protocol::{Frame, MAX_FRAME_SIZE, Operation};
+use crate::network::request::{PendingRequests, Requester};
 use crate::network::ring_buffer::RingBuffer;
+use crate::network::transport::{PeerAddr, Transport, TransportRead, TransportWrite};
+use crate::network::write_buffer::WriteBuffer;
 use std::fmt::Debug;
-use std::io;
-use std::io::IoSliceMut;
-use std::net::SocketAddr;
+use std::future;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
-use tokio::sync::RwLock;
+use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
 
+/// Initial capacity of each connection buffer
 pub const BUFFER_SIZE: usize = 4096;
+/// Buffers grow to accommodate large frames; room is left for a maximum size frame plus whatever precedes it
+pub const MAX_BUFFER_SIZE: usize = 2 * MAX_FRAME_SIZE;
 
-pub type ReadBufferT = RingBuffer<u8, { BUFFER_SIZE }>;
-pub type WriteBufferT = Arc<RwLock<RingBuffer<u8, { BUFFER_SIZE }>>>;
+pub type ReadBufferT = RingBuffer<u8>;
+pub type WriteBufferT = Arc<WriteBuffer>;
+
+pub fn new_buffer() -> RingBuffer<u8> {
+    RingBuffer::with_max_capacity(BUFFER_SIZE, MAX_BUFFER_SIZE)
+}
+
+/// Serialize an operation and push it to the write buffer, waiting for space if the buffer is full.
+pub async fn send<T: Operation>(write_buffer: &WriteBufferT, operation: &T) -> Result<(), AppError> {
+    let bytes: Vec<u8> = operation.as_bytes();
+    if bytes.len() > MAX_FRAME_SIZE {
+        return Err(AppError::new(&format!(
+            "Frame exceeds maximum size; [length: {}] [max: {}]",
+            bytes.len(),
+            MAX_FRAME_SIZE
+        )));
+    }
+    write_buffer.push(bytes.as_slice()).await
+}
 
 pub enum BytesRead {
     Some(usize),
@@ -23,76 +43,83 @@ pub enum BytesRead {
 
 #[derive(Debug)]
 pub struct Connection {
-    pub socket_addr: Arc<SocketAddr>,
+    pub peer_addr: Arc<PeerAddr>,
+    pub pending_requests: Arc<PendingRequests>,
     pub reader: ConnectionReader,
     pub writer: ConnectionWriter,
 }
 
 impl Connection {
-    pub fn new(tcp_stream: TcpStream, socket_addr: SocketAddr) -> Self {
-        let socket_addr: Arc<SocketAddr> = Arc::new(socket_addr);
-        let (reader, writer): (OwnedReadHalf, OwnedWriteHalf) = tcp_stream.into_split();
-        let write_buffer: Arc<RwLock<RingBuffer<u8, 4096>>> = Arc::new(RwLock::new(RingBuffer::new()));
+    pub fn new<T: Transport>(transport: T, peer_addr: PeerAddr) -> Self {
+        let peer_addr: Arc<PeerAddr> = Arc::new(peer_addr);
+        let (reader, writer): (T::Read, T::Write) = transport.into_split();
+        let write_buffer: WriteBufferT = Arc::new(WriteBuffer::new(new_buffer()));
+        let pending_requests: Arc<PendingRequests> = Arc::new(PendingRequests::default());
         Connection {
-            socket_addr: socket_addr.clone(),
+            peer_addr: peer_addr.clone(),
+            pending_requests: pending_requests.clone(),
             reader: ConnectionReader {
-                socket_addr: socket_addr.clone(),
-                tcp_stream_read: reader,
-                read_buffer: RingBuffer::new(),
+                peer_addr: peer_addr.clone(),
+                transport_read: Box::new(reader),
+                read_buffer: new_buffer(),
                 write_buffer: write_buffer.clone(),
+                pending_requests,
             },
             writer: ConnectionWriter {
-                socket_addr,
-                tcp_stream_write: writer,
+                peer_addr,
+                transport_write: Box::new(writer),
                 buffer: write_buffer,
             },
         }
     }
+
+    pub fn requester(&self) -> Requester {
+        Requester::new(self.writer.buffer.clone(), self.pending_requests.clone())
+    }
 }
 
 #[derive(Debug)]
 pub struct ConnectionWriter {
-    pub socket_addr: Arc<SocketAddr>,
-    pub tcp_stream_write: OwnedWriteHalf,
+    pub peer_addr: Arc<PeerAddr>,
+    pub transport_write: Box<dyn TransportWrite>,
     pub buffer: WriteBufferT,
 }
 
 impl ConnectionWriter {
     pub async fn write_frame(&mut self, frame: &Frame) -> Result<(), AppError> {
-        self.tcp_stream_write.write_all(frame.data.as_slice()).await?;
+        self.transport_write.write_all(frame.data.as_slice()).await?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub struct ConnectionReader {
-    pub socket_addr: Arc<SocketAddr>,
-    pub tcp_stream_read: OwnedReadHalf,
+    pub peer_addr: Arc<PeerAddr>,
+    pub transport_read: Box<dyn TransportRead>,
     pub read_buffer: ReadBufferT,
     pub write_buffer: WriteBufferT,
+    pub pending_requests: Arc<PendingRequests>,
 }
 
 impl ConnectionReader {
     pub async fn read_chunk(&mut self) -> Result<BytesRead, AppError> {
-        loop {
-            self.tcp_stream_read.readable().await?;
-            let mut io_slices: [IoSliceMut; 2] = unsafe { self.read_buffer.current_empty_slices_as_io_slice_mut() };
-            let read_r: io::Result<usize> = self.tcp_stream_read.try_read_vectored(&mut io_slices);
+        if self.read_buffer.available_space() == 0 {
+            // A zero-length read would be indistinguishable from the stream closing
+            return Err(AppError::new("Read buffer is full"));
+        }
+
+        // Reads fill the contiguous empty space following the buffered bytes; wrapped space is filled by the next read
+        let [first, second]: [&mut [MaybeUninit<u8>]; 2] = self.read_buffer.current_empty_slices_mut();
+        let empty: &mut [MaybeUninit<u8>] = if first.is_empty() { second } else { first };
+        let mut read_buf: ReadBuf = ReadBuf::uninit(empty);
+        let transport_read: &mut Box<dyn TransportRead> = &mut self.transport_read;
+        future::poll_fn(|cx| Pin::new(&mut *transport_read).poll_read(cx, &mut read_buf)).await?;
 
-            match read_r {
-                Ok(0) => {
-                    return Ok(BytesRead::ReadClosed);
-                }
-                Ok(n) => {
-                    self.read_buffer.advance(n)?;
-                    return Ok(BytesRead::Some(n));
-                }
-                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                    // TcpStream may not be ready for read. TcpStream#readable may return false positives.
-                    continue;
-                }
-                Err(e) => return Err(AppError::from(e)),
-            }
+        let n: usize = read_buf.filled().len();
+        if n == 0 {
+            return Ok(BytesRead::ReadClosed);
         }
+        self.read_buffer.advance(n)?;
+        Ok(BytesRead::Some(n))
     }
 }
@@ -1,14 +1,16 @@
 use crate::error::{AppError, AppErrorStatic};
-use crate::network::protocol::{Frame, Head, OperationType};
+use crate::network::protocol::{Frame, Head, MAX_FRAME_SIZE, OP_CODE_SIZE, OperationType, VARIABLE_LENGTH_SIZE};
 use crate::network::ring_buffer::{RingBuffer, RingBufferView};
 
 pub trait FrameBuffer {
+    /// Pop every complete frame.
+    /// If an incomplete frame remains, the buffer is grown such that the remainder of the frame will fit.
     fn pop_frames(&mut self) -> Result<Vec<Frame>, AppErrorStatic>;
     fn peek_frame_head(&self) -> Result<Option<Head>, AppError>;
     fn pop_frame_data(&mut self, head: &Head) -> Result<Vec<u8>, AppError>;
 }
 
-impl<const N: usize> FrameBuffer for RingBuffer<u8, N> {
+impl FrameBuffer for RingBuffer<u8> {
     fn pop_frames(&mut self) -> Result<Vec<Frame>, AppErrorStatic> {
         let mut frames: Vec<Frame> = Vec::new();
         loop {
@@ -17,6 +19,7 @@ impl<const N: usize> FrameBuffer for RingBuffer<u8, N> {
                 break;
             };
             if head.length > bytes_remaining {
+                self.reserve(head.length - bytes_remaining)?;
                 break;
             }
 
@@ -28,19 +31,31 @@ impl<const N: usize> FrameBuffer for RingBuffer<u8, N> {
     }
 
     fn peek_frame_head(&self) -> Result<Option<Head>, AppError> {
-        if self.used_space() < 1 {
+        if self.used_space() < OP_CODE_SIZE {
             return Ok(None);
         }
-        let op_code_view: RingBufferView<u8> = self.peek(1)?; // Must be modified if OpCode changes size
-        let op_type: OperationType = OperationType::from_op_code(&op_code_view[0])?;
+        let op_code_view: RingBufferView<u8> = self.peek(OP_CODE_SIZE)?; // Must be modified if OpCode changes size
+        let op_type: OperationType = OperationType::from_op_code_or_unknown(&op_code_view[0]);
 
         let frame_size: usize = match op_type.fixed_size() {
             None => {
-                if self.used_space() < 3 {
+                let head_size: usize = OP_CODE_SIZE + VARIABLE_LENGTH_SIZE;
+                if self.used_space() < head_size {
                     return Ok(None);
                 }
-                let length_view: RingBufferView<u8> = self.peek(3)?;
-                u16::from_be_bytes([length_view[1], length_view[2]]) as usize
+                let head_view: RingBufferView<u8> = self.peek(head_size)?;
+                let mut length_bytes: [u8; VARIABLE_LENGTH_SIZE] = [0; VARIABLE_LENGTH_SIZE];
+                for (i, byte) in length_bytes.iter_mut().enumerate() {
+                    *byte = head_view[OP_CODE_SIZE + i];
+                }
+                let length: usize = u32::from_be_bytes(length_bytes) as usize;
+                if length < head_size || length > MAX_FRAME_SIZE {
+                    return Err(AppError::new(&format!(
+                        "Invalid frame length; [op_type: {}] [length: {}] [max: {}]",
+                        op_type, length, MAX_FRAME_SIZE
+                    )));
+                }
+                length
             }
             Some(size) => size,
         };
@@ -57,3 +72,56 @@ impl<const N: usize> FrameBuffer for RingBuffer<u8, N> {
         Ok(frame_vec)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::protocol::{_PlaceholderDynamic, Heartbeat, Operation};
+
+    #[test]
+    fn large_frame() {
+        let dynamic: _PlaceholderDynamic = _PlaceholderDynamic {
+            string: "x".repeat(300 * 1024),
+        };
+        let bytes: Vec<u8> = dynamic.as_bytes();
+        let mut read_buffer: RingBuffer<u8> = RingBuffer::with_max_capacity(4096, 2 * MAX_FRAME_SIZE);
+        read_buffer.push(&Heartbeat.as_bytes()).unwrap();
+
+        // Emulate socket reads, which only ever fill the buffer's currently available space
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut written: usize = 0;
+        while written < bytes.len() {
+            let count: usize = read_buffer.available_space().min(bytes.len() - written);
+            assert!(count > 0, "Read buffer stalled");
+            read_buffer.push(&bytes[written..(written + count)]).unwrap();
+            written += count;
+            frames.append(&mut read_buffer.pop_frames().unwrap());
+        }
+
+        assert_eq!(2, frames.len());
+        assert!(matches!(frames[0].head.op_type, OperationType::Heartbeat));
+        assert_eq!(dynamic, _PlaceholderDynamic::from_frame(&frames[1]).unwrap());
+        assert!(read_buffer.is_empty());
+    }
+
+    #[test]
+    fn reject_oversized_frame() {
+        let mut read_buffer: RingBuffer<u8> = RingBuffer::with_max_capacity(4096, 2 * MAX_FRAME_SIZE);
+        read_buffer.push(&[_PlaceholderDynamic::OP_CODE]).unwrap();
+        read_buffer.push(&((MAX_FRAME_SIZE + 1) as u32).to_be_bytes()).unwrap();
+        assert!(read_buffer.pop_frames().is_err());
+    }
+
+    #[test]
+    fn skip_unknown_op_code() {
+        let mut read_buffer: RingBuffer<u8> = RingBuffer::new(64);
+        read_buffer.push(&[u8::MAX, 0, 0, 0, 7, 1, 2]).unwrap();
+        read_buffer.push(&Heartbeat.as_bytes()).unwrap();
+
+        let frames: Vec<Frame> = read_buffer.pop_frames().unwrap();
+        assert_eq!(2, frames.len());
+        assert!(matches!(frames[0].head.op_type, OperationType::Unknown(u8::MAX)));
+        assert_eq!(7, frames[0].head.length);
+        assert!(matches!(frames[1].head.op_type, OperationType::Heartbeat));
+    }
+}
@@ -0,0 +1,146 @@
+//! Protocol version and capability negotiation.
+//! A client must open every connection with a [Hello] frame.
+//! The server answers with a [HelloAck] if the client is compatible, or a [HelloReject] followed by closing the connection.
+
+use crate::error::AppError;
+use crate::network::codec::{ByteReader, Decode, Encode};
+use crate::network::protocol::{Hello, HelloAck, HelloReject};
+use std::fmt::{self, Display};
+use std::ops::{BitAnd, BitOr};
+
+pub type ProtocolVersion = u16;
+
+/// Must be incremented whenever the wire format changes in a way that is not backwards compatible.
+pub const PROTOCOL_VERSION: ProtocolVersion = 11;
+
+/// A set of optional protocol features.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Capabilities(pub u32);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    /// The peer sends [crate::network::protocol::Heartbeat] frames
+    pub const HEARTBEAT: Capabilities = Capabilities(1 << 0);
+
+    /// Capabilities implemented by this build
+    pub const SUPPORTED: Capabilities = Capabilities(Self::HEARTBEAT.0);
+    /// Capabilities which a peer must support in order to connect.
+    /// Heartbeats are required so that silent connections can be reaped.
+    pub const REQUIRED: Capabilities = Capabilities(Self::HEARTBEAT.0);
+
+    pub const fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn missing(&self, required: Capabilities) -> Capabilities {
+        Capabilities(required.0 & !self.0)
+    }
+}
+
+impl BitAnd for Capabilities {
+    type Output = Capabilities;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Capabilities(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Capabilities(self.0 | rhs.0)
+    }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Capabilities({:#010x})", self.0)
+    }
+}
+
+impl Encode for Capabilities {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.0.encode(buffer);
+    }
+}
+
+impl Decode for Capabilities {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Capabilities(u32::decode(reader)?))
+    }
+}
+
+/// The [Hello] sent by this build.
+pub fn hello() -> Hello {
+    Hello {
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: Capabilities::SUPPORTED,
+    }
+}
+
+/// Evaluate a peer's [Hello] against this build.
+pub fn negotiate(hello: &Hello) -> Result<HelloAck, HelloReject> {
+    if hello.protocol_version != PROTOCOL_VERSION {
+        return Err(HelloReject {
+            protocol_version: PROTOCOL_VERSION,
+            reason: format!(
+                "Incompatible protocol version; [client: {}] [server: {}]",
+                hello.protocol_version, PROTOCOL_VERSION
+            ),
+        });
+    }
+
+    let missing: Capabilities = hello.capabilities.missing(Capabilities::REQUIRED);
+    if missing != Capabilities::NONE {
+        return Err(HelloReject {
+            protocol_version: PROTOCOL_VERSION,
+            reason: format!("Missing required capabilities; [{}]", missing),
+        });
+    }
+
+    Ok(HelloAck {
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: hello.capabilities & Capabilities::SUPPORTED,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_compatible() {
+        let ack: HelloAck = negotiate(&hello()).unwrap();
+        assert_eq!(PROTOCOL_VERSION, ack.protocol_version);
+        assert_eq!(Capabilities::SUPPORTED, ack.capabilities);
+
+        let ack: HelloAck = negotiate(&Hello {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Capabilities(u32::MAX),
+        })
+        .unwrap();
+        assert_eq!(Capabilities::SUPPORTED, ack.capabilities);
+    }
+
+    #[test]
+    fn reject_missing_capabilities() {
+        let reject: HelloReject = negotiate(&Hello {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Capabilities::NONE,
+        })
+        .unwrap_err();
+        assert!(reject.reason.contains("capabilities"));
+    }
+
+    #[test]
+    fn reject_version_mismatch() {
+        let reject: HelloReject = negotiate(&Hello {
+            protocol_version: PROTOCOL_VERSION + 1,
+            capabilities: Capabilities::SUPPORTED,
+        })
+        .unwrap_err();
+        assert_eq!(PROTOCOL_VERSION, reject.protocol_version);
+        assert!(reject.reason.contains("protocol version"));
+    }
+}
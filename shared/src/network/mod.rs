@@ -1,6 +1,12 @@
+pub mod backoff;
+pub mod codec;
 pub mod connection;
 pub mod frame_buffer;
+pub mod handshake;
 pub mod monitor;
 pub mod protocol;
+pub mod request;
 pub mod ring_buffer;
 pub mod socket;
+pub mod write_buffer;
+pub mod transport;
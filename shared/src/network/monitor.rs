@@ -1,52 +1,99 @@
-use crate::error::AppErrorStatic;
-use crate::network::connection::{ConnectionReader, ConnectionWriter, WriteBufferT};
+use crate::error::{AppError, AppErrorStatic};
+use crate::network::connection::{self, BytesRead, ConnectionReader, ConnectionWriter, WriteBufferT};
 use crate::network::frame_buffer::FrameBuffer;
-use crate::network::protocol::Frame;
-use crate::network::ring_buffer::RingBuffer;
+use crate::network::protocol::{Frame, Heartbeat};
 use std::time::Duration;
-use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
-use tokio::time;
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
 
+/// Peers which negotiate [crate::network::handshake::Capabilities::HEARTBEAT] send a [Heartbeat] this often.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Returned by frame callbacks to indicate whether the connection should remain open.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RouteResult {
+    Continue,
+    /// Stop reading from the connection. Frames already pushed to the write buffer are still flushed.
+    Close,
+}
+
+/// Read frames until the connection closes or a callback yields [RouteResult::Close].
+/// Responses to pending requests are delivered to their requesters rather than the callback.
 pub async fn monitor_incoming_frames<F, Fut>(mut reader: ConnectionReader, callback: F)
 where
     F: Fn(WriteBufferT, Frame) -> Fut,
-    Fut: Future<Output = ()>,
+    Fut: Future<Output = RouteResult>,
 {
-    loop {
-        let Ok(_) = reader.read_chunk().await else {
-            log::info!("Connection terminated; {:?}", reader);
-            break;
-        };
+    'read: loop {
+        match reader.read_chunk().await {
+            Ok(BytesRead::Some(_)) => {}
+            Ok(BytesRead::ReadClosed) => {
+                log::info!("Connection closed by peer; {:?}", reader);
+                break;
+            }
+            Err(e) => {
+                log::info!("Connection terminated; {:?}; {}", reader, e);
+                break;
+            }
+        }
 
         match reader.read_buffer.pop_frames() {
             Ok(frames) => {
                 for frame in frames {
-                    callback(reader.write_buffer.clone(), frame).await;
+                    let Some(frame) = reader.pending_requests.resolve(frame) else {
+                        continue;
+                    };
+                    if let RouteResult::Close = callback(reader.write_buffer.clone(), frame).await {
+                        log::info!("Closing connection; {:?}", reader);
+                        break 'read;
+                    }
                 }
             }
             Err(e) => {
-                log::error!("Failed to read from TCP stream; {:#}", e);
+                log::error!("Failed to read frames; {:#}", e);
                 break;
             }
         }
     }
+
+    reader.pending_requests.cancel_all();
 }
 
-pub async fn monitor_outgoing_frames(mut writer: ConnectionWriter) -> Result<(), AppErrorStatic> {
+/// Write frames to the network as they are pushed to the write buffer.
+/// Returns only if a frame cannot be written.
+pub async fn monitor_outgoing_frames(writer: &mut ConnectionWriter) -> Result<(), AppErrorStatic> {
     loop {
-        let buffer_g: RwLockReadGuard<RingBuffer<u8, 4096>> = writer.buffer.read().await;
-        if buffer_g.used_space() == 0 {
-            time::sleep(Duration::from_millis(50)).await;
-            continue;
+        if write_buffered_frames(writer).await? == 0 {
+            writer.buffer.pushed().await;
         }
-        drop(buffer_g);
+    }
+}
+
+/// Push a [Heartbeat] every interval, beginning one interval from now.
+/// Returns only if a heartbeat cannot be pushed to the write buffer.
+pub async fn send_heartbeats(write_buffer: WriteBufferT, interval: Duration) -> Result<(), AppErrorStatic> {
+    let mut interval: Interval = time::interval_at(Instant::now() + interval, interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        connection::send(&write_buffer, &Heartbeat).await?;
+    }
+}
 
-        let mut buffer_g: RwLockWriteGuard<RingBuffer<u8, 4096>> = writer.buffer.write().await;
-        let frames: Vec<Frame> = buffer_g.pop_frames()?;
-        drop(buffer_g);
+/// Write any frames remaining in the write buffer, e.g. before closing the connection.
+pub async fn flush_outgoing_frames(writer: &mut ConnectionWriter, timeout: Duration) -> Result<(), AppErrorStatic> {
+    match time::timeout(timeout, write_buffered_frames(writer)).await {
+        Ok(flush_r) => flush_r.map(|_| ()),
+        Err(_) => Err(AppErrorStatic::from(AppError::new(
+            "Timed out flushing outgoing frames",
+        ))),
+    }
+}
 
-        for frame in frames {
-            writer.write_frame(&frame).await?;
-        }
+/// Returns the number of frames written.
+async fn write_buffered_frames(writer: &mut ConnectionWriter) -> Result<usize, AppErrorStatic> {
+    let frames: Vec<Frame> = writer.buffer.pop_frames().await?;
+    for frame in &frames {
+        writer.write_frame(frame).await?;
     }
+    Ok(frames.len())
 }
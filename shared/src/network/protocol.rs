@@ -2,24 +2,25 @@
 //! Each frame begins with a 1-byte operation code.
 //! A frame can be fixed-length or variable-length.
 //! If fixed, the frame's data immediately follows the operation code.
-//! If variable, the frame's total length is written as a 2-byte Big-Endian unsigned integer.
+//! If variable, the frame's total length is written as a 4-byte Big-Endian unsigned integer.
+//! No frame may exceed [MAX_FRAME_SIZE] bytes.
 //! The operation code and optional length field constitute the frame's "head".
 //! The rest of the frame is considered the frame's "body".
+//! Bodies are serialized field by field with [Encode] and [Decode]; see [crate::network::codec].
+//! The body of every [Request] and [Response] begins with its [RequestId], which pairs a response with its request.
 
 use crate::error::AppError;
+use crate::map::{Map, MapGeneratorType, MapSize};
+use crate::network::codec::{ByteReader, Decode, Encode};
+use crate::network::handshake::{Capabilities, ProtocolVersion};
 use std::fmt::{self, Display};
-use std::mem;
 use uuid::Uuid;
 
-macro_rules! fixed_size_impl {
-    () => {
-        const FIXED_SIZE: ::std::option::Option<usize> = ::std::option::Option::Some(size_of::<Self>());
-
-        fn as_bytes(&self) -> ::std::vec::Vec<u8> {
-            ::std::vec::Vec::from(unsafe { mem::transmute_copy::<Self, [u8; Self::FIXED_SIZE.unwrap()]>(self) })
-        }
-    };
-}
+pub const OP_CODE_SIZE: usize = size_of::<OpCode>();
+pub const VARIABLE_LENGTH_SIZE: usize = size_of::<u32>();
+/// Upper bound on the total size of any frame, including its head.
+/// A peer declaring a larger frame is considered malformed.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct Frame {
@@ -33,6 +34,52 @@ impl Display for Frame {
     }
 }
 
+impl Frame {
+    /// Interpret a single complete frame, e.g. one nested within [Deliver].
+    pub fn from_bytes(data: Vec<u8>) -> Result<Frame, AppError> {
+        let mut reader: ByteReader = ByteReader::new(&data);
+        let op_type: OperationType = OperationType::from_op_code(&OpCode::decode(&mut reader)?)?;
+        let length: usize = match op_type.fixed_size() {
+            Some(size) => size,
+            None => u32::decode(&mut reader)? as usize,
+        };
+        if length != data.len() {
+            return Err(AppError::new(&format!(
+                "Frame length mismatch; [{}] [expected: {}] [actual: {}]",
+                op_type,
+                length,
+                data.len()
+            )));
+        }
+
+        Ok(Frame {
+            head: Head { op_type, length },
+            data,
+        })
+    }
+
+    /// The [RequestId] of a [Response] frame, or None if the frame is not a response.
+    pub fn response_request_id(&self) -> Option<RequestId> {
+        if !self.head.op_type.is_response() {
+            return None;
+        }
+        self.request_id()
+    }
+
+    /// The [RequestId] of a [Request] or [Response] frame, or None if the frame is neither.
+    pub fn request_id(&self) -> Option<RequestId> {
+        if !self.head.op_type.is_request() && !self.head.op_type.is_response() {
+            return None;
+        }
+        let head_size: usize = match self.head.op_type.fixed_size() {
+            Some(_) => OP_CODE_SIZE,
+            None => OP_CODE_SIZE + VARIABLE_LENGTH_SIZE,
+        };
+        let mut reader: ByteReader = ByteReader::new(self.data.get(head_size..)?);
+        RequestId::decode(&mut reader).ok()
+    }
+}
+
 #[derive(Debug)]
 pub struct Head {
     pub op_type: OperationType,
@@ -47,153 +94,1547 @@ impl Display for Head {
 
 pub type OpCode = u8;
 
-#[derive(Debug)]
-pub enum OperationType {
+/// Identifies a [Request] so that its [Response] can be matched to it.
+/// Zero is never assigned to a request.
+pub type RequestId = u32;
+
+/// Orders the frames delivered to a session; see [Deliver].
+/// The first frame delivered to a session has sequence 1.
+pub type Sequence = u64;
+
+/// Declares [OperationType] and its lookups from a single list of operations.
+/// Each operation is listed once, marked `: Request` or `: Response` if it implements that trait, which is checked at
+/// compile time. Adding an operation means defining its type below and adding it to the list.
+macro_rules! operation_types {
+    ($($name:ident $(: $kind:ident)?),* $(,)?) => {
+        #[derive(Debug)]
+        pub enum OperationType {
+            $($name,)*
+            /// An op code unknown to this build; such frames are assumed to be variable length so that they can be
+            /// skipped
+            Unknown(OpCode),
+        }
+
+        impl Display for OperationType {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let string: &'static str = match self {
+                    $(OperationType::$name => stringify!($name),)*
+                    OperationType::Unknown(op_code) => return write!(f, "OperationType(Unknown: {})", op_code),
+                };
+                write!(f, "OperationType({})", string)
+            }
+        }
+
+        impl OperationType {
+            pub fn from_op_code(op_code: &OpCode) -> Result<Self, AppError> {
+                match *op_code {
+                    $($name::OP_CODE => Ok(OperationType::$name),)*
+                    _ => Err(AppError::new(&format!("Invalid op code; [{}]", op_code))),
+                }
+            }
+
+            pub const fn op_code(&self) -> OpCode {
+                match self {
+                    $(OperationType::$name => $name::OP_CODE,)*
+                    OperationType::Unknown(op_code) => *op_code,
+                }
+            }
+
+            pub const fn fixed_size(&self) -> Option<usize> {
+                match self {
+                    $(OperationType::$name => $name::FIXED_SIZE,)*
+                    OperationType::Unknown(_) => None,
+                }
+            }
+
+            /// Whether frames of this type are a [Request].
+            pub const fn is_request(&self) -> bool {
+                match self {
+                    $(OperationType::$name => operation_types!(@is Request $($kind)?),)*
+                    OperationType::Unknown(_) => false,
+                }
+            }
+
+            /// Whether frames of this type are a [Response] to some [Request].
+            pub const fn is_response(&self) -> bool {
+                match self {
+                    $(OperationType::$name => operation_types!(@is Response $($kind)?),)*
+                    OperationType::Unknown(_) => false,
+                }
+            }
+        }
+
+        $($(const _: () = operation_types!(@check $kind $name);)?)*
+    };
+    (@is Request Request) => { true };
+    (@is Response Response) => { true };
+    (@is $wanted:ident $($kind:ident)?) => { false };
+    (@check $kind:ident $name:ident) => {{
+        const fn implements<T: $kind>() {}
+        implements::<$name>()
+    }};
+}
+
+operation_types! {
     Heartbeat,
-    Register,
-    Acknowledgement,
+    Register: Request,
+    Acknowledgement: Response,
     _PlaceholderDynamic,
+    Hello,
+    HelloAck,
+    HelloReject,
+    SessionGranted: Response,
+    Resume: Request,
+    Resumed: Response,
+    ResumeReject: Response,
+    Deliver,
+    ProtocolError,
+    Error: Response,
+    ListGames: Request,
+    GameList: Response,
+    CreateGame: Request,
+    JoinGame: Request,
+    GameJoined: Response,
+    LeaveGame: Request,
+    CreateAccount: Request,
+    Login: Request,
+    LoggedIn: Response,
+    GetMap: Request,
+    GameMap: Response,
+    GameUpdated,
+}
+
+impl OperationType {
+    /// As [OperationType::from_op_code], but yields [OperationType::Unknown] rather than failing.
+    pub fn from_op_code_or_unknown(op_code: &OpCode) -> Self {
+        OperationType::from_op_code(op_code).unwrap_or(OperationType::Unknown(*op_code))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Heartbeat;
+
+impl Encode for Heartbeat {
+    fn encode(&self, _buffer: &mut Vec<u8>) {}
+}
+
+impl Decode for Heartbeat {
+    fn decode(_reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Heartbeat)
+    }
+}
+
+impl Operation for Heartbeat {
+    const OP_CODE: OpCode = 1;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE);
+}
+
+/// Opens a new session for the account which was issued the token. Answered with [SessionGranted].
+/// An unknown token is answered with an [Error]; the client should log in again.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Register {
+    pub request_id: RequestId,
+    pub account_token: Uuid,
+}
+
+impl Encode for Register {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.account_token.encode(buffer);
+    }
+}
+
+impl Decode for Register {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Register {
+            request_id: RequestId::decode(reader)?,
+            account_token: Uuid::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Register {
+    const OP_CODE: OpCode = 2;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<Uuid>());
+}
+
+impl Request for Register {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Acknowledgement {
+    pub request_id: RequestId,
+    pub op_code_acknowledged: OpCode,
+}
+
+impl Encode for Acknowledgement {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.op_code_acknowledged.encode(buffer);
+    }
+}
+
+impl Decode for Acknowledgement {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Acknowledgement {
+            request_id: RequestId::decode(reader)?,
+            op_code_acknowledged: OpCode::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Acknowledgement {
+    const OP_CODE: OpCode = 3;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<OpCode>());
+}
+
+impl Response for Acknowledgement {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct _PlaceholderDynamic {
+    pub string: String,
+}
+
+impl Encode for _PlaceholderDynamic {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.string.encode(buffer);
+    }
+}
+
+impl Decode for _PlaceholderDynamic {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(_PlaceholderDynamic {
+            string: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for _PlaceholderDynamic {
+    const OP_CODE: OpCode = 4;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+/// The first frame sent by a client on a new connection.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hello {
+    pub protocol_version: ProtocolVersion,
+    pub capabilities: Capabilities,
+}
+
+impl Encode for Hello {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.protocol_version.encode(buffer);
+        self.capabilities.encode(buffer);
+    }
+}
+
+impl Decode for Hello {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Hello {
+            protocol_version: ProtocolVersion::decode(reader)?,
+            capabilities: Capabilities::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Hello {
+    const OP_CODE: OpCode = 5;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<ProtocolVersion>() + size_of::<Capabilities>());
+}
+
+/// Sent by the server to accept a [Hello].
+/// The capabilities are those supported by both peers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HelloAck {
+    pub protocol_version: ProtocolVersion,
+    pub capabilities: Capabilities,
+}
+
+impl Encode for HelloAck {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.protocol_version.encode(buffer);
+        self.capabilities.encode(buffer);
+    }
+}
+
+impl Decode for HelloAck {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(HelloAck {
+            protocol_version: ProtocolVersion::decode(reader)?,
+            capabilities: Capabilities::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for HelloAck {
+    const OP_CODE: OpCode = 6;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<ProtocolVersion>() + size_of::<Capabilities>());
+}
+
+/// Sent by the server to refuse a connection. The server closes the connection after sending this frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelloReject {
+    /// The server's protocol version
+    pub protocol_version: ProtocolVersion,
+    pub reason: String,
+}
+
+impl Encode for HelloReject {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.protocol_version.encode(buffer);
+        self.reason.encode(buffer);
+    }
+}
+
+impl Decode for HelloReject {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(HelloReject {
+            protocol_version: ProtocolVersion::decode(reader)?,
+            reason: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for HelloReject {
+    const OP_CODE: OpCode = 7;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+/// Sent by the server in response to [Register].
+/// The token identifies the session when the client reconnects; see [Resume].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionGranted {
+    pub request_id: RequestId,
+    pub session_token: Uuid,
+    /// Unfinished games in which the user is a player, e.g. those joined before the server restarted
+    pub games: Vec<Uuid>,
+}
+
+impl Encode for SessionGranted {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.session_token.encode(buffer);
+        self.games.encode(buffer);
+    }
+}
+
+impl Decode for SessionGranted {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(SessionGranted {
+            request_id: RequestId::decode(reader)?,
+            session_token: Uuid::decode(reader)?,
+            games: Vec::<Uuid>::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for SessionGranted {
+    const OP_CODE: OpCode = 8;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for SessionGranted {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Sent by a client after reconnecting, in place of [Register].
+/// Answered with [Resumed] or [ResumeReject].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Resume {
+    pub request_id: RequestId,
+    pub session_token: Uuid,
+    /// The last [Deliver] sequence received by the client. Every later frame is replayed.
+    pub last_sequence: Sequence,
+}
+
+impl Encode for Resume {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.session_token.encode(buffer);
+        self.last_sequence.encode(buffer);
+    }
+}
+
+impl Decode for Resume {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Resume {
+            request_id: RequestId::decode(reader)?,
+            session_token: Uuid::decode(reader)?,
+            last_sequence: Sequence::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Resume {
+    const OP_CODE: OpCode = 9;
+    const FIXED_SIZE: Option<usize> =
+        Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<Uuid>() + size_of::<Sequence>());
+}
+
+impl Request for Resume {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Sent by the server to accept a [Resume]. The missed frames follow as [Deliver] frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resumed {
+    pub request_id: RequestId,
+    /// Games in which the session's user is a player
+    pub games: Vec<Uuid>,
+    /// Number of [Deliver] frames which will be replayed
+    pub replayed: u32,
+    /// Some missed frames were discarded before the client reconnected.
+    /// The client must request fresh state for its games.
+    pub incomplete: bool,
+}
+
+impl Encode for Resumed {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.games.encode(buffer);
+        self.replayed.encode(buffer);
+        self.incomplete.encode(buffer);
+    }
+}
+
+impl Decode for Resumed {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Resumed {
+            request_id: RequestId::decode(reader)?,
+            games: Vec::<Uuid>::decode(reader)?,
+            replayed: u32::decode(reader)?,
+            incomplete: bool::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Resumed {
+    const OP_CODE: OpCode = 10;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for Resumed {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Sent by the server when a session cannot be resumed, e.g. because it has expired.
+/// The client should [Register] again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumeReject {
+    pub request_id: RequestId,
+    pub reason: String,
+}
+
+impl Encode for ResumeReject {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.reason.encode(buffer);
+    }
+}
+
+impl Decode for ResumeReject {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(ResumeReject {
+            request_id: RequestId::decode(reader)?,
+            reason: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for ResumeReject {
+    const OP_CODE: OpCode = 11;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for ResumeReject {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Envelope for a frame pushed by the server to a session.
+/// Delivered frames are retained by the server so that they can be replayed after the client reconnects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deliver {
+    pub sequence: Sequence,
+    /// A complete frame, including its head
+    pub frame: Vec<u8>,
+}
+
+impl Encode for Deliver {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.sequence.encode(buffer);
+        self.frame.encode(buffer);
+    }
+}
+
+impl Decode for Deliver {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Deliver {
+            sequence: Sequence::decode(reader)?,
+            frame: Vec::<u8>::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Deliver {
+    const OP_CODE: OpCode = 12;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+/// Sent in reply to a frame which the receiver does not recognize or does not accept.
+/// The connection remains open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolError {
+    /// The op code of the offending frame
+    pub op_code: OpCode,
+    pub reason: String,
+}
+
+impl Encode for ProtocolError {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.op_code.encode(buffer);
+        self.reason.encode(buffer);
+    }
+}
+
+impl Decode for ProtocolError {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(ProtocolError {
+            op_code: OpCode::decode(reader)?,
+            reason: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for ProtocolError {
+    const OP_CODE: OpCode = 13;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+/// Broad category of an [Error], which the receiver may act upon without parsing the message.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The receiver failed to handle an otherwise valid frame
+    Internal = 1,
+    /// The frame could not be decoded
+    Malformed = 2,
+    /// The request was understood but refused, e.g. joining a full game
+    Rejected = 3,
+    /// The account token is unknown; the client should log in again
+    Unauthorized = 4,
+}
+
+impl ErrorCode {
+    pub fn from_u16(value: u16) -> Result<Self, AppError> {
+        match value {
+            1 => Ok(ErrorCode::Internal),
+            2 => Ok(ErrorCode::Malformed),
+            3 => Ok(ErrorCode::Rejected),
+            4 => Ok(ErrorCode::Unauthorized),
+            _ => Err(AppError::new(&format!("Invalid error code; [{}]", value))),
+        }
+    }
 }
 
-impl Display for OperationType {
+impl Display for ErrorCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let string: &'static str = match self {
-            OperationType::Heartbeat => "Heartbeat",
-            OperationType::Register => "Register",
-            OperationType::Acknowledgement => "Acknowledgement",
-            OperationType::_PlaceholderDynamic => "_PlaceholderDynamic",
+            ErrorCode::Internal => "Internal error",
+            ErrorCode::Malformed => "Malformed request",
+            ErrorCode::Rejected => "Request rejected",
+            ErrorCode::Unauthorized => "Not logged in",
         };
-        write!(f, "OperationType({})", string)
+        write!(f, "{}", string)
     }
 }
 
-impl OperationType {
-    pub fn from_op_code(op_code: &OpCode) -> Result<Self, AppError> {
-        match op_code {
-            &Heartbeat::OP_CODE => Ok(OperationType::Heartbeat),
-            &Register::OP_CODE => Ok(OperationType::Register),
-            &Acknowledgement::OP_CODE => Ok(OperationType::Acknowledgement),
-            &_PlaceholderDynamic::OP_CODE => Ok(OperationType::_PlaceholderDynamic),
-            _ => Err(AppError::new(&format!("Invalid op code; [{}]", op_code))),
+impl Encode for ErrorCode {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        (*self as u16).encode(buffer);
+    }
+}
+
+impl Decode for ErrorCode {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        ErrorCode::from_u16(u16::decode(reader)?)
+    }
+}
+
+/// Tells the peer that handling one of its frames failed.
+/// If the frame was a [Request], this is its [Response]; otherwise the request ID is zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub request_id: RequestId,
+    pub code: ErrorCode,
+    /// Suitable for display to the user
+    pub message: String,
+}
+
+impl Error {
+    /// Describe the error to the peer, omitting any details which are only meaningful locally, e.g. the backtrace.
+    pub fn from_app_error(request_id: RequestId, code: ErrorCode, error: &AppError) -> Self {
+        Error {
+            request_id,
+            code,
+            message: String::from(error.summary()),
         }
     }
+}
+
+impl Encode for Error {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.code.encode(buffer);
+        self.message.encode(buffer);
+    }
+}
+
+impl Decode for Error {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Error {
+            request_id: RequestId::decode(reader)?,
+            code: ErrorCode::decode(reader)?,
+            message: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Error {
+    const OP_CODE: OpCode = 14;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for Error {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Lifecycle of a game, as listed in the lobby
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameStatus {
+    /// Waiting for players to join
+    Open = 1,
+    InProgress = 2,
+    Finished = 3,
+}
 
-    pub const fn fixed_size(&self) -> Option<usize> {
-        match self {
-            OperationType::Heartbeat => Heartbeat::FIXED_SIZE,
-            OperationType::Register => Register::FIXED_SIZE,
-            OperationType::Acknowledgement => Acknowledgement::FIXED_SIZE,
-            OperationType::_PlaceholderDynamic => _PlaceholderDynamic::FIXED_SIZE,
+impl GameStatus {
+    pub fn from_u8(value: u8) -> Result<Self, AppError> {
+        match value {
+            1 => Ok(GameStatus::Open),
+            2 => Ok(GameStatus::InProgress),
+            3 => Ok(GameStatus::Finished),
+            _ => Err(AppError::new(&format!("Invalid game status; [{}]", value))),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-#[repr(C, packed(1))]
-pub struct Heartbeat {
-    pub op_code: OpCode,
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string: &'static str = match self {
+            GameStatus::Open => "Open",
+            GameStatus::InProgress => "In progress",
+            GameStatus::Finished => "Finished",
+        };
+        write!(f, "{}", string)
+    }
 }
 
-impl<'a> From<&'a Frame> for Heartbeat {
-    fn from(frame: &'a Frame) -> Self {
-        unsafe { *(frame.data.as_ptr() as *const Heartbeat) }
+impl Encode for GameStatus {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        (*self as u8).encode(buffer);
     }
 }
 
-impl<'a> Operation for Heartbeat {
-    const OP_CODE: OpCode = 1;
+impl Decode for GameStatus {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        GameStatus::from_u8(u8::decode(reader)?)
+    }
+}
 
-    fixed_size_impl!();
+/// Chosen by the player who creates a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSettings {
+    pub name: String,
+    pub max_players: u8,
+    pub map_generator: MapGeneratorType,
+    pub map_size: MapSize,
 }
 
-#[derive(Debug, Copy, Clone)]
-#[repr(C, packed(1))]
-pub struct Register {
-    pub op_code: OpCode,
+impl Encode for GameSettings {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.name.encode(buffer);
+        self.max_players.encode(buffer);
+        self.map_generator.encode(buffer);
+        self.map_size.encode(buffer);
+    }
+}
+
+impl Decode for GameSettings {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameSettings {
+            name: String::decode(reader)?,
+            max_players: u8::decode(reader)?,
+            map_generator: MapGeneratorType::decode(reader)?,
+            map_size: MapSize::decode(reader)?,
+        })
+    }
+}
+
+/// A snapshot of a game's lobby state
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSummary {
+    pub id: Uuid,
+    pub settings: GameSettings,
+    /// User IDs, in order of joining
+    pub players: Vec<Uuid>,
+    pub status: GameStatus,
+}
+
+impl GameSummary {
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= usize::from(self.settings.max_players)
+    }
+}
+
+impl Encode for GameSummary {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.id.encode(buffer);
+        self.settings.encode(buffer);
+        self.players.encode(buffer);
+        self.status.encode(buffer);
+    }
+}
+
+impl Decode for GameSummary {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameSummary {
+            id: Uuid::decode(reader)?,
+            settings: GameSettings::decode(reader)?,
+            players: Vec::<Uuid>::decode(reader)?,
+            status: GameStatus::decode(reader)?,
+        })
+    }
+}
+
+/// Asks the server for the games which can be joined, along with the unfinished games already joined.
+/// Answered with [GameList].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ListGames {
+    pub request_id: RequestId,
+}
+
+impl Encode for ListGames {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+    }
+}
+
+impl Decode for ListGames {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(ListGames {
+            request_id: RequestId::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for ListGames {
+    const OP_CODE: OpCode = 15;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>());
+}
+
+impl Request for ListGames {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameList {
+    pub request_id: RequestId,
+    pub games: Vec<GameSummary>,
+}
+
+impl Encode for GameList {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.games.encode(buffer);
+    }
+}
+
+impl Decode for GameList {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameList {
+            request_id: RequestId::decode(reader)?,
+            games: Vec::<GameSummary>::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for GameList {
+    const OP_CODE: OpCode = 16;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for GameList {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Creates a game and joins it. Answered with [GameJoined].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateGame {
+    pub request_id: RequestId,
+    pub settings: GameSettings,
+}
+
+impl Encode for CreateGame {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.settings.encode(buffer);
+    }
+}
+
+impl Decode for CreateGame {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(CreateGame {
+            request_id: RequestId::decode(reader)?,
+            settings: GameSettings::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for CreateGame {
+    const OP_CODE: OpCode = 17;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Request for CreateGame {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Answered with [GameJoined]. Joining a game again enters it without changing it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JoinGame {
+    pub request_id: RequestId,
+    pub game_id: Uuid,
+}
+
+impl Encode for JoinGame {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.game_id.encode(buffer);
+    }
+}
+
+impl Decode for JoinGame {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(JoinGame {
+            request_id: RequestId::decode(reader)?,
+            game_id: Uuid::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for JoinGame {
+    const OP_CODE: OpCode = 18;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<Uuid>());
+}
+
+impl Request for JoinGame {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Sent by the server once the session's user is a player in the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameJoined {
+    pub request_id: RequestId,
+    pub game: GameSummary,
+}
+
+impl Encode for GameJoined {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.game.encode(buffer);
+    }
+}
+
+impl Decode for GameJoined {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameJoined {
+            request_id: RequestId::decode(reader)?,
+            game: GameSummary::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for GameJoined {
+    const OP_CODE: OpCode = 19;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Response for GameJoined {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+/// Answered with [Acknowledgement].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LeaveGame {
+    pub request_id: RequestId,
+    pub game_id: Uuid,
+}
+
+impl Encode for LeaveGame {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.game_id.encode(buffer);
+    }
+}
+
+impl Decode for LeaveGame {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(LeaveGame {
+            request_id: RequestId::decode(reader)?,
+            game_id: Uuid::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for LeaveGame {
+    const OP_CODE: OpCode = 20;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<Uuid>());
+}
+
+impl Request for LeaveGame {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Creates an account and logs in to it. Answered with [LoggedIn].
+/// The server stores only a hash of the password.
+#[derive(Clone, PartialEq)]
+pub struct CreateAccount {
+    pub request_id: RequestId,
+    pub username: String,
+    pub password: String,
+}
+
+/// Omits the password, which would otherwise be logged with the parsed frame
+impl fmt::Debug for CreateAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateAccount")
+            .field("request_id", &self.request_id)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Encode for CreateAccount {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.username.encode(buffer);
+        self.password.encode(buffer);
+    }
+}
+
+impl Decode for CreateAccount {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(CreateAccount {
+            request_id: RequestId::decode(reader)?,
+            username: String::decode(reader)?,
+            password: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for CreateAccount {
+    const OP_CODE: OpCode = 21;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Request for CreateAccount {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Answered with [LoggedIn].
+#[derive(Clone, PartialEq)]
+pub struct Login {
+    pub request_id: RequestId,
+    pub username: String,
+    pub password: String,
+}
+
+/// Omits the password, which would otherwise be logged with the parsed frame
+impl fmt::Debug for Login {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Login")
+            .field("request_id", &self.request_id)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Encode for Login {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.username.encode(buffer);
+        self.password.encode(buffer);
+    }
+}
+
+impl Decode for Login {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(Login {
+            request_id: RequestId::decode(reader)?,
+            username: String::decode(reader)?,
+            password: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for Login {
+    const OP_CODE: OpCode = 22;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+impl Request for Login {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
+}
+
+/// Sent by the server in response to [CreateAccount] or [Login].
+/// The account token is kept by the client across launches and presented in [Register].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedIn {
+    pub request_id: RequestId,
     pub user_id: Uuid,
+    pub account_token: Uuid,
+    pub username: String,
 }
 
-impl<'a> From<&'a Frame> for Register {
-    fn from(frame: &'a Frame) -> Self {
-        unsafe { *(frame.data.as_ptr() as *const Register) }
+impl Encode for LoggedIn {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.user_id.encode(buffer);
+        self.account_token.encode(buffer);
+        self.username.encode(buffer);
     }
 }
 
-impl<'a> Operation for Register {
-    const OP_CODE: OpCode = 2;
+impl Decode for LoggedIn {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(LoggedIn {
+            request_id: RequestId::decode(reader)?,
+            user_id: Uuid::decode(reader)?,
+            account_token: Uuid::decode(reader)?,
+            username: String::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for LoggedIn {
+    const OP_CODE: OpCode = 23;
+    const FIXED_SIZE: Option<usize> = None;
+}
 
-    fixed_size_impl!();
+impl Response for LoggedIn {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-#[repr(C, packed(1))]
-pub struct Acknowledgement {
-    pub op_code: OpCode,
-    pub op_code_acknowledged: OpCode,
+/// Answered with [GameMap]. Only the game's players may request its map.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GetMap {
+    pub request_id: RequestId,
+    pub game_id: Uuid,
 }
 
-impl<'a> From<&'a Frame> for Acknowledgement {
-    fn from(frame: &'a Frame) -> Self {
-        unsafe { *(frame.data.as_ptr() as *const Acknowledgement) }
+impl Encode for GetMap {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.game_id.encode(buffer);
     }
 }
 
-impl<'a> Operation for Acknowledgement {
-    const OP_CODE: OpCode = 3;
+impl Decode for GetMap {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GetMap {
+            request_id: RequestId::decode(reader)?,
+            game_id: Uuid::decode(reader)?,
+        })
+    }
+}
 
-    fixed_size_impl!();
+impl Operation for GetMap {
+    const OP_CODE: OpCode = 24;
+    const FIXED_SIZE: Option<usize> = Some(OP_CODE_SIZE + size_of::<RequestId>() + size_of::<Uuid>());
 }
 
-// Dynamically-sized frames cannot be directly interpreted from bits, since their size is not statically known
-#[derive(Debug)]
-#[repr(C, packed(1))]
-pub struct _PlaceholderDynamic<'a> {
-    pub op_code: OpCode,
-    pub length: u16,
-    pub string: &'a str,
+impl Request for GetMap {
+    fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = request_id;
+    }
 }
 
-impl<'a> From<&'a Frame> for _PlaceholderDynamic<'a> {
-    fn from(frame: &'a Frame) -> Self {
-        _PlaceholderDynamic {
-            op_code: frame.data[0],
-            length: u16::from_be_bytes(frame.data[1..3].try_into().unwrap()),
-            string: str::from_utf8(&frame.data[3..]).unwrap(),
-        }
+/// The map generated by the server for a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMap {
+    pub request_id: RequestId,
+    pub game_id: Uuid,
+    pub map: Map,
+}
+
+impl Encode for GameMap {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.request_id.encode(buffer);
+        self.game_id.encode(buffer);
+        self.map.encode(buffer);
     }
 }
 
-impl<'a> Operation for _PlaceholderDynamic<'a> {
-    const OP_CODE: OpCode = 4;
+impl Decode for GameMap {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameMap {
+            request_id: RequestId::decode(reader)?,
+            game_id: Uuid::decode(reader)?,
+            map: Map::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for GameMap {
+    const OP_CODE: OpCode = 25;
     const FIXED_SIZE: Option<usize> = None;
+}
 
-    fn as_bytes(&self) -> Vec<u8> {
-        let mut bytes: Vec<u8> = Vec::with_capacity(usize::from(self.length));
-        bytes.push(self.op_code);
-        bytes.extend_from_slice(&self.length.to_be_bytes());
-        bytes.extend_from_slice(self.string.as_bytes());
-        bytes
+impl Response for GameMap {
+    fn request_id(&self) -> RequestId {
+        self.request_id
     }
 }
 
-pub trait Operation {
+/// Pushed to the players of a game, and to a player who has just left it, whenever its lobby state changes.
+/// Always wrapped in a [Deliver], so that it is replayed to a client which was disconnected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameUpdated {
+    pub game: GameSummary,
+}
+
+impl Encode for GameUpdated {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.game.encode(buffer);
+    }
+}
+
+impl Decode for GameUpdated {
+    fn decode(reader: &mut ByteReader) -> Result<Self, AppError> {
+        Ok(GameUpdated {
+            game: GameSummary::decode(reader)?,
+        })
+    }
+}
+
+impl Operation for GameUpdated {
+    const OP_CODE: OpCode = 26;
+    const FIXED_SIZE: Option<usize> = None;
+}
+
+pub trait Operation: Encode + Decode {
     const OP_CODE: OpCode;
+    /// Total size of the frame, including its head.
     /// None iff not fixed size
     const FIXED_SIZE: Option<usize>;
 
-    fn as_bytes(&self) -> Vec<u8>;
+    /// Serialize the operation as a complete frame, including its head.
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(Self::FIXED_SIZE.unwrap_or(OP_CODE_SIZE + VARIABLE_LENGTH_SIZE));
+        Self::OP_CODE.encode(&mut bytes);
+        if Self::FIXED_SIZE.is_none() {
+            0_u32.encode(&mut bytes); // Overwritten once the body's length is known
+        }
+
+        self.encode(&mut bytes);
+
+        match Self::FIXED_SIZE {
+            Some(size) => assert_eq!(size, bytes.len(), "Encoded size does not match FIXED_SIZE"),
+            None => {
+                let length: u32 = u32::try_from(bytes.len()).expect("Frame length exceeds u32::MAX");
+                bytes[OP_CODE_SIZE..(OP_CODE_SIZE + VARIABLE_LENGTH_SIZE)].copy_from_slice(&length.to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Deserialize the operation from a complete frame, validating its head and length.
+    fn from_frame(frame: &Frame) -> Result<Self, AppError> {
+        let mut reader: ByteReader = ByteReader::new(&frame.data);
+
+        let op_code: OpCode = OpCode::decode(&mut reader)?;
+        if op_code != Self::OP_CODE {
+            return Err(AppError::new(&format!(
+                "Unexpected op code; [expected: {}] [actual: {}]",
+                Self::OP_CODE,
+                op_code
+            )));
+        }
+
+        let declared_length: usize = match Self::FIXED_SIZE {
+            Some(size) => size,
+            None => u32::decode(&mut reader)? as usize,
+        };
+        if declared_length != frame.data.len() {
+            return Err(AppError::new(&format!(
+                "Frame length mismatch; [expected: {}] [actual: {}]",
+                declared_length,
+                frame.data.len()
+            )));
+        }
+
+        let operation: Self = Self::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(operation)
+    }
+}
+
+/// An operation which expects a [Response].
+pub trait Request: Operation {
+    fn request_id(&self) -> RequestId;
+    fn set_request_id(&mut self, request_id: RequestId);
+}
+
+/// An operation sent in reply to a [Request].
+/// Its body must begin with the [RequestId] of the request; see [Frame::response_request_id].
+pub trait Response: Operation {
+    fn request_id(&self) -> RequestId;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    /// We want to be extra careful about accidentally changing the sizes of these structs
+    fn frame_of<T: Operation>(operation: &T) -> Frame {
+        let data: Vec<u8> = operation.as_bytes();
+        Frame {
+            head: Head {
+                op_type: OperationType::from_op_code(&T::OP_CODE).unwrap(),
+                length: data.len(),
+            },
+            data,
+        }
+    }
+
+    /// We want to be extra careful about accidentally changing the sizes of these frames
     #[test]
     fn size_snapshots() {
         assert_eq!(1, size_of::<OpCode>());
-        assert_eq!(1, size_of::<Heartbeat>());
-        assert_eq!(17, size_of::<Register>());
-        assert_eq!(2, size_of::<Acknowledgement>());
+        assert_eq!(Some(1), Heartbeat::FIXED_SIZE);
+        assert_eq!(Some(21), Register::FIXED_SIZE);
+        assert_eq!(Some(6), Acknowledgement::FIXED_SIZE);
+
+        assert_eq!(1, Heartbeat.as_bytes().len());
+        assert_eq!(
+            21,
+            Register {
+                request_id: 1,
+                account_token: Uuid::nil()
+            }
+            .as_bytes()
+            .len()
+        );
+        assert_eq!(
+            6,
+            Acknowledgement {
+                request_id: 1,
+                op_code_acknowledged: 2
+            }
+            .as_bytes()
+            .len()
+        );
+    }
+
+    #[test]
+    fn register_wire_format() {
+        let account_token: Uuid = Uuid::from_u128(0x0001_0203_0405_0607_0809_0a0b_0c0d_0e0f);
+        let bytes: Vec<u8> = Register {
+            request_id: 0x0a0b_0c0d,
+            account_token,
+        }
+        .as_bytes();
+        assert_eq!(Register::OP_CODE, bytes[0]);
+        assert_eq!(&[0x0a, 0x0b, 0x0c, 0x0d], &bytes[1..5]);
+        assert_eq!(&(0..16).collect::<Vec<u8>>(), &bytes[5..]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let register: Register = Register {
+            request_id: 7,
+            account_token: Uuid::from_u128(42),
+        };
+        assert_eq!(register, Register::from_frame(&frame_of(&register)).unwrap());
+
+        let session_granted: SessionGranted = SessionGranted {
+            request_id: 7,
+            session_token: Uuid::from_u128(43),
+            games: vec![Uuid::from_u128(44), Uuid::from_u128(45)],
+        };
+        assert_eq!(
+            session_granted,
+            SessionGranted::from_frame(&frame_of(&session_granted)).unwrap()
+        );
+
+        let acknowledgement: Acknowledgement = Acknowledgement {
+            request_id: 7,
+            op_code_acknowledged: Register::OP_CODE,
+        };
+        assert_eq!(
+            acknowledgement,
+            Acknowledgement::from_frame(&frame_of(&acknowledgement)).unwrap()
+        );
+
+        let dynamic: _PlaceholderDynamic = _PlaceholderDynamic {
+            string: String::from("hello world"),
+        };
+        let frame: Frame = frame_of(&dynamic);
+        assert_eq!(
+            u32::from_be_bytes([frame.data[1], frame.data[2], frame.data[3], frame.data[4]]) as usize,
+            frame.data.len()
+        );
+        assert_eq!(dynamic, _PlaceholderDynamic::from_frame(&frame).unwrap());
+    }
+
+    #[test]
+    fn reject_short_frame() {
+        let mut frame: Frame = frame_of(&Register {
+            request_id: 7,
+            account_token: Uuid::from_u128(42),
+        });
+        frame.data.truncate(10);
+        assert!(Register::from_frame(&frame).is_err());
+    }
+
+    #[test]
+    fn reject_mismatched_frame() {
+        let frame: Frame = frame_of(&Heartbeat);
+        assert!(Register::from_frame(&frame).is_err());
+
+        let mut frame: Frame = frame_of(&_PlaceholderDynamic {
+            string: String::from("hello"),
+        });
+        frame.data.push(0);
+        assert!(_PlaceholderDynamic::from_frame(&frame).is_err());
+    }
+
+    #[test]
+    fn response_request_id() {
+        let frame: Frame = frame_of(&Acknowledgement {
+            request_id: 0x0102_0304,
+            op_code_acknowledged: Register::OP_CODE,
+        });
+        assert_eq!(Some(0x0102_0304), frame.response_request_id());
+
+        let frame: Frame = frame_of(&Register {
+            request_id: 7,
+            account_token: Uuid::from_u128(42),
+        });
+        assert_eq!(None, frame.response_request_id());
+        assert_eq!(Some(7), frame.request_id());
+    }
+
+    #[test]
+    fn error_from_app_error() {
+        let error: Error = Error::from_app_error(3, ErrorCode::Malformed, &AppError::new("Bad input"));
+        assert_eq!("Bad input", error.message);
+
+        let frame: Frame = frame_of(&error);
+        assert_eq!(Some(3), frame.response_request_id());
+        assert_eq!(error, Error::from_frame(&frame).unwrap());
+
+        let mut bytes: Vec<u8> = error.as_bytes();
+        let code_start: usize = OP_CODE_SIZE + VARIABLE_LENGTH_SIZE + size_of::<RequestId>();
+        bytes[code_start..(code_start + 2)].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert!(Error::from_frame(&Frame::from_bytes(bytes).unwrap()).is_err());
+    }
+
+    #[test]
+    fn lobby_round_trip() {
+        let game: GameSummary = GameSummary {
+            id: Uuid::from_u128(1),
+            settings: GameSettings {
+                name: String::from("Lobby"),
+                max_players: 4,
+                map_generator: MapGeneratorType::ParticleRepulsion,
+                map_size: MapSize { width: 96, height: 64 },
+            },
+            players: vec![Uuid::from_u128(2), Uuid::from_u128(3)],
+            status: GameStatus::Open,
+        };
+        let game_list: GameList = GameList {
+            request_id: 5,
+            games: vec![game.clone()],
+        };
+        let frame: Frame = frame_of(&game_list);
+        assert_eq!(Some(5), frame.response_request_id());
+        assert_eq!(game_list, GameList::from_frame(&frame).unwrap());
+
+        let create_game: CreateGame = CreateGame {
+            request_id: 6,
+            settings: game.settings.clone(),
+        };
+        let frame: Frame = frame_of(&create_game);
+        assert_eq!(Some(6), frame.request_id());
+        assert_eq!(create_game, CreateGame::from_frame(&frame).unwrap());
+
+        let game_updated: GameUpdated = GameUpdated { game: game.clone() };
+        let frame: Frame = frame_of(&game_updated);
+        assert_eq!(None, frame.response_request_id());
+        assert_eq!(game_updated, GameUpdated::from_frame(&frame).unwrap());
+
+        let mut bytes: Vec<u8> = GameJoined { request_id: 7, game }.as_bytes();
+        *bytes.last_mut().unwrap() = 0; // The status is the last field
+        assert!(GameJoined::from_frame(&Frame::from_bytes(bytes).unwrap()).is_err());
+    }
+
+    #[test]
+    fn account_round_trip() {
+        let login: Login = Login {
+            request_id: 8,
+            username: String::from("player"),
+            password: String::from("correct horse"),
+        };
+        let frame: Frame = frame_of(&login);
+        assert_eq!(Some(8), frame.request_id());
+        assert_eq!(login, Login::from_frame(&frame).unwrap());
+
+        let logged_in: LoggedIn = LoggedIn {
+            request_id: 8,
+            user_id: Uuid::from_u128(1),
+            account_token: Uuid::from_u128(2),
+            username: login.username,
+        };
+        let frame: Frame = frame_of(&logged_in);
+        assert_eq!(Some(8), frame.response_request_id());
+        assert_eq!(logged_in, LoggedIn::from_frame(&frame).unwrap());
+    }
+
+    #[test]
+    fn map_round_trip() {
+        let get_map: GetMap = GetMap {
+            request_id: 9,
+            game_id: Uuid::from_u128(1),
+        };
+        let frame: Frame = frame_of(&get_map);
+        assert_eq!(Some(9), frame.request_id());
+        assert_eq!(get_map, GetMap::from_frame(&frame).unwrap());
+
+        let game_map: GameMap = GameMap {
+            request_id: 9,
+            game_id: get_map.game_id,
+            map: Map::empty(MapSize::DEFAULT),
+        };
+        let frame: Frame = frame_of(&game_map);
+        assert_eq!(Some(9), frame.response_request_id());
+        assert_eq!(game_map, GameMap::from_frame(&frame).unwrap());
+    }
+
+    #[test]
+    fn operation_types() {
+        for op_code in 1..=GameUpdated::OP_CODE {
+            let op_type: OperationType = OperationType::from_op_code(&op_code).unwrap();
+            assert_eq!(op_code, op_type.op_code());
+            assert!(!(op_type.is_request() && op_type.is_response()));
+        }
+        assert_eq!("OperationType(GetMap)", OperationType::GetMap.to_string());
+        assert!(OperationType::Register.is_request() && !OperationType::Register.is_response());
+        assert!(OperationType::Resumed.is_response());
+        assert!(!OperationType::Heartbeat.is_request() && !OperationType::Heartbeat.is_response());
+        assert_eq!(Some(OP_CODE_SIZE), OperationType::Heartbeat.fixed_size());
+        assert!(OperationType::from_op_code(&0).is_err());
+        assert!(OperationType::from_op_code_or_unknown(&0).fixed_size().is_none());
+    }
+
+    #[test]
+    fn nested_frame() {
+        let inner: _PlaceholderDynamic = _PlaceholderDynamic {
+            string: String::from("replayed"),
+        };
+        let deliver: Deliver = Deliver {
+            sequence: 3,
+            frame: inner.as_bytes(),
+        };
+        let deliver: Deliver = Deliver::from_frame(&frame_of(&deliver)).unwrap();
+
+        let frame: Frame = Frame::from_bytes(deliver.frame).unwrap();
+        assert_eq!(inner, _PlaceholderDynamic::from_frame(&frame).unwrap());
+
+        let mut truncated: Vec<u8> = Heartbeat.as_bytes();
+        truncated.push(0);
+        assert!(Frame::from_bytes(truncated).is_err());
+        assert!(Frame::from_bytes(Vec::new()).is_err());
     }
 }
@@ -0,0 +1,181 @@
+//! Pairing of [Request] frames with their [crate::network::protocol::Response].
+//! Responses are intercepted by [crate::network::monitor::monitor_incoming_frames] before frames are routed.
+
+use crate::error::AppError;
+use crate::network::connection::{self, WriteBufferT};
+use crate::network::protocol::{Frame, Request, RequestId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time;
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Requests awaiting a response on a single connection.
+#[derive(Debug, Default)]
+pub struct PendingRequests {
+    last_request_id: AtomicU32,
+    pending: Mutex<HashMap<RequestId, oneshot::Sender<Frame>>>,
+}
+
+impl PendingRequests {
+    /// Assign a new request ID and begin waiting for its response.
+    pub fn register(&self) -> (RequestId, oneshot::Receiver<Frame>) {
+        let request_id: RequestId = loop {
+            let request_id: RequestId = self.last_request_id.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+            if request_id != 0 {
+                break request_id;
+            }
+        };
+
+        let (sender, receiver): (oneshot::Sender<Frame>, oneshot::Receiver<Frame>) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request_id, sender);
+        (request_id, receiver)
+    }
+
+    /// Deliver a response frame to its waiting request.
+    /// Returns the frame if it is not a response to a pending request, so that it may be routed normally.
+    pub fn resolve(&self, frame: Frame) -> Option<Frame> {
+        let Some(request_id) = frame.response_request_id() else {
+            return Some(frame);
+        };
+        let Some(sender) = self.pending.lock().unwrap().remove(&request_id) else {
+            return Some(frame);
+        };
+
+        // The requester may have timed out in the meantime, in which case the response is dropped
+        let _ = sender.send(frame);
+        None
+    }
+
+    pub fn cancel(&self, request_id: RequestId) {
+        self.pending.lock().unwrap().remove(&request_id);
+    }
+
+    /// Fail every pending request, e.g. once the connection has closed.
+    pub fn cancel_all(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Sends requests on a connection and awaits their responses.
+#[derive(Debug, Clone)]
+pub struct Requester {
+    pub write_buffer: WriteBufferT,
+    pub pending_requests: Arc<PendingRequests>,
+}
+
+impl Requester {
+    pub fn new(write_buffer: WriteBufferT, pending_requests: Arc<PendingRequests>) -> Self {
+        Requester {
+            write_buffer,
+            pending_requests,
+        }
+    }
+
+    /// Send the request with [DEFAULT_REQUEST_TIMEOUT]; see [Requester::request_timeout].
+    pub async fn request<T: Request>(&self, operation: T) -> Result<Frame, AppError> {
+        self.request_timeout(operation, DEFAULT_REQUEST_TIMEOUT).await
+    }
+
+    /// Assign the request an ID, send it, and wait for the response frame.
+    /// The response may be any operation type; the caller is expected to parse it.
+    pub async fn request_timeout<T: Request>(&self, mut operation: T, timeout: Duration) -> Result<Frame, AppError> {
+        let (request_id, receiver): (RequestId, oneshot::Receiver<Frame>) = self.pending_requests.register();
+        operation.set_request_id(request_id);
+
+        if let Err(e) = connection::send(&self.write_buffer, &operation).await {
+            self.pending_requests.cancel(request_id);
+            return Err(e);
+        }
+
+        match time::timeout(timeout, receiver).await {
+            Ok(Ok(frame)) => Ok(frame),
+            Ok(Err(_)) => Err(AppError::new(&format!(
+                "Connection closed awaiting response; [request_id: {}]",
+                request_id
+            ))),
+            Err(_) => {
+                self.pending_requests.cancel(request_id);
+                Err(AppError::new(&format!(
+                    "Timed out awaiting response; [request_id: {}] [timeout: {:?}]",
+                    request_id, timeout
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::frame_buffer::FrameBuffer;
+    use crate::network::protocol::{Acknowledgement, Heartbeat, Operation, Register};
+    use crate::network::ring_buffer::RingBuffer;
+    use crate::network::write_buffer::WriteBuffer;
+    use uuid::Uuid;
+
+    fn frame_of<T: Operation>(operation: &T) -> Frame {
+        let mut read_buffer: RingBuffer<u8> = connection::new_buffer();
+        read_buffer.push(&operation.as_bytes()).unwrap();
+        read_buffer.pop_frames().unwrap().remove(0)
+    }
+
+    #[test]
+    fn resolve() {
+        let pending: PendingRequests = PendingRequests::default();
+        let (first_id, mut first) = pending.register();
+        let (second_id, mut second) = pending.register();
+        assert_ne!(first_id, second_id);
+
+        let acknowledgement: Acknowledgement = Acknowledgement {
+            request_id: second_id,
+            op_code_acknowledged: Register::OP_CODE,
+        };
+        assert!(pending.resolve(frame_of(&acknowledgement)).is_none());
+        assert_eq!(
+            acknowledgement,
+            Acknowledgement::from_frame(&second.try_recv().unwrap()).unwrap()
+        );
+        assert!(first.try_recv().is_err());
+
+        // Not a response, or a response to nothing pending
+        assert!(pending.resolve(frame_of(&Heartbeat)).is_some());
+        assert!(pending.resolve(frame_of(&acknowledgement)).is_some());
+
+        pending.cancel_all();
+        assert!(pending.is_empty());
+        assert!(first.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let requester: Requester = Requester::new(
+            Arc::new(WriteBuffer::new(connection::new_buffer())),
+            Arc::new(PendingRequests::default()),
+        );
+        let register: Register = Register {
+            request_id: 0,
+            account_token: Uuid::nil(),
+        };
+
+        let request_r: Result<Frame, AppError> = requester.request_timeout(register, Duration::from_millis(10)).await;
+        assert!(request_r.is_err());
+        assert!(requester.pending_requests.is_empty());
+
+        // The request was written with its assigned ID
+        let frame: Frame = requester.write_buffer.pop_frames().await.unwrap().remove(0);
+        assert_ne!(0, Register::from_frame(&frame).unwrap().request_id);
+    }
+}
@@ -7,69 +7,94 @@ use std::mem::MaybeUninit;
 use std::ops::Index;
 use std::{mem, slice};
 
-pub struct RingBuffer<T, const N: usize>
+/// A heap-allocated circular buffer.
+/// The buffer begins at its initial capacity and may grow (by doubling) up to its maximum capacity.
+/// Once emptied, a grown buffer returns to its initial capacity on its next write.
+pub struct RingBuffer<T>
 where
     T: Copy,
 {
-    buffer: [MaybeUninit<T>; N],
+    buffer: Box<[MaybeUninit<T>]>,
+    initial_capacity: usize,
+    max_capacity: usize,
     read_pos: usize,
     write_pos: usize,
     empty: bool,
 }
 
-impl<T: Copy, const N: usize> Display for RingBuffer<T, N> {
+impl<T: Copy> Display for RingBuffer<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "(RingBuffer{{capacity: {}, read_pos: {}, used_space: {}}})",
-            N,
+            self.capacity(),
             self.read_pos,
             self.used_space()
         )
     }
 }
 
-impl<T: Copy, const N: usize> Debug for RingBuffer<T, N> {
+impl<T: Copy> Debug for RingBuffer<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         Display::fmt(self, f)
     }
 }
 
-impl<T, const N: usize> RingBuffer<T, N>
+impl<T> RingBuffer<T>
 where
     T: Copy,
 {
-    pub const fn new() -> Self {
+    /// Create a buffer which never grows beyond its initial capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_max_capacity(capacity, capacity)
+    }
+
+    pub fn with_max_capacity(capacity: usize, max_capacity: usize) -> Self {
+        assert!(capacity > 0, "RingBuffer capacity must be positive");
+        assert!(
+            capacity <= max_capacity,
+            "RingBuffer capacity must not exceed its maximum capacity"
+        );
         Self {
-            buffer: [MaybeUninit::<T>::uninit(); N],
+            buffer: Self::allocate(capacity),
+            initial_capacity: capacity,
+            max_capacity,
             read_pos: 0,
             write_pos: 0,
             empty: true,
         }
     }
 
+    fn allocate(capacity: usize) -> Box<[MaybeUninit<T>]> {
+        vec![MaybeUninit::<T>::uninit(); capacity].into_boxed_slice()
+    }
+
     pub const fn capacity(&self) -> usize {
-        N
+        self.buffer.len()
+    }
+
+    pub const fn max_capacity(&self) -> usize {
+        self.max_capacity
     }
 
     pub const fn used_space(&self) -> usize {
-        Self::used_space_internal(self.empty, self.read_pos, self.write_pos)
+        Self::used_space_internal(self.buffer.len(), self.empty, self.read_pos, self.write_pos)
     }
 
-    const fn used_space_internal(empty: bool, read_pos: usize, write_pos: usize) -> usize {
+    const fn used_space_internal(capacity: usize, empty: bool, read_pos: usize, write_pos: usize) -> usize {
         if empty {
             0
         } else if write_pos > read_pos {
             write_pos - read_pos
         } else if write_pos < read_pos {
-            N - (read_pos - write_pos)
+            capacity - (read_pos - write_pos)
         } else {
-            N
+            capacity
         }
     }
 
     pub const fn available_space(&self) -> usize {
-        N - self.used_space()
+        self.capacity() - self.used_space()
     }
 
     pub const fn is_empty(&self) -> bool {
@@ -77,46 +102,93 @@ where
     }
 
     pub const fn is_full(&self) -> bool {
-        self.used_space() == N
+        self.used_space() == self.capacity()
     }
 
-    pub fn push(&mut self, slice: &[T]) -> Result<(), AppError> {
-        if slice.len() > self.available_space() {
-            return Err(AppError::new("Not enough space in the buffer"));
+    /// Grow the buffer if necessary such that at least `additional` elements can be pushed without wrapping past the
+    /// read position. Fails if the maximum capacity would be exceeded.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), AppError> {
+        self.shrink_if_empty();
+        if additional <= self.available_space() {
+            return Ok(());
+        }
+
+        let required: usize = self.used_space() + additional;
+        if required > self.max_capacity {
+            return Err(AppError::new(&format!(
+                "Not enough space in the buffer; [required: {}] [max_capacity: {}]",
+                required, self.max_capacity
+            )));
         }
 
-        let slice_maybe: &[MaybeUninit<T>] = unsafe { mem::transmute(slice) };
+        let mut capacity: usize = self.capacity();
+        while capacity < required {
+            capacity = capacity.saturating_mul(2);
+        }
+        self.reallocate(capacity.min(self.max_capacity));
+        Ok(())
+    }
 
-        if (self.write_pos + slice.len()) < N {
+    /// Move the buffer's contents to the start of a new allocation of the given capacity.
+    fn reallocate(&mut self, capacity: usize) {
+        let used_space: usize = self.used_space();
+        debug_assert!(used_space <= capacity);
+
+        let mut buffer: Box<[MaybeUninit<T>]> = Self::allocate(capacity);
+        let (first, second): (&[MaybeUninit<T>], &[MaybeUninit<T>]) = if self.empty {
+            (&[], &[])
+        } else if self.write_pos > self.read_pos {
+            (&self.buffer[self.read_pos..self.write_pos], &[])
+        } else {
+            (&self.buffer[self.read_pos..], &self.buffer[..self.write_pos])
+        };
+        buffer[..first.len()].copy_from_slice(first);
+        buffer[first.len()..(first.len() + second.len())].copy_from_slice(second);
+
+        self.buffer = buffer;
+        self.read_pos = 0;
+        self.write_pos = used_space % capacity;
+    }
+
+    /// Push the slice, growing the buffer if necessary.
+    pub fn push(&mut self, slice: &[T]) -> Result<(), AppError> {
+        self.reserve(slice.len())?;
+
+        let capacity: usize = self.capacity();
+        let slice_maybe: &[MaybeUninit<T>] = unsafe { mem::transmute::<&[T], &[MaybeUninit<T>]>(slice) };
+
+        if (self.write_pos + slice.len()) < capacity {
             let target: &mut [MaybeUninit<T>] = &mut self.buffer[self.write_pos..(self.write_pos + slice.len())];
             target.copy_from_slice(slice_maybe);
         } else {
-            let a = &mut self.buffer[self.write_pos..N];
+            let a = &mut self.buffer[self.write_pos..capacity];
             let a_len = a.len();
             a.copy_from_slice(&slice_maybe[0..a_len]);
 
             let b = &mut self.buffer[0..(slice.len() - a_len)];
             let b_len = b.len();
-            b.copy_from_slice(&slice_maybe[0..b_len]);
+            b.copy_from_slice(&slice_maybe[a_len..]);
 
             assert_eq!(slice.len(), a_len + b_len);
         }
 
-        self.advance(slice.len());
-        Ok(())
+        self.advance(slice.len())
     }
 
     pub fn advance(&mut self, count: usize) -> Result<(), AppError> {
         if count > self.available_space() {
             return Err(AppError::new("Not enough space in the buffer"));
         }
+        if count == 0 {
+            return Ok(());
+        }
 
-        self.write_pos = (self.write_pos + count) % N;
+        self.write_pos = (self.write_pos + count) % self.capacity();
         self.empty = false;
         Ok(())
     }
 
-    pub fn peek<'a>(&'a self, count: usize) -> Result<RingBufferView<'a, T>, AppError> {
+    pub fn peek(&self, count: usize) -> Result<RingBufferView<'_, T>, AppError> {
         Self::peek_internal(&self.buffer, self.empty, self.read_pos, self.write_pos, count)
     }
 
@@ -127,7 +199,8 @@ where
         write_pos: usize,
         count: usize,
     ) -> Result<RingBufferView<'a, T>, AppError> {
-        if count > Self::used_space_internal(empty, read_pos, write_pos) {
+        let capacity: usize = buffer.len();
+        if count > Self::used_space_internal(capacity, empty, read_pos, write_pos) {
             return Err(AppError::new("Not enough content in the buffer"));
         }
 
@@ -140,11 +213,11 @@ where
             return Ok(view);
         }
 
-        if read_pos + count < N {
-            view.first = unsafe { mem::transmute(&buffer[read_pos..(read_pos + count)]) };
+        if read_pos + count < capacity {
+            view.first = unsafe { mem::transmute::<&[MaybeUninit<T>], &[T]>(&buffer[read_pos..(read_pos + count)]) };
         } else {
-            view.first = unsafe { mem::transmute(&buffer[read_pos..N]) };
-            view.second = unsafe { mem::transmute(&buffer[0..(count - view.first.len())]) };
+            view.first = unsafe { mem::transmute::<&[MaybeUninit<T>], &[T]>(&buffer[read_pos..capacity]) };
+            view.second = unsafe { mem::transmute::<&[MaybeUninit<T>], &[T]>(&buffer[0..(count - view.first.len())]) };
 
             assert_eq!(count, view.first.len() + view.second.len());
         }
@@ -152,20 +225,34 @@ where
         Ok(view)
     }
 
-    pub fn pop<'a>(&'a mut self, count: usize) -> Result<RingBufferView<'a, T>, AppError> {
-        let view: RingBufferView<T> =
-            Self::peek_internal(&self.buffer, self.empty, self.read_pos, self.write_pos, count)?;
+    pub fn pop(&mut self, count: usize) -> Result<RingBufferView<'_, T>, AppError> {
+        Self::peek_internal(&self.buffer, self.empty, self.read_pos, self.write_pos, count)?;
 
-        self.read_pos = (self.read_pos + count) % N;
-        if self.read_pos == self.write_pos {
-            self.empty = true;
+        let read_pos: usize = self.read_pos;
+        let write_pos: usize = self.write_pos;
+        let empty: bool = self.empty;
+        if count > 0 {
+            self.read_pos = (self.read_pos + count) % self.capacity();
+            if self.read_pos == self.write_pos {
+                self.empty = true;
+            }
+        }
+        Self::peek_internal(&self.buffer, empty, read_pos, write_pos, count)
+    }
+
+    /// Return a grown buffer to its initial capacity once it has been emptied.
+    fn shrink_if_empty(&mut self) {
+        if self.empty && self.capacity() > self.initial_capacity {
+            self.buffer = Self::allocate(self.initial_capacity);
+            self.read_pos = 0;
+            self.write_pos = 0;
         }
-        Ok(view)
     }
 
-    pub fn current_empty_slices_mut<'a>(&'a mut self) -> [&'a mut [MaybeUninit<T>]; 2] {
+    pub fn current_empty_slices_mut(&mut self) -> [&mut [MaybeUninit<T>]; 2] {
+        self.shrink_if_empty();
         let available_space: usize = self.available_space();
-        let slices: [&'a mut [MaybeUninit<T>]; 2] = {
+        let slices: [&mut [MaybeUninit<T>]; 2] = {
             if self.write_pos < self.read_pos {
                 [&mut self.buffer[self.write_pos..self.read_pos], &mut []]
             } else if self.write_pos > self.read_pos || self.empty {
@@ -185,8 +272,8 @@ where
     /// In order to convert to [IoSliceMut], [T] must be cast to [u8].
     /// Any data input via this returned mutable slice will be entered as raw bytes.
     /// Thus, usage of this function's return value is unsafe.
-    pub unsafe fn current_empty_slices_as_io_slice_mut<'a>(&'a mut self) -> [IoSliceMut<'a>; 2] {
-        let mut slices = self.current_empty_slices_mut();
+    pub unsafe fn current_empty_slices_as_io_slice_mut(&mut self) -> [IoSliceMut<'_>; 2] {
+        let slices = self.current_empty_slices_mut();
         [
             IoSliceMut::new(unsafe {
                 slice::from_raw_parts_mut(slices[0].as_mut_ptr() as *mut u8, slices[0].len() * size_of::<T>())
@@ -212,8 +299,8 @@ where
 {
     fn from(view: RingBufferView<'a, T>) -> Self {
         let mut target: Vec<T> = Vec::with_capacity(view.len());
-        target.extend_from_slice(&view.first);
-        target.extend_from_slice(&view.second);
+        target.extend_from_slice(view.first);
+        target.extend_from_slice(view.second);
         target
     }
 }
@@ -247,6 +334,10 @@ where
         self.first.len() + self.second.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn copy_to(&self, dest: &mut [T]) {
         dest[..self.first.len()].copy_from_slice(self.first);
         dest[self.first.len()..].copy_from_slice(self.second);
@@ -266,7 +357,7 @@ mod tests {
 
         #[test]
         fn push() {
-            let mut ring_buffer: RingBuffer<u8, 32> = RingBuffer::<u8, 32>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(32);
             assert!(ring_buffer.is_empty());
 
             let hello_world = b"hello world";
@@ -278,7 +369,7 @@ mod tests {
             assert!(result.is_ok());
 
             let result = ring_buffer.push(&vec![1; ring_buffer.capacity()]);
-            assert_eq!(true, result.is_err());
+            assert!(result.is_err());
 
             assert!(!ring_buffer.is_empty());
             assert_eq!(hello_world.len() + numbers.len(), ring_buffer.used_space());
@@ -294,7 +385,7 @@ mod tests {
 
         #[test]
         fn peek() {
-            let mut ring_buffer: RingBuffer<u8, 32> = RingBuffer::<u8, 32>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(32);
             let hello_world = b"hello world";
             ring_buffer.push(hello_world).unwrap();
 
@@ -311,7 +402,7 @@ mod tests {
 
         #[test]
         fn pop() {
-            let mut ring_buffer: RingBuffer<u8, 32> = RingBuffer::<u8, 32>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(32);
             let hello_world = b"hello world";
             ring_buffer.push(hello_world).unwrap();
 
@@ -329,7 +420,7 @@ mod tests {
 
         #[test]
         fn wrap() {
-            let mut ring_buffer: RingBuffer<u8, 4> = RingBuffer::<u8, 4>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(4);
             ring_buffer.push(&[1, 2, 3, 4]).unwrap();
 
             assert_eq!(4, ring_buffer.capacity());
@@ -351,14 +442,14 @@ mod tests {
 
         #[test]
         fn current_empty_slices_mut() {
-            let mut ring_buffer: RingBuffer<u8, 4> = RingBuffer::<u8, 4>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(4);
 
             let slices: [&mut [MaybeUninit<u8>]; 2] = ring_buffer.current_empty_slices_mut();
-            assert!(slices[0].len() == 4 && slices[1].len() == 0);
+            assert!(slices[0].len() == 4 && slices[1].is_empty());
 
             ring_buffer.push(&[1, 2, 3]).unwrap();
             let slices: [&mut [MaybeUninit<u8>]; 2] = ring_buffer.current_empty_slices_mut();
-            assert!(slices[0].len() == 1 && slices[1].len() == 0);
+            assert!(slices[0].len() == 1 && slices[1].is_empty());
 
             ring_buffer.pop(2).unwrap();
             let slices: [&mut [MaybeUninit<u8>]; 2] = ring_buffer.current_empty_slices_mut();
@@ -366,7 +457,49 @@ mod tests {
 
             ring_buffer.push(&[4, 5, 6]).unwrap();
             let slices: [&mut [MaybeUninit<u8>]; 2] = ring_buffer.current_empty_slices_mut();
-            assert!(slices[0].len() == 0 && slices[1].len() == 0);
+            assert!(slices[0].is_empty() && slices[1].is_empty());
+        }
+
+        #[test]
+        fn push_wrapped() {
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(4);
+            ring_buffer.push(&[1, 2, 3]).unwrap();
+            let _ = ring_buffer.pop(3).unwrap();
+
+            ring_buffer.push(&[4, 5, 6]).unwrap();
+            assert_eq!(vec![4, 5, 6], Vec::from(ring_buffer.pop(3).unwrap()));
+        }
+
+        #[test]
+        fn grow() {
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::with_max_capacity(4, 16);
+            ring_buffer.push(&[1, 2, 3]).unwrap();
+            let _ = ring_buffer.pop(2).unwrap();
+            ring_buffer.push(&[4, 5]).unwrap();
+
+            // Wrapped content is preserved in order across reallocation
+            ring_buffer.push(&[6, 7, 8, 9, 10]).unwrap();
+            assert_eq!(8, ring_buffer.capacity());
+            assert_eq!(vec![3, 4, 5, 6, 7, 8, 9, 10], Vec::from(ring_buffer.peek(8).unwrap()));
+
+            assert!(ring_buffer.push(&[0; 9]).is_err());
+            assert!(ring_buffer.reserve(8).is_ok());
+            assert_eq!(16, ring_buffer.capacity());
+            assert!(ring_buffer.reserve(9).is_err());
+        }
+
+        #[test]
+        fn shrink() {
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::with_max_capacity(4, 64);
+            ring_buffer.push(&[1; 40]).unwrap();
+            assert_eq!(64, ring_buffer.capacity());
+
+            let _ = ring_buffer.pop(40).unwrap();
+            assert_eq!(64, ring_buffer.capacity());
+
+            ring_buffer.push(&[2]).unwrap();
+            assert_eq!(4, ring_buffer.capacity());
+            assert_eq!(vec![2], Vec::from(ring_buffer.pop(1).unwrap()));
         }
     }
 
@@ -375,7 +508,7 @@ mod tests {
 
         #[test]
         fn index() {
-            let mut ring_buffer: RingBuffer<u8, 32> = RingBuffer::<u8, 32>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(32);
             ring_buffer.push(&[1, 2]).unwrap();
 
             let view = ring_buffer.pop(2).unwrap();
@@ -386,11 +519,11 @@ mod tests {
         #[test]
         #[should_panic]
         fn index_out_of_bounds() {
-            let mut ring_buffer: RingBuffer<u8, 32> = RingBuffer::<u8, 32>::new();
+            let mut ring_buffer: RingBuffer<u8> = RingBuffer::new(32);
             ring_buffer.push(&[1, 2, 3]).unwrap();
             let view = ring_buffer.pop(2).unwrap();
 
-            view[3];
+            let _ = view[3];
         }
     }
 }
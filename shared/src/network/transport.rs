@@ -0,0 +1,164 @@
+//! Byte streams which a [crate::network::connection::Connection] can run over.
+//! TCP is used between processes; the in-memory duplex lets a client and server run in a single process, e.g. in tests.
+
+use std::fmt::{self, Debug, Display};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadHalf, WriteHalf};
+use tokio::net::{TcpStream, tcp};
+
+/// Buffer size of each direction of an in-memory duplex
+pub const MEMORY_BUFFER_SIZE: usize = 64 * 1024;
+
+pub trait TransportRead: AsyncRead + Debug + Send + Unpin {}
+
+impl<T: AsyncRead + Debug + Send + Unpin> TransportRead for T {}
+
+pub trait TransportWrite: AsyncWrite + Debug + Send + Unpin {}
+
+impl<T: AsyncWrite + Debug + Send + Unpin> TransportWrite for T {}
+
+/// A bidirectional byte stream which can be split into independently owned halves.
+pub trait Transport: Send + 'static {
+    type Read: TransportRead + 'static;
+    type Write: TransportWrite + 'static;
+
+    fn into_split(self) -> (Self::Read, Self::Write);
+}
+
+impl Transport for TcpStream {
+    type Read = tcp::OwnedReadHalf;
+    type Write = tcp::OwnedWriteHalf;
+
+    fn into_split(self) -> (Self::Read, Self::Write) {
+        TcpStream::into_split(self)
+    }
+}
+
+#[cfg(unix)]
+impl Transport for tokio::net::UnixStream {
+    type Read = tokio::net::unix::OwnedReadHalf;
+    type Write = tokio::net::unix::OwnedWriteHalf;
+
+    fn into_split(self) -> (Self::Read, Self::Write) {
+        tokio::net::UnixStream::into_split(self)
+    }
+}
+
+/// One end of an in-process pipe; see [memory_pair].
+#[derive(Debug)]
+pub struct MemoryTransport {
+    /// Identifies this end of the pipe
+    pub id: u64,
+    /// Identifies the other end of the pipe
+    pub peer_id: u64,
+    stream: DuplexStream,
+}
+
+impl MemoryTransport {
+    pub fn peer_addr(&self) -> PeerAddr {
+        PeerAddr::Memory(self.peer_id)
+    }
+}
+
+impl Transport for MemoryTransport {
+    type Read = ReadHalf<DuplexStream>;
+    type Write = WriteHalf<DuplexStream>;
+
+    fn into_split(self) -> (Self::Read, Self::Write) {
+        tokio::io::split(self.stream)
+    }
+}
+
+/// Create both ends of an in-process pipe, each buffering up to [MEMORY_BUFFER_SIZE] bytes.
+pub fn memory_pair() -> (MemoryTransport, MemoryTransport) {
+    static LAST_ID: AtomicU64 = AtomicU64::new(0);
+
+    let a_id: u64 = LAST_ID.fetch_add(1, Ordering::Relaxed) + 1;
+    let b_id: u64 = LAST_ID.fetch_add(1, Ordering::Relaxed) + 1;
+    let (a, b): (DuplexStream, DuplexStream) = tokio::io::duplex(MEMORY_BUFFER_SIZE);
+    (
+        MemoryTransport {
+            id: a_id,
+            peer_id: b_id,
+            stream: a,
+        },
+        MemoryTransport {
+            id: b_id,
+            peer_id: a_id,
+            stream: b,
+        },
+    )
+}
+
+/// The remote end of a transport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    /// Unix domain socket peers are usually unnamed
+    Unix(Option<String>),
+    Memory(u64),
+}
+
+impl Display for PeerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerAddr::Tcp(socket_addr) => write!(f, "{}", socket_addr),
+            PeerAddr::Unix(Some(path)) => write!(f, "unix:{}", path),
+            PeerAddr::Unix(None) => write!(f, "unix:(unnamed)"),
+            PeerAddr::Memory(id) => write!(f, "memory:{}", id),
+        }
+    }
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(socket_addr: SocketAddr) -> Self {
+        PeerAddr::Tcp(socket_addr)
+    }
+}
+
+#[cfg(unix)]
+impl From<tokio::net::unix::SocketAddr> for PeerAddr {
+    fn from(socket_addr: tokio::net::unix::SocketAddr) -> Self {
+        PeerAddr::Unix(socket_addr.as_pathname().map(|path| path.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::connection::{self, BytesRead, Connection};
+    use crate::network::frame_buffer::FrameBuffer;
+    use crate::network::protocol::{Frame, Heartbeat, OperationType};
+
+    async fn exchange_heartbeat<T: Transport>(a: T, b: T) {
+        let Connection { mut writer, .. } = Connection::new(a, PeerAddr::Memory(0));
+        let Connection { mut reader, .. } = Connection::new(b, PeerAddr::Memory(0));
+
+        connection::send(&writer.buffer, &Heartbeat).await.unwrap();
+        for frame in writer.buffer.pop_frames().await.unwrap() {
+            writer.write_frame(&frame).await.unwrap();
+        }
+
+        assert!(matches!(reader.read_chunk().await.unwrap(), BytesRead::Some(_)));
+        let frames: Vec<Frame> = reader.read_buffer.pop_frames().unwrap();
+        assert!(matches!(frames[0].head.op_type, OperationType::Heartbeat));
+
+        drop(writer);
+        assert!(matches!(reader.read_chunk().await.unwrap(), BytesRead::ReadClosed));
+    }
+
+    #[tokio::test]
+    async fn memory() {
+        let (a, b): (MemoryTransport, MemoryTransport) = memory_pair();
+        assert_eq!(PeerAddr::Memory(b.id), a.peer_addr());
+        exchange_heartbeat(a, b).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix() {
+        let (a, b) = tokio::net::UnixStream::pair().unwrap();
+        exchange_heartbeat(a, b).await;
+    }
+}
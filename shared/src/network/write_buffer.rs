@@ -0,0 +1,184 @@
+//! Outgoing bytes shared between the tasks which push frames and the task which writes them to the network.
+
+use crate::error::{AppError, AppErrorStatic};
+use crate::network::frame_buffer::FrameBuffer;
+use crate::network::protocol::Frame;
+use crate::network::ring_buffer::RingBuffer;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::futures::Notified;
+use tokio::sync::{Notify, RwLock, RwLockWriteGuard};
+
+/// Pushers wait once this many bytes are buffered, so that a slow peer holds back its senders long before the buffer
+/// reaches its maximum capacity. A frame larger than this is still accepted into an empty buffer.
+pub const BACKPRESSURE_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub struct WriteBuffer {
+    buffer: RwLock<RingBuffer<u8>>,
+    /// Wakes the writer once bytes are pushed
+    pushed: Notify,
+    /// Wakes pushers waiting for space once frames are popped
+    popped: Notify,
+    /// Set once the writer has stopped, after which nothing more is written
+    closed: AtomicBool,
+}
+
+impl WriteBuffer {
+    pub fn new(buffer: RingBuffer<u8>) -> Self {
+        WriteBuffer {
+            buffer: RwLock::new(buffer),
+            pushed: Notify::new(),
+            popped: Notify::new(),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Push bytes, waiting for the writer to make space if [BACKPRESSURE_SIZE] bytes are buffered.
+    /// Fails if the bytes could never fit, or once the buffer is closed.
+    pub async fn push(&self, bytes: &[u8]) -> Result<(), AppError> {
+        loop {
+            // Register for the notification before checking for space, so that a pop in between is not missed
+            let mut popped: Pin<&mut Notified> = std::pin::pin!(self.popped.notified());
+            popped.as_mut().enable();
+            self.check_open()?;
+
+            let mut buffer_g: RwLockWriteGuard<RingBuffer<u8>> = self.buffer.write().await;
+            if bytes.len() > buffer_g.max_capacity() {
+                return Err(AppError::new(&format!(
+                    "Not enough space in the buffer; [length: {}] [max_capacity: {}]",
+                    bytes.len(),
+                    buffer_g.max_capacity()
+                )));
+            }
+            let used_space: usize = buffer_g.used_space();
+            let fits: bool = used_space + bytes.len() <= buffer_g.max_capacity();
+            if fits && (used_space == 0 || used_space + bytes.len() <= BACKPRESSURE_SIZE) {
+                buffer_g.push(bytes)?;
+                drop(buffer_g);
+                self.pushed.notify_one();
+                return Ok(());
+            }
+            drop(buffer_g);
+
+            popped.await;
+        }
+    }
+
+    /// Push bytes, failing immediately if the buffer is full.
+    pub async fn try_push(&self, bytes: &[u8]) -> Result<(), AppError> {
+        self.check_open()?;
+        self.buffer.write().await.push(bytes)?;
+        self.pushed.notify_one();
+        Ok(())
+    }
+
+    /// Pop every complete frame, waking any pushers waiting for space.
+    pub async fn pop_frames(&self) -> Result<Vec<Frame>, AppErrorStatic> {
+        let frames: Vec<Frame> = self.buffer.write().await.pop_frames()?;
+        if !frames.is_empty() {
+            self.popped.notify_waiters();
+        }
+        Ok(frames)
+    }
+
+    /// Wait until frames have been pushed since the last call.
+    /// Intended for a single writer task.
+    pub async fn pushed(&self) {
+        self.pushed.notified().await;
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.buffer.read().await.is_empty()
+    }
+
+    /// Fail every pending and future push, e.g. once the connection's writer has stopped.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.popped.notify_waiters();
+    }
+
+    fn check_open(&self) -> Result<(), AppError> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(AppError::new("Write buffer is closed"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::protocol::{_PlaceholderDynamic, Heartbeat, Operation};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time;
+
+    #[tokio::test]
+    async fn push_waits_for_space() {
+        let write_buffer: Arc<WriteBuffer> = Arc::new(WriteBuffer::new(RingBuffer::new(2)));
+        write_buffer.push(&Heartbeat.as_bytes()).await.unwrap();
+        write_buffer.push(&Heartbeat.as_bytes()).await.unwrap();
+        assert!(write_buffer.try_push(&Heartbeat.as_bytes()).await.is_err());
+
+        let pusher = tokio::spawn({
+            let write_buffer: Arc<WriteBuffer> = write_buffer.clone();
+            async move { write_buffer.push(&Heartbeat.as_bytes()).await.is_ok() }
+        });
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!pusher.is_finished());
+
+        assert_eq!(2, write_buffer.pop_frames().await.unwrap().len());
+        assert!(time::timeout(Duration::from_secs(1), pusher).await.unwrap().unwrap());
+        assert_eq!(1, write_buffer.pop_frames().await.unwrap().len());
+
+        assert!(write_buffer.push(&[0; 3]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pushed_wakes_writer() {
+        let write_buffer: WriteBuffer = WriteBuffer::new(RingBuffer::new(16));
+        write_buffer.push(&Heartbeat.as_bytes()).await.unwrap();
+        // The notification is retained until the writer waits
+        time::timeout(Duration::from_secs(1), write_buffer.pushed()).await.unwrap();
+        assert!(time::timeout(Duration::from_millis(10), write_buffer.pushed()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn push_waits_past_backpressure_size() {
+        let write_buffer: Arc<WriteBuffer> = Arc::new(WriteBuffer::new(RingBuffer::with_max_capacity(
+            16,
+            4 * BACKPRESSURE_SIZE,
+        )));
+        // A frame larger than the backpressure size is accepted into an empty buffer
+        let large: _PlaceholderDynamic = _PlaceholderDynamic {
+            string: "x".repeat(BACKPRESSURE_SIZE),
+        };
+        write_buffer.push(&large.as_bytes()).await.unwrap();
+
+        let pusher = tokio::spawn({
+            let write_buffer: Arc<WriteBuffer> = write_buffer.clone();
+            async move { write_buffer.push(&Heartbeat.as_bytes()).await.is_ok() }
+        });
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!pusher.is_finished());
+        assert_eq!(1, write_buffer.pop_frames().await.unwrap().len());
+        assert!(time::timeout(Duration::from_secs(1), pusher).await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn close_fails_pushers() {
+        let write_buffer: Arc<WriteBuffer> = Arc::new(WriteBuffer::new(RingBuffer::new(1)));
+        write_buffer.push(&Heartbeat.as_bytes()).await.unwrap();
+        let pusher = tokio::spawn({
+            let write_buffer: Arc<WriteBuffer> = write_buffer.clone();
+            async move { write_buffer.push(&Heartbeat.as_bytes()).await.is_err() }
+        });
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!pusher.is_finished());
+
+        write_buffer.close();
+        assert!(time::timeout(Duration::from_secs(1), pusher).await.unwrap().unwrap());
+        assert!(write_buffer.try_push(&Heartbeat.as_bytes()).await.is_err());
+    }
+}
@@ -1,4 +1,9 @@
+//! Randomness. The free functions draw from the operating system and are never reproducible.
+//! Anything which must be reproduced from a game's seed, such as map generation, draws from a [SeededRng] instead;
+//! see [GameSeed::rng].
+
 use rand::{RngCore, rngs::ThreadRng};
+use std::fmt::{self, Display};
 use uuid::Uuid;
 
 pub fn random_uuid() -> Uuid {
@@ -9,3 +14,177 @@ pub fn random_uuid() -> Uuid {
     assert_eq!(random_bytes.len(), 16);
     Uuid::from_slice(&random_bytes[..]).unwrap() // Err is only returned for non 16 byte length
 }
+
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut random_bytes: [u8; N] = [0; N];
+    rand::rng().fill_bytes(&mut random_bytes);
+    random_bytes
+}
+
+/// Uniformly distributed in [0, 1)
+pub fn random_unit() -> f64 {
+    rand::random::<f64>()
+}
+
+/// Identifies the independent streams drawn from a game's seed, so that adding draws to one subsystem does not
+/// change the sequence seen by another.
+/// The discriminants are part of every saved game's derivation and must never change.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RngStream {
+    Map = 1,
+    Players = 2,
+    Combat = 3,
+}
+
+/// The root of all of a game's randomness, chosen when the game is created
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GameSeed(pub u64);
+
+impl GameSeed {
+    pub fn random() -> GameSeed {
+        GameSeed(rand::rng().next_u64())
+    }
+
+    /// The generator for one subsystem of the game
+    pub fn rng(&self, stream: RngStream) -> SeededRng {
+        SeededRng::new(mix(self.0 ^ mix(stream as u64)))
+    }
+}
+
+impl Display for GameSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A portable pseudorandom generator: xoshiro256** seeded through SplitMix64.
+/// The same seed yields the same sequence on every platform and build, so the algorithm and every derived method
+/// must never change. Not suitable for secrets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    state: [u64; 4],
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        let mut splitmix: u64 = seed;
+        let mut state: [u64; 4] = [0; 4];
+        for word in &mut state {
+            splitmix = splitmix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            *word = mix(splitmix);
+        }
+        SeededRng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result: u64 = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t: u64 = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// The upper half of the next u64, as its upper bits are the strongest
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniformly distributed in [0, 1), with 53 bits of precision
+    pub fn next_unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Uniformly distributed in [0, bound), without modulo bias.
+    /// Panics if the bound is 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+        // Values below the threshold would make the smallest results more likely
+        let threshold: u64 = bound.wrapping_neg() % bound;
+        loop {
+            let value: u64 = self.next_u64();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j: usize = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// The SplitMix64 finalizer
+const fn mix(value: u64) -> u64 {
+    let mut z: u64 = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reference_sequence() {
+        // Published xoshiro256** output when seeded through SplitMix64; guards portability across builds
+        let mut rng: SeededRng = SeededRng::new(0);
+        assert_eq!(0x99ec_5f36_cb75_f2b4, rng.next_u64());
+        assert_eq!(0xbf6e_1f78_4956_452a, rng.next_u64());
+        assert_eq!(0x1a5f_849d_4933_e6e0, rng.next_u64());
+    }
+
+    #[test]
+    fn streams_are_reproducible_and_independent() {
+        let seed: GameSeed = GameSeed(0x5eed);
+        let map: Vec<u64> = (0..8).map(|_| seed.rng(RngStream::Map).next_u64()).collect();
+        assert!(map.iter().all(|value| *value == map[0]));
+
+        let mut first: SeededRng = seed.rng(RngStream::Map);
+        let mut second: SeededRng = seed.rng(RngStream::Map);
+        let mut players: SeededRng = seed.rng(RngStream::Players);
+        let mut other_game: SeededRng = GameSeed(0x5eee).rng(RngStream::Map);
+        for _ in 0..100 {
+            let value: u64 = first.next_u64();
+            assert_eq!(value, second.next_u64());
+            assert_ne!(value, players.next_u64());
+            assert_ne!(value, other_game.next_u64());
+        }
+    }
+
+    #[test]
+    fn bounded_values() {
+        let mut rng: SeededRng = SeededRng::new(7);
+        let mut seen: [bool; 6] = [false; 6];
+        for _ in 0..1000 {
+            let unit: f64 = rng.next_unit();
+            assert!((0.0..1.0).contains(&unit));
+            seen[rng.below(6) as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(0, rng.below(1));
+        assert!(rng.below(u64::MAX) < u64::MAX);
+    }
+
+    #[test]
+    fn shuffle_permutes() {
+        let mut items: Vec<u32> = (0..50).collect();
+        SeededRng::new(1).shuffle(&mut items);
+        assert_ne!((0..50).collect::<Vec<u32>>(), items);
+
+        let mut again: Vec<u32> = (0..50).collect();
+        SeededRng::new(1).shuffle(&mut again);
+        assert_eq!(items, again);
+        items.sort();
+        assert_eq!((0..50).collect::<Vec<u32>>(), items);
+    }
+}